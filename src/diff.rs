@@ -0,0 +1,223 @@
+//! Utilities for comparing directory trees and files.
+//!
+//! This module answers "what changed between these two trees?", for example before and
+//! after running a codemod over a project. With the `line-diff` feature,
+//! [`diff_files_lines`] also shows what changed within a file.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//! use xio::diff::diff_directories;
+//!
+//! async fn report() -> std::io::Result<()> {
+//!     let diff = diff_directories(Path::new("before"), Path::new("after"), true).await?;
+//!     for path in &diff.differing {
+//!         println!("changed: {}", path.display());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::is_excluded_by_default;
+use log::debug;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use walkdir::WalkDir;
+
+/// Differences between two directory trees, as paths relative to their roots
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirDiff {
+    /// Files present only in the first tree
+    pub only_in_a: Vec<PathBuf>,
+    /// Files present only in the second tree
+    pub only_in_b: Vec<PathBuf>,
+    /// Files present in both trees whose contents differ
+    pub differing: Vec<PathBuf>,
+}
+
+impl DirDiff {
+    /// Returns true if both trees contain the same files with the same contents
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.differing.is_empty()
+    }
+}
+
+/// Compares two directory trees file by file.
+///
+/// Both trees are walked using the crate's default filters (hidden entries, `.git` and
+/// `target` directories are skipped) and their files are matched by relative path.
+/// Files present in both trees are first compared by size; when `compare_contents` is
+/// set, files of equal size are additionally compared byte by byte.
+///
+/// # Arguments
+///
+/// * `a` - The root of the first tree
+/// * `b` - The root of the second tree
+/// * `compare_contents` - Whether to compare the contents of files with equal sizes
+///
+/// # Returns
+///
+/// Returns a [`DirDiff`] with sorted relative paths.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - Either root cannot be read
+/// - The metadata of a file cannot be read
+/// - A file cannot be opened or read while comparing contents
+pub async fn diff_directories(a: &Path, b: &Path, compare_contents: bool) -> io::Result<DirDiff> {
+    let files_a = collect_file_sizes(a)?;
+    let mut files_b = collect_file_sizes(b)?;
+    let mut diff = DirDiff::default();
+
+    for (relative, size_a) in files_a {
+        match files_b.remove(&relative) {
+            None => diff.only_in_a.push(relative),
+            Some(size_b) if size_a != size_b => diff.differing.push(relative),
+            Some(_) => {
+                if compare_contents && !files_equal(&a.join(&relative), &b.join(&relative)).await? {
+                    diff.differing.push(relative);
+                }
+            }
+        }
+    }
+    diff.only_in_b = files_b.into_keys().collect();
+
+    debug!(
+        "Compared {} and {}: {} only in a, {} only in b, {} differing",
+        a.display(),
+        b.display(),
+        diff.only_in_a.len(),
+        diff.only_in_b.len(),
+        diff.differing.len()
+    );
+    Ok(diff)
+}
+
+/// Collects the sizes of all files under `root`, keyed by their path relative to `root`
+fn collect_file_sizes(root: &Path) -> io::Result<BTreeMap<PathBuf, u64>> {
+    let mut files = BTreeMap::new();
+    for entry in WalkDir::new(root).into_iter().filter_entry(|e| !is_excluded_by_default(e)) {
+        let entry = entry?;
+        if entry.file_type().is_file()
+            && let Ok(relative) = entry.path().strip_prefix(root)
+        {
+            files.insert(relative.to_path_buf(), entry.metadata()?.len());
+        }
+    }
+    Ok(files)
+}
+
+/// Compares the contents of two files in fixed-size chunks
+async fn files_equal(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut file_a = File::open(a).await?;
+    let mut file_b = File::open(b).await?;
+    let mut buf_a = vec![0; 64 * 1024];
+    let mut buf_b = vec![0; 64 * 1024];
+
+    loop {
+        let read_a = read_full(&mut file_a, &mut buf_a).await?;
+        let read_b = read_full(&mut file_b, &mut buf_b).await?;
+        if read_a != read_b || buf_a[..read_a] != buf_b[..read_b] {
+            return Ok(false);
+        }
+        if read_a == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads until `buf` is full or the end of the file is reached
+async fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let read = file.read(&mut buf[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+/// One line of a line-level diff produced by [`diff_files_lines`]
+#[cfg(feature = "line-diff")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineChange {
+    /// A line present only in the second file
+    Added(String),
+    /// A line present only in the first file
+    Removed(String),
+    /// A line present in both files
+    Unchanged(String),
+}
+
+/// Computes a line-level diff between two text files.
+///
+/// The files are compared with the Myers diff algorithm, the same one behind `diff`
+/// and `git diff`, and the result lists every line of both files in order: lines only
+/// in `a` as [`LineChange::Removed`], lines only in `b` as [`LineChange::Added`], and
+/// shared lines as [`LineChange::Unchanged`]. Lines are compared without their line
+/// endings, so `\n` versus `\r\n` and a missing final newline do not count as changes.
+/// Both files are read into memory, and the diff itself needs memory proportional to
+/// their combined size, which is fine for source files and logs of many megabytes.
+///
+/// # Arguments
+///
+/// * `a` - The first (old) file
+/// * `b` - The second (new) file
+///
+/// # Returns
+///
+/// Returns the lines of both files as a sequence of changes.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if either file cannot be read or is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::diff::{diff_files_lines, LineChange};
+///
+/// async fn show_changes() -> std::io::Result<()> {
+///     for change in diff_files_lines(Path::new("before.rs"), Path::new("after.rs")).await? {
+///         match change {
+///             LineChange::Added(line) => println!("+{line}"),
+///             LineChange::Removed(line) => println!("-{line}"),
+///             LineChange::Unchanged(_) => {}
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "line-diff")]
+pub async fn diff_files_lines(a: &Path, b: &Path) -> io::Result<Vec<LineChange>> {
+    let old = tokio::fs::read_to_string(a).await?;
+    let new = tokio::fs::read_to_string(b).await?;
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let diff = similar::TextDiff::configure()
+        .algorithm(similar::Algorithm::Myers)
+        .diff_slices(&old_lines, &new_lines);
+
+    let changes: Vec<LineChange> = diff
+        .iter_all_changes()
+        .map(|change| {
+            let line = change.value().to_string();
+            match change.tag() {
+                similar::ChangeTag::Insert => LineChange::Added(line),
+                similar::ChangeTag::Delete => LineChange::Removed(line),
+                similar::ChangeTag::Equal => LineChange::Unchanged(line),
+            }
+        })
+        .collect();
+    debug!("Diffed {} and {}: {} lines", a.display(), b.display(), changes.len());
+    Ok(changes)
+}
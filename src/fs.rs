@@ -18,17 +18,25 @@
 //! }
 //! ```
 
-use std::path::Path;
+use crate::read::{FileTooLarge, ReadOptions};
+use log::{debug, info};
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Component, Path, PathBuf};
 
 /// Checks if a file has a specific extension.
 ///
 /// This function compares the file extension case-sensitively with the provided extension.
-/// The extension comparison is done without the leading dot.
+/// A single leading dot in `extension` is ignored, so `"txt"` and `".txt"` are equivalent.
+/// An extension with several parts, such as `"tar.gz"`, is matched against the end of the
+/// file name, as described for [`compound_extension`]: `archive.tar.gz` has both the
+/// extension `"gz"` and the extension `"tar.gz"`.
 ///
 /// # Arguments
 ///
 /// * `path` - The path to check
-/// * `extension` - The extension to check for, without the leading dot (e.g., "txt" not ".txt")
+/// * `extension` - The extension to check for, with or without the leading dot ("txt" or ".txt")
 ///
 /// # Returns
 ///
@@ -43,12 +51,91 @@ use std::path::Path;
 /// use xio::fs::has_extension;
 ///
 /// assert!(has_extension(Path::new("document.pdf"), "pdf"));
+/// assert!(has_extension(Path::new("document.pdf"), ".pdf"));
 /// assert!(!has_extension(Path::new("document"), "pdf"));
 /// assert!(!has_extension(Path::new(".hidden"), "hidden")); // Hidden files return false
+/// assert!(has_extension(Path::new("archive.tar.gz"), "tar.gz"));
 /// ```
 #[must_use]
 pub fn has_extension(path: &Path, extension: &str) -> bool {
-    path.extension().is_some_and(|ext| ext == extension)
+    let extension = strip_extension_dot(extension);
+    if !extension.contains('.') {
+        return path.extension().is_some_and(|ext| ext == extension);
+    }
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .and_then(|name| name.strip_suffix(extension))
+        .and_then(|stem| stem.strip_suffix('.'))
+        .is_some_and(|stem| !stem.is_empty())
+}
+
+/// Returns the extension of a file, without the dot.
+///
+/// This is the last part of the file name after a dot, as given by [`Path::extension`],
+/// as a string slice: `archive.tar.gz` has the extension `"gz"`, and hidden files such
+/// as `.bashrc` have none.
+///
+/// # Arguments
+///
+/// * `path` - The path whose extension to return
+///
+/// # Returns
+///
+/// Returns the extension, or `None` if the file has none or it is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::file_extension;
+///
+/// assert_eq!(file_extension(Path::new("src/main.rs")), Some("rs"));
+/// assert_eq!(file_extension(Path::new("archive.tar.gz")), Some("gz"));
+/// assert_eq!(file_extension(Path::new(".bashrc")), None);
+/// ```
+#[must_use]
+pub fn file_extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(OsStr::to_str)
+}
+
+/// Returns the full, possibly multi-part extension of a file, without the leading dot.
+///
+/// The compound extension is everything after the first dot of the file name, so
+/// `archive.tar.gz` has the compound extension `"tar.gz"`. Leading dots of hidden files
+/// are not counted: `.bashrc` has no extension and `.backup.tar.gz` has `"tar.gz"`.
+/// Since every dot counts, a version number in the name becomes part of the result
+/// (`lib-1.2.tar.gz` gives `"2.tar.gz"`); use [`has_extension`] to test for a known
+/// compound extension instead.
+///
+/// # Arguments
+///
+/// * `path` - The path whose compound extension to return
+///
+/// # Returns
+///
+/// Returns the compound extension, or `None` if the file name has no dot after its
+/// first character, ends with a dot, or is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::compound_extension;
+///
+/// assert_eq!(compound_extension(Path::new("archive.tar.gz")).as_deref(), Some("tar.gz"));
+/// assert_eq!(compound_extension(Path::new("notes.txt")).as_deref(), Some("txt"));
+/// assert_eq!(compound_extension(Path::new("Makefile")), None);
+/// ```
+#[must_use]
+pub fn compound_extension(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let (_, extension) = name.trim_start_matches('.').split_once('.')?;
+    (!extension.is_empty()).then(|| extension.to_string())
+}
+
+/// Removes a single leading dot from an extension argument, so `".txt"` means `"txt"`.
+pub(crate) fn strip_extension_dot(extension: &str) -> &str {
+    extension.strip_prefix('.').unwrap_or(extension)
 }
 
 /// Recursively finds all files with a specific extension in a directory and its subdirectories.
@@ -59,13 +146,16 @@ pub fn has_extension(path: &Path, extension: &str) -> bool {
 /// # Arguments
 ///
 /// * `dir` - The root directory to start the search from
-/// * `extension` - The extension to filter files by, without the leading dot (e.g., "txt" not ".txt")
+/// * `extension` - The extension to filter files by, with or without the leading dot ("txt" or ".txt")
 ///
 /// # Returns
 ///
 /// Returns an iterator that yields `PathBuf` instances for each matching file found.
 /// The iterator automatically handles any permissions errors or inaccessible directories
 /// by silently skipping them.
+/// The paths start with `dir` as given, the same [`PathMode::AsWalked`](crate::PathMode)
+/// form the walkers use by default; [`PathMode::apply`](crate::PathMode::apply) converts
+/// them to another form.
 ///
 /// # Examples
 ///
@@ -92,10 +182,430 @@ pub fn get_files_with_extension<'a>(
         .map(|e| e.path().to_path_buf())
 }
 
+/// Counts the files with a specific extension in a directory and its subdirectories.
+///
+/// The count is taken during the walk without building a `PathBuf` for each file,
+/// which makes this a cheap way to compute a total for progress reporting before a
+/// long operation. The walk applies the crate's default filter, so hidden files and
+/// directories, git directories and target directories are not counted, matching
+/// what [`walk_directory`](crate::walk_directory) processes.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the search from
+/// * `extension` - The extension to count, with or without the leading dot ("txt" or ".txt")
+///
+/// # Returns
+///
+/// Returns the number of matching files. Unreadable entries are silently skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::count_files_with_extension;
+///
+/// let total = count_files_with_extension(Path::new("./images"), "jpg");
+/// println!("Found {total} matching files");
+/// ```
+#[must_use]
+pub fn count_files_with_extension(dir: &Path, extension: &str) -> usize {
+    file_entries_under(dir)
+        .filter(|e| has_extension(Path::new(e.file_name()), extension))
+        .count()
+}
+
+/// The key under which [`extension_stats`] counts files without an extension
+pub const NO_EXTENSION: &str = "(none)";
+
+/// Counts the files in a directory tree and sums their sizes, grouped by extension.
+///
+/// The tree is walked once, applying the crate's default filter like
+/// [`count_files_with_extension`], and each file's size is taken from the metadata of
+/// its directory entry. Extensions are lowercased, so `photo.JPG` and `photo.jpg` land
+/// in the same bucket, and files without an extension are counted under
+/// [`NO_EXTENSION`]. This is cheaper than one pass per extension when an overview of
+/// the whole tree is wanted.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+///
+/// # Returns
+///
+/// Returns a map from lowercased extension (without the dot) to the number of files
+/// and their total size in bytes.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if `dir` itself cannot be read. Unreadable entries below it,
+/// and files whose metadata cannot be read, are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::extension_stats;
+///
+/// fn print_overview() -> std::io::Result<()> {
+///     for (extension, (count, bytes)) in extension_stats(Path::new("."))? {
+///         println!("{extension}: {count} files, {bytes} bytes");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn extension_stats(dir: &Path) -> io::Result<HashMap<String, (usize, u64)>> {
+    let mut stats: HashMap<String, (usize, u64)> = HashMap::new();
+    let walker = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| !crate::is_excluded_by_default(e));
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.depth() == 0 => return Err(e.into()),
+            Err(e) => {
+                debug!("Skipping unreadable entry: {e}");
+                continue;
+            }
+        };
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let len = match entry.metadata() {
+            Ok(metadata) => metadata.len(),
+            Err(e) => {
+                debug!("Skipping file without readable metadata: {e}");
+                continue;
+            }
+        };
+        let extension = Path::new(entry.file_name())
+            .extension()
+            .map_or_else(|| NO_EXTENSION.to_string(), |ext| ext.to_string_lossy().to_lowercase());
+        let (count, bytes) = stats.entry(extension).or_default();
+        *count += 1;
+        *bytes += len;
+    }
+    Ok(stats)
+}
+
+/// Recursively finds all files with a specific file name in a directory and its subdirectories.
+///
+/// The full file name, including its extension, must match exactly (case-sensitively),
+/// which makes this the tool for finding every `Cargo.toml` in a workspace. The walk
+/// applies the crate's default filter, so hidden files and directories, git directories
+/// and target directories are skipped; as a consequence, hidden names such as `.env`
+/// are never found.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the search from
+/// * `name` - The file name to look for (e.g., "Cargo.toml")
+///
+/// # Returns
+///
+/// Returns an iterator yielding the path of each matching file. Unreadable entries are
+/// silently skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::find_files_by_name;
+///
+/// for manifest in find_files_by_name(Path::new("."), "Cargo.toml") {
+///     println!("Found crate: {}", manifest.display());
+/// }
+/// ```
+pub fn find_files_by_name<'a>(dir: &'a Path, name: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+    files_under(dir).filter(move |path| path.file_name().is_some_and(|n| n == name))
+}
+
+/// Recursively finds all files with a specific stem in a directory and its subdirectories.
+///
+/// The stem is the file name without its final extension, so searching for `README`
+/// finds `README.md` and `README.txt` as well as a plain `README`. The comparison is
+/// case-sensitive and uses the same default filter as [`find_files_by_name`].
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the search from
+/// * `stem` - The file stem to look for (e.g., "README")
+///
+/// # Returns
+///
+/// Returns an iterator yielding the path of each matching file. Unreadable entries are
+/// silently skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::find_files_by_stem;
+///
+/// let readmes: Vec<_> = find_files_by_stem(Path::new("."), "README").collect();
+/// println!("Found {} READMEs", readmes.len());
+/// ```
+pub fn find_files_by_stem<'a>(dir: &'a Path, stem: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+    files_under(dir).filter(move |path| path.file_stem().is_some_and(|s| s == stem))
+}
+
+/// Finds the first file under a directory satisfying a predicate.
+///
+/// Unlike collecting a search and taking its first element, traversal stops as soon as
+/// a match is found, so locating a single file in a large tree stays cheap. Directories
+/// are walked depth-first in file-name order, which makes the result deterministic, and
+/// the crate's default filter applies: hidden entries, git directories and target
+/// directories are neither returned nor descended into.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the search from
+/// * `predicate` - Returns `true` for the file being searched for
+///
+/// # Returns
+///
+/// Returns `Some` with the path of the first matching file, or `None` if no file matches.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if `dir` itself cannot be read. Unreadable entries below it
+/// are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::find_first;
+///
+/// fn find_schema() -> std::io::Result<()> {
+///     let schema = find_first(Path::new("."), |path| path.ends_with("schema.sql"))?;
+///     if let Some(schema) = schema {
+///         println!("Schema found at {}", schema.display());
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn find_first<P>(dir: &Path, predicate: P) -> io::Result<Option<PathBuf>>
+where
+    P: Fn(&Path) -> bool,
+{
+    let walker = walkdir::WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !crate::is_excluded_by_default(e));
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.depth() == 0 => return Err(e.into()),
+            Err(e) => {
+                debug!("Skipping unreadable entry: {e}");
+                continue;
+            }
+        };
+        if entry.file_type().is_file() && predicate(entry.path()) {
+            return Ok(Some(entry.into_path()));
+        }
+    }
+    Ok(None)
+}
+
+/// Recursively finds symlinks whose target no longer exists.
+///
+/// The tree is walked without following symlinks, applying the crate's default filter
+/// (hidden entries, git directories and target directories are skipped). Each symlink
+/// found is resolved, and it is reported if its target is missing, including a target
+/// that is itself a dangling link. Symlinks whose target cannot be checked, for example
+/// because of permissions, are skipped.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the search from
+///
+/// # Returns
+///
+/// Returns the paths of the dangling symlinks, ordered by file name within each
+/// directory.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if `dir` itself cannot be read. Unreadable entries below it
+/// are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::find_broken_symlinks;
+///
+/// fn report_dangling() -> std::io::Result<()> {
+///     for link in find_broken_symlinks(Path::new("./data"))? {
+///         println!("Dangling: {}", link.display());
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn find_broken_symlinks(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let walker = walkdir::WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !crate::is_excluded_by_default(e));
+    let mut broken = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.depth() == 0 => return Err(e.into()),
+            Err(e) => {
+                debug!("Skipping unreadable entry: {e}");
+                continue;
+            }
+        };
+        if !entry.path_is_symlink() {
+            continue;
+        }
+        match entry.path().try_exists() {
+            Ok(true) => {}
+            Ok(false) => broken.push(entry.into_path()),
+            Err(e) => debug!("Skipping symlink that cannot be resolved: {}: {e}", entry.path().display()),
+        }
+    }
+    Ok(broken)
+}
+
+/// Recursively removes symlinks whose target no longer exists.
+///
+/// The dangling symlinks are found as by [`find_broken_symlinks`]. Only the links are
+/// removed; nothing they pointed to is touched. With `dry_run` set, nothing is removed
+/// and the returned list shows what would be.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the search from
+/// * `dry_run` - Whether to only report the dangling symlinks without removing them
+///
+/// # Returns
+///
+/// Returns the paths of the dangling symlinks that were (or would be) removed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if `dir` itself cannot be read or a symlink cannot be removed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::remove_broken_symlinks;
+///
+/// fn preview_cleanup() -> std::io::Result<()> {
+///     let dangling = remove_broken_symlinks(Path::new("./data"), true)?;
+///     println!("Would remove {} dangling symlinks", dangling.len());
+///     Ok(())
+/// }
+/// ```
+pub fn remove_broken_symlinks(dir: &Path, dry_run: bool) -> io::Result<Vec<PathBuf>> {
+    let broken = find_broken_symlinks(dir)?;
+    for link in &broken {
+        if dry_run {
+            info!("Would remove: {}", link.display());
+        } else {
+            std::fs::remove_file(link)?;
+            info!("Removed: {}", link.display());
+        }
+    }
+    Ok(broken)
+}
+
+/// Searches upward from a directory for a marker file or directory.
+///
+/// Starting at `start` and moving to each parent in turn, the way cargo looks for
+/// `Cargo.toml` and git looks for `.git`, this returns the first directory containing an
+/// entry named `marker`. The marker may be a file or a directory.
+///
+/// A relative `start` is resolved against the current directory and normalized
+/// lexically (see [`normalize_path`]) before ascending, so the search can continue past
+/// the current directory; symlinks are not resolved.
+///
+/// # Arguments
+///
+/// * `start` - The directory to start searching from
+/// * `marker` - The name of the entry marking the directory being searched for
+///
+/// # Returns
+///
+/// Returns `Some` with the directory containing `marker`, or `None` if the filesystem
+/// root is reached without finding it.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::find_up;
+///
+/// if let Some(root) = find_up(Path::new("."), "Cargo.toml") {
+///     println!("Crate root: {}", root.display());
+/// }
+/// ```
+#[must_use]
+pub fn find_up(start: &Path, marker: &str) -> Option<PathBuf> {
+    find_up_any(start, &[marker]).map(|(dir, _)| dir)
+}
+
+/// Searches upward from a directory for any of several markers.
+///
+/// This behaves like [`find_up`], but each directory is checked for every marker in
+/// order. The nearest directory containing any of the markers is returned; within that
+/// directory, earlier markers take precedence when reporting which one matched.
+///
+/// # Arguments
+///
+/// * `start` - The directory to start searching from
+/// * `markers` - The names of the entries to look for
+///
+/// # Returns
+///
+/// Returns `Some` with the directory and the marker found in it, or `None` if the
+/// filesystem root is reached without finding any marker.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::find_up_any;
+///
+/// if let Some((root, marker)) = find_up_any(Path::new("."), &[".git", ".hg"]) {
+///     println!("Repository root ({marker}): {}", root.display());
+/// }
+/// ```
+#[must_use]
+pub fn find_up_any<'a>(start: &Path, markers: &[&'a str]) -> Option<(PathBuf, &'a str)> {
+    let start = normalize_path(&std::path::absolute(start).ok()?);
+    start.ancestors().find_map(|dir| {
+        markers
+            .iter()
+            .find(|marker| dir.join(marker).symlink_metadata().is_ok())
+            .map(|marker| (dir.to_path_buf(), *marker))
+    })
+}
+
+/// Walks `dir` with the crate's default filter, yielding the path of every file.
+fn files_under(dir: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    file_entries_under(dir).map(walkdir::DirEntry::into_path)
+}
+
+/// Walks `dir` with the crate's default filter, yielding the entry of every file.
+fn file_entries_under(dir: &Path) -> impl Iterator<Item = walkdir::DirEntry> + '_ {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| !crate::is_excluded_by_default(e))
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+}
+
 /// Reads a file's contents into a String with comprehensive error handling.
 ///
 /// This function provides a convenient wrapper around `std::fs::read_to_string`
 /// with improved error messages that include the file path in case of failure.
+/// It blocks the calling thread; in async code use [`read_to_string_async`].
 ///
 /// # Arguments
 ///
@@ -129,3 +639,1003 @@ pub fn read_to_string(path: &Path) -> anyhow::Result<String> {
     std::fs::read_to_string(path)
         .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", path.display(), e))
 }
+
+/// Reads a file's contents into a String without blocking, with the path in errors.
+///
+/// This is the async counterpart of [`read_to_string`], built on
+/// `tokio::fs::read_to_string`, so it can be used inside async code without stalling
+/// the runtime. Errors carry the same message naming the file path, unlike
+/// [`read_file_content`](crate::read_file_content), which returns the bare
+/// `io::Error`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+///
+/// # Returns
+///
+/// Returns the file contents as a `String`.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The file does not exist
+/// * The process lacks permissions to read the file
+/// * The file contains invalid UTF-8 data
+/// * Any other I/O error occurs during reading
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::read_to_string_async;
+///
+/// async fn load_config() -> xio::anyhow::Result<String> {
+///     read_to_string_async(Path::new("config.toml")).await
+/// }
+/// ```
+pub async fn read_to_string_async(path: &Path) -> anyhow::Result<String> {
+    tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", path.display(), e))
+}
+
+/// Reads a file's contents into a String, using the given [`ReadOptions`].
+///
+/// This is the configurable counterpart of [`read_to_string`]: the size limit and byte
+/// order mark handling of `options` are applied, and errors carry the file path.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+/// * `options` - Options controlling the size limit and byte order mark handling
+///
+/// # Returns
+///
+/// Returns the file contents as a `String`.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The file does not exist or cannot be read
+/// * The file is larger than the limit; the error can be downcast to [`FileTooLarge`]
+/// * The file contains invalid UTF-8 data
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::read_to_string_with;
+/// use xio::{FileTooLarge, ReadOptions};
+///
+/// let options = ReadOptions::new().with_max_bytes(1024);
+/// match read_to_string_with(Path::new("notes.txt"), &options) {
+///     Ok(contents) => println!("{contents}"),
+///     Err(e) if e.is::<FileTooLarge>() => eprintln!("Skipping large file: {e}"),
+///     Err(e) => eprintln!("Error reading file: {e}"),
+/// }
+/// ```
+pub fn read_to_string_with(path: &Path, options: &ReadOptions) -> anyhow::Result<String> {
+    let read = || -> io::Result<String> {
+        let file = std::fs::File::open(path)?;
+        options.check_size(path, file.metadata()?.len())?;
+        let mut bytes = Vec::new();
+        io::Read::read_to_end(&mut io::Read::take(file, options.read_limit()), &mut bytes)?;
+        options.decode(path, bytes)
+    };
+    read().map_err(|e| match e.get_ref().and_then(|inner| inner.downcast_ref::<FileTooLarge>()) {
+        Some(too_large) => anyhow::Error::new(too_large.clone()),
+        None => anyhow::anyhow!("Failed to read file {}: {}", path.display(), e),
+    })
+}
+
+/// Determines whether two paths refer to the same underlying file.
+///
+/// The comparison uses the file's identity rather than its path: the device and
+/// inode numbers on Unix and the volume serial number and file index on Windows. Two
+/// different paths therefore compare equal when one is a hard link or symlink to the
+/// other, or when they differ only in spelling (`./a` and `a`). This is the check to
+/// run before copying a file onto itself, which would truncate it.
+///
+/// # Arguments
+///
+/// * `a` - The first path
+/// * `b` - The second path
+///
+/// # Returns
+///
+/// Returns `true` if both paths resolve to the same file, `false` otherwise.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * Either path does not exist
+/// * The process lacks permissions to open either path
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::is_same_file;
+///
+/// fn safe_copy(src: &Path, dst: &Path) -> std::io::Result<()> {
+///     if dst.exists() && is_same_file(src, dst)? {
+///         return Ok(());
+///     }
+///     std::fs::copy(src, dst).map(|_| ())
+/// }
+/// ```
+pub fn is_same_file(a: &Path, b: &Path) -> io::Result<bool> {
+    same_file::is_same_file(a, b)
+}
+
+/// Computes the path of `path` relative to `base`.
+///
+/// This strips the `base` prefix from `path` component-wise, which is what is needed
+/// when mirroring a directory structure into another location.
+///
+/// # Arguments
+///
+/// * `path` - The path to make relative
+/// * `base` - The base directory the result should be relative to
+///
+/// # Returns
+///
+/// Returns `Some` with the relative path if `path` is `base` or lies under it (an empty
+/// path when both are equal), or `None` if `path` is not under `base`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use xio::fs::relative_path;
+///
+/// assert_eq!(
+///     relative_path(Path::new("/data/images/cat.png"), Path::new("/data")),
+///     Some(PathBuf::from("images/cat.png"))
+/// );
+/// assert_eq!(relative_path(Path::new("/other/cat.png"), Path::new("/data")), None);
+/// ```
+#[must_use]
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    path.strip_prefix(base).ok().map(Path::to_path_buf)
+}
+
+/// Turns an arbitrary string into a file name that is valid on common operating systems.
+///
+/// This is [`sanitize_filename_with`] using `_` as the replacement character and a
+/// maximum length of 255 bytes, the limit of most filesystems.
+///
+/// # Arguments
+///
+/// * `name` - The string to derive a file name from, such as user input or a URL segment
+///
+/// # Returns
+///
+/// Returns a non-empty file name without path separators or reserved characters.
+///
+/// # Examples
+///
+/// ```
+/// use xio::fs::sanitize_filename;
+///
+/// assert_eq!(sanitize_filename("report: 2024/05?.txt"), "report_ 2024_05_.txt");
+/// assert_eq!(sanitize_filename("con.txt"), "con_.txt");
+/// ```
+#[must_use]
+pub fn sanitize_filename(name: &str) -> String {
+    sanitize_filename_with(name, '_', 255)
+}
+
+/// Turns an arbitrary string into a file name, with a custom replacement and length limit.
+///
+/// The result is safe to use as a single path component on Windows, macOS and Linux:
+/// - The characters `/ \ : * ? " < > |` and control characters are replaced by
+///   `replacement`, or removed if `replacement` is itself one of them
+/// - Trailing dots and spaces, which Windows strips silently, are removed
+/// - The name is truncated to at most `max_len` bytes on a character boundary, keeping
+///   the extension when it fits
+/// - Reserved Windows device names (`CON`, `PRN`, `AUX`, `NUL`, `COM1`-`COM9` and
+///   `LPT1`-`LPT9`, in any case and with any extension) get `replacement` appended to
+///   their stem, replacing the stem's last characters if the name would exceed `max_len`
+/// - An empty result, `.` or `..` becomes `replacement` (or `_` if it was removed)
+///
+/// # Arguments
+///
+/// * `name` - The string to derive a file name from
+/// * `replacement` - The character substituted for illegal characters
+/// * `max_len` - The maximum length of the result in bytes (at least 1)
+///
+/// # Returns
+///
+/// Returns a non-empty file name without path separators or reserved characters.
+///
+/// # Examples
+///
+/// ```
+/// use xio::fs::sanitize_filename_with;
+///
+/// assert_eq!(sanitize_filename_with("a<b>c", '-', 255), "a-b-c");
+/// assert_eq!(sanitize_filename_with("very long name.txt", '_', 8), "very.txt");
+/// ```
+#[must_use]
+pub fn sanitize_filename_with(name: &str, replacement: char, max_len: usize) -> String {
+    let replacement = Some(replacement).filter(|&c| !is_illegal_filename_char(c));
+    let mut sanitized: String = name
+        .chars()
+        .filter_map(|c| if is_illegal_filename_char(c) { replacement } else { Some(c) })
+        .collect();
+    let fallback = replacement.unwrap_or('_');
+
+    let max_len = max_len.max(1);
+    if sanitized.len() > max_len {
+        sanitized = truncate_filename(sanitized.trim_end_matches(['.', ' ']), max_len);
+    }
+    let mut sanitized = sanitized.trim_end_matches(['.', ' ']).to_string();
+
+    let stem_len = sanitized.find('.').unwrap_or(sanitized.len());
+    if is_reserved_windows_name(&sanitized[..stem_len]) {
+        // Reserved stems are ASCII, so dropping bytes from their end keeps within `max_len`
+        let excess = (sanitized.len() + fallback.len_utf8()).saturating_sub(max_len);
+        let keep = stem_len.saturating_sub(excess);
+        sanitized.replace_range(keep..stem_len, fallback.encode_utf8(&mut [0; 4]));
+    }
+    if sanitized.is_empty() {
+        sanitized = fallback.to_string();
+    }
+    sanitized
+}
+
+/// Returns true for characters that are not allowed in file names on some platform
+fn is_illegal_filename_char(c: char) -> bool {
+    matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+}
+
+/// Returns true if `stem` is a device name that Windows reserves regardless of extension
+fn is_reserved_windows_name(stem: &str) -> bool {
+    let upper = stem.to_ascii_uppercase();
+    matches!(upper.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || ((upper.starts_with("COM") || upper.starts_with("LPT"))
+            && upper.len() == 4
+            && upper.as_bytes()[3].is_ascii_digit()
+            && upper.as_bytes()[3] != b'0')
+}
+
+/// Shortens `name` to at most `max_len` bytes, keeping its extension if that leaves room
+/// for at least one character of the stem
+fn truncate_filename(name: &str, max_len: usize) -> String {
+    let floor = |s: &str, len: usize| {
+        let mut end = len.min(s.len());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        end
+    };
+    match name.rfind('.').filter(|&dot| dot > 0 && name.len() - dot < max_len) {
+        Some(dot) => {
+            let extension = &name[dot..];
+            let stem = &name[..floor(&name[..dot], max_len - extension.len())];
+            format!("{}{extension}", stem.trim_end_matches(['.', ' ']))
+        }
+        None => name[..floor(name, max_len)].to_string(),
+    }
+}
+
+/// Lexically normalizes a path by resolving `.` and `..` components.
+///
+/// Unlike [`std::fs::canonicalize`], this never touches the filesystem, so it works
+/// for paths that do not exist yet. It behaves like Go's `path.Clean`: `.` components
+/// are dropped, each `..` removes the preceding normal component, `..` at the root of
+/// an absolute path is discarded, and leading `..` components of a relative path are
+/// kept. An empty result becomes `.`.
+///
+/// Because symlinks are not resolved, `a/link/..` normalizes to `a` even if `link`
+/// points elsewhere.
+///
+/// # Arguments
+///
+/// * `path` - The path to normalize
+///
+/// # Returns
+///
+/// Returns the normalized path.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use xio::fs::normalize_path;
+///
+/// assert_eq!(normalize_path(Path::new("./dir/../other/file.txt")), PathBuf::from("other/file.txt"));
+/// assert_eq!(normalize_path(Path::new("/../etc/./hosts")), PathBuf::from("/etc/hosts"));
+/// assert_eq!(normalize_path(Path::new("../a/../../b")), PathBuf::from("../../b"));
+/// assert_eq!(normalize_path(Path::new("a/..")), PathBuf::from("."));
+/// ```
+#[must_use]
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    // Number of normal components that a following `..` may pop
+    let mut depth = 0usize;
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => normalized.push(component),
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                normalized.pop();
+                depth -= 1;
+            }
+            Component::ParentDir => {
+                if !normalized.has_root() {
+                    normalized.push("..");
+                }
+            }
+            Component::Normal(name) => {
+                normalized.push(name);
+                depth += 1;
+            }
+        }
+    }
+
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Ensures that a directory exists, creating it and any missing parents.
+///
+/// This is the asynchronous counterpart of `mkdir -p`: it succeeds if the directory
+/// already exists and creates the whole directory tree otherwise.
+///
+/// # Arguments
+///
+/// * `path` - The directory that should exist
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the directory exists.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * `path` exists but is not a directory
+/// * The process lacks permissions to create the directory
+/// * Any other I/O error occurs while creating the directory tree
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::ensure_dir;
+///
+/// async fn prepare_output() -> std::io::Result<()> {
+///     ensure_dir(Path::new("output/reports")).await
+/// }
+/// ```
+pub async fn ensure_dir(path: &Path) -> io::Result<()> {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) if metadata.is_dir() => Ok(()),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a directory", path.display()),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            debug!("Creating directory: {}", path.display());
+            tokio::fs::create_dir_all(path).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Creates a file if it is missing, or updates its timestamps to now if it exists.
+///
+/// This is the equivalent of the `touch` command, useful for build-system sentinel files
+/// and cache invalidation. The content of an existing file is left untouched.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to touch
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the file exists with its access and modification times set.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The parent directory does not exist
+/// * The process lacks permissions to create or write to the file
+/// * `path` refers to a directory
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::touch;
+///
+/// async fn mark_built() -> std::io::Result<()> {
+///     touch(Path::new("target/.built")).await
+/// }
+/// ```
+pub async fn touch(path: &Path) -> io::Result<()> {
+    touch_with_time(path, std::time::SystemTime::now()).await
+}
+
+/// Creates a file if it is missing and sets its timestamps to a specific time.
+///
+/// This behaves like [`touch`], but sets both the access and modification times to
+/// `time` instead of the current time.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to touch
+/// * `time` - The access and modification time to set
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the file exists with its access and modification times set.
+///
+/// # Errors
+///
+/// This function will return an error in the same situations as [`touch`].
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::time::{Duration, SystemTime};
+/// use xio::fs::touch_with_time;
+///
+/// async fn backdate() -> std::io::Result<()> {
+///     let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+///     touch_with_time(Path::new("cache.stamp"), an_hour_ago).await
+/// }
+/// ```
+pub async fn touch_with_time(path: &Path, time: std::time::SystemTime) -> io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        file.set_times(std::fs::FileTimes::new().set_accessed(time).set_modified(time))?;
+        debug!("Touched {}", path.display());
+        Ok(())
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Checks whether a file is empty (zero bytes long).
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to check
+///
+/// # Returns
+///
+/// Returns `true` if the file's length is zero, `false` otherwise.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The file does not exist or its metadata cannot be read
+/// * `path` refers to a directory
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::is_empty_file;
+///
+/// async fn check() -> std::io::Result<()> {
+///     if is_empty_file(Path::new("output.txt")).await? {
+///         println!("Nothing was written");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn is_empty_file(path: &Path) -> io::Result<bool> {
+    let metadata = tokio::fs::metadata(path).await?;
+    if metadata.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is a directory", path.display()),
+        ));
+    }
+    Ok(metadata.len() == 0)
+}
+
+/// Checks whether a directory has no entries.
+///
+/// Only the first entry of the directory is read, so this is cheap even for
+/// directories containing many files.
+///
+/// # Arguments
+///
+/// * `path` - The path of the directory to check
+///
+/// # Returns
+///
+/// Returns `true` if the directory contains no entries, `false` otherwise.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The directory does not exist
+/// * `path` is not a directory
+/// * The process lacks permissions to read the directory
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::is_empty_dir;
+///
+/// async fn prune() -> std::io::Result<()> {
+///     let dir = Path::new("output");
+///     if is_empty_dir(dir).await? {
+///         tokio::fs::remove_dir(dir).await?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn is_empty_dir(path: &Path) -> io::Result<bool> {
+    let mut entries = tokio::fs::read_dir(path).await?;
+    Ok(entries.next_entry().await?.is_none())
+}
+
+/// Checks asynchronously whether a path exists.
+///
+/// This is the non-blocking counterpart of [`Path::exists`], safe to call from async
+/// code on slow filesystems. Symlinks are followed, so a broken symlink does not exist.
+/// Like `Path::exists`, errors such as missing permissions are treated as "does not
+/// exist"; use [`is_file`] or [`is_dir`] when that distinction matters.
+///
+/// # Arguments
+///
+/// * `path` - The path to check
+///
+/// # Returns
+///
+/// Returns `true` if the path exists and could be inspected, `false` otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::path_exists;
+///
+/// async fn has_config() -> bool {
+///     path_exists(Path::new("config.toml")).await
+/// }
+/// ```
+pub async fn path_exists(path: &Path) -> bool {
+    tokio::fs::try_exists(path).await.unwrap_or(false)
+}
+
+/// Checks asynchronously whether a path is a regular file.
+///
+/// Symlinks are followed, so a symlink to a file counts as a file.
+///
+/// # Arguments
+///
+/// * `path` - The path to check
+///
+/// # Returns
+///
+/// Returns `true` if the path is a file, or `false` if it is something else or does not
+/// exist.
+///
+/// # Errors
+///
+/// This function will return an error if the path's metadata cannot be read for a
+/// reason other than the path not existing, for example missing permissions.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::is_file;
+///
+/// async fn check() -> std::io::Result<()> {
+///     if is_file(Path::new("Cargo.toml")).await? {
+///         println!("Found the manifest");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn is_file(path: &Path) -> io::Result<bool> {
+    metadata_if_exists(path).await.map(|metadata| metadata.is_some_and(|m| m.is_file()))
+}
+
+/// Checks asynchronously whether a path is a directory.
+///
+/// Symlinks are followed, so a symlink to a directory counts as a directory.
+///
+/// # Arguments
+///
+/// * `path` - The path to check
+///
+/// # Returns
+///
+/// Returns `true` if the path is a directory, or `false` if it is something else or does
+/// not exist.
+///
+/// # Errors
+///
+/// This function will return an error if the path's metadata cannot be read for a
+/// reason other than the path not existing, for example missing permissions.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::is_dir;
+///
+/// async fn check() -> std::io::Result<()> {
+///     if !is_dir(Path::new("output")).await? {
+///         tokio::fs::create_dir_all("output").await?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn is_dir(path: &Path) -> io::Result<bool> {
+    metadata_if_exists(path).await.map(|metadata| metadata.is_some_and(|m| m.is_dir()))
+}
+
+/// Reads the metadata of `path`, returning `None` if it does not exist.
+async fn metadata_if_exists(path: &Path) -> io::Result<Option<std::fs::Metadata>> {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// An immediate child of a directory, as returned by [`read_dir_entries`]
+///
+/// The type flags come from the directory listing and describe the entry itself, so
+/// for a symlink only `is_symlink` is set, whatever the link points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntryInfo {
+    /// The file name of the entry
+    pub name: OsString,
+    /// The directory joined with the file name
+    pub path: PathBuf,
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+    /// Whether the entry is a regular file
+    pub is_file: bool,
+    /// Whether the entry is a symbolic link
+    pub is_symlink: bool,
+}
+
+/// Lists the immediate children of a directory with their file types.
+///
+/// This reads a single level, without recursing, and takes each entry's type from the
+/// directory listing, which on most platforms needs no extra `stat` call. Entries are
+/// sorted by name.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to list
+///
+/// # Returns
+///
+/// Returns the entries of `dir`, including hidden ones.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The directory does not exist or is not a directory
+/// * The process lacks permissions to read the directory
+/// * The type of an entry cannot be determined
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::read_dir_entries;
+///
+/// async fn list() -> std::io::Result<()> {
+///     for entry in read_dir_entries(Path::new(".")).await? {
+///         let kind = if entry.is_dir { "dir" } else { "file" };
+///         println!("{kind}: {}", entry.name.to_string_lossy());
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn read_dir_entries(dir: &Path) -> io::Result<Vec<DirEntryInfo>> {
+    collect_dir_entries(dir, false).await
+}
+
+/// Lists the immediate children of a directory like [`read_dir_entries`], applying the default filter.
+///
+/// Entries the crate's walkers skip by default (hidden entries, `.git` and `target`)
+/// are left out.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to list
+///
+/// # Returns
+///
+/// Returns the entries of `dir` that pass the default filter, sorted by name.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * The directory does not exist or is not a directory
+/// * The process lacks permissions to read the directory
+/// * The type of an entry cannot be determined
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::read_dir_entries_filtered;
+///
+/// async fn subdirectories() -> std::io::Result<Vec<std::path::PathBuf>> {
+///     let entries = read_dir_entries_filtered(Path::new(".")).await?;
+///     Ok(entries.into_iter().filter(|e| e.is_dir).map(|e| e.path).collect())
+/// }
+/// ```
+pub async fn read_dir_entries_filtered(dir: &Path) -> io::Result<Vec<DirEntryInfo>> {
+    collect_dir_entries(dir, true).await
+}
+
+/// Reads the entries of `dir`, optionally skipping those excluded by default.
+async fn collect_dir_entries(dir: &Path, apply_default_filter: bool) -> io::Result<Vec<DirEntryInfo>> {
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = read_dir.next_entry().await? {
+        let name = entry.file_name();
+        if apply_default_filter && crate::is_excluded_name(&name) {
+            debug!("Skipping excluded entry: {}", entry.path().display());
+            continue;
+        }
+        let file_type = entry.file_type().await?;
+        entries.push(DirEntryInfo {
+            name,
+            path: entry.path(),
+            is_dir: file_type.is_dir(),
+            is_file: file_type.is_file(),
+            is_symlink: file_type.is_symlink(),
+        });
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Returns `desired` if nothing exists there, or the first free variant with a counter.
+///
+/// The counter is inserted before the extension in parentheses, so `report.txt`
+/// becomes `report (1).txt`, then `report (2).txt`, and so on; a name without an
+/// extension such as `notes` becomes `notes (1)`. Only the last extension is kept
+/// after the counter (`archive.tar (1).gz`). Broken symlinks count as taken.
+///
+/// The check and the later use of the path are separate steps, so another process
+/// could take the name in between; create the file with `create_new` when that matters.
+///
+/// # Arguments
+///
+/// * `desired` - The path that would be used if it were free
+///
+/// # Returns
+///
+/// Returns a path that did not exist at the time of the check.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * `desired` has no file name, such as `/` or a path ending in `..`
+/// * Checking whether a candidate exists fails
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::unique_path;
+///
+/// async fn save_report(content: &str) -> std::io::Result<()> {
+///     let path = unique_path(Path::new("out/report.txt")).await?;
+///     tokio::fs::write(path, content).await
+/// }
+/// ```
+pub async fn unique_path(desired: &Path) -> io::Result<PathBuf> {
+    if !path_is_taken(desired).await? {
+        return Ok(desired.to_path_buf());
+    }
+    let stem = desired.file_stem().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} has no file name", desired.display()),
+        )
+    })?;
+    for counter in 1.. {
+        let mut name = stem.to_os_string();
+        name.push(format!(" ({counter})"));
+        if let Some(extension) = desired.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        let candidate = desired.with_file_name(name);
+        if !path_is_taken(&candidate).await? {
+            return Ok(candidate);
+        }
+    }
+    unreachable!("ran out of counters looking for a free name")
+}
+
+/// Returns true if anything, including a broken symlink, exists at `path`
+async fn path_is_taken(path: &Path) -> io::Result<bool> {
+    match tokio::fs::symlink_metadata(path).await {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// How to resolve a file that already exists at the destination of a move or copy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Leave both files in place and carry on
+    #[default]
+    Skip,
+    /// Replace the destination file
+    Overwrite,
+    /// Stop with an `AlreadyExists` error
+    Error,
+    /// Keep both, moving or copying to a free name chosen by [`unique_path`]
+    Rename,
+}
+
+/// Moves the contents of a directory into another directory, merging the two trees.
+///
+/// `dst` is created if it does not exist, and each entry of `src` is moved into it:
+/// subdirectories that exist on both sides are merged recursively,
+/// other entries are renamed into place, and files that already exist in `dst` are
+/// handled according to `on_conflict`. With [`ConflictPolicy::Rename`], a clashing
+/// entry is moved to a free name such as `report (1).txt` instead. Moves across filesystems fall back to copying
+/// and deleting. Directories of `src` that end up empty, including `src` itself, are
+/// removed; skipped files keep their directories alive.
+///
+/// Entries are moved one at a time, so an error (including a conflict under
+/// [`ConflictPolicy::Error`]) leaves the entries moved so far in `dst` and the rest in
+/// `src`. A file is never replaced by a directory or the other way around; such a clash
+/// is an error under [`ConflictPolicy::Overwrite`] and handled like any other conflict
+/// under the other policies.
+///
+/// # Arguments
+///
+/// * `src` - The directory whose contents should be moved
+/// * `dst` - The directory to merge them into
+/// * `on_conflict` - What to do when a file already exists in `dst`
+///
+/// # Returns
+///
+/// Returns the paths in `src` that were skipped because of a conflict.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations:
+/// * `src` is not a directory or cannot be read
+/// * `dst` is `src` itself or lies inside it (`ErrorKind::InvalidInput`)
+/// * `dst` exists and is not a directory
+/// * A conflict occurs under [`ConflictPolicy::Error`], or a file and a directory clash
+///   under [`ConflictPolicy::Overwrite`]
+/// * An entry cannot be moved
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::fs::{move_dir_merge, ConflictPolicy};
+///
+/// async fn consolidate() -> std::io::Result<()> {
+///     let skipped = move_dir_merge(Path::new("incoming"), Path::new("archive"), ConflictPolicy::Skip).await?;
+///     for path in skipped {
+///         println!("Already archived: {}", path.display());
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn move_dir_merge(
+    src: &Path,
+    dst: &Path,
+    on_conflict: ConflictPolicy,
+) -> io::Result<Vec<PathBuf>> {
+    if !tokio::fs::metadata(src).await?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotADirectory,
+            format!("{} is not a directory", src.display()),
+        ));
+    }
+    // Merging a directory into itself or a subdirectory would move entries in place or
+    // fail halfway, so both are rejected before anything is moved
+    let nested = normalize_path(&std::path::absolute(dst)?)
+        .starts_with(normalize_path(&std::path::absolute(src)?));
+    if nested || is_same_file(src, dst).unwrap_or(false) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot merge {} into {}, which is inside it", src.display(), dst.display()),
+        ));
+    }
+    ensure_dir(dst).await?;
+    let mut skipped = Vec::new();
+    merge_dir(src, dst, on_conflict, &mut skipped).await?;
+    Ok(skipped)
+}
+
+/// Moves every entry of `src` into the existing directory `dst`, then removes `src` if empty.
+async fn merge_dir(
+    src: &Path,
+    dst: &Path,
+    on_conflict: ConflictPolicy,
+    skipped: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let mut entries = tokio::fs::read_dir(src).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let from = entry.path();
+        let to = dst.join(entry.file_name());
+        let is_dir = entry.file_type().await?.is_dir();
+        let existing = match tokio::fs::symlink_metadata(&to).await {
+            Ok(metadata) => Some(metadata.is_dir()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        match existing {
+            None => move_entry(&from, &to, is_dir, on_conflict, skipped).await?,
+            Some(true) if is_dir => Box::pin(merge_dir(&from, &to, on_conflict, skipped)).await?,
+            Some(dst_is_dir) => match on_conflict {
+                ConflictPolicy::Skip => {
+                    debug!("Skipping {}, {} already exists", from.display(), to.display());
+                    skipped.push(from);
+                }
+                ConflictPolicy::Overwrite if !is_dir && !dst_is_dir => {
+                    debug!("Overwriting {} with {}", to.display(), from.display());
+                    move_entry(&from, &to, false, on_conflict, skipped).await?;
+                }
+                ConflictPolicy::Rename => {
+                    let to = unique_path(&to).await?;
+                    debug!("Moving {} to free name {}", from.display(), to.display());
+                    move_entry(&from, &to, is_dir, on_conflict, skipped).await?;
+                }
+                ConflictPolicy::Overwrite | ConflictPolicy::Error => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("Cannot move {}: {} already exists", from.display(), to.display()),
+                    ));
+                }
+            },
+        }
+    }
+
+    if is_empty_dir(src).await? {
+        tokio::fs::remove_dir(src).await?;
+    }
+    Ok(())
+}
+
+/// Renames `from` to `to`, copying and deleting instead when they are on different filesystems.
+async fn move_entry(
+    from: &Path,
+    to: &Path,
+    is_dir: bool,
+    on_conflict: ConflictPolicy,
+    skipped: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    match tokio::fs::rename(from, to).await {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            debug!("Moving {} across filesystems", from.display());
+            if is_dir {
+                tokio::fs::create_dir(to).await?;
+                Box::pin(merge_dir(from, to, on_conflict, skipped)).await
+            } else {
+                tokio::fs::copy(from, to).await?;
+                tokio::fs::remove_file(from).await
+            }
+        }
+        result => result,
+    }
+}
@@ -0,0 +1,359 @@
+//! File hashing and checksum manifests.
+//!
+//! This module computes SHA-256 digests of files and reads and writes checksum
+//! manifests in the format used by `sha256sum`, so a manifest written here can be
+//! checked with `sha256sum -c` and vice versa. [`hash_file_with`] computes digests
+//! with other algorithms, enabled through the `blake3`, `md5` and `xxhash` features.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//! use xio::hash::{verify_checksum_manifest, write_checksum_manifest};
+//!
+//! async fn publish() -> anyhow::Result<()> {
+//!     let dataset = Path::new("dataset");
+//!     let manifest = dataset.join("SHA256SUMS");
+//!     write_checksum_manifest(dataset, Some("png"), &manifest).await?;
+//!     assert!(verify_checksum_manifest(dataset, Some("png"), &manifest).await?.is_empty());
+//!     Ok(())
+//! }
+//! ```
+
+use crate::is_excluded_by_default;
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use log::{debug, info, warn};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use walkdir::WalkDir;
+
+/// A difference between a directory and its checksum manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// A file listed in the manifest does not exist
+    Missing(PathBuf),
+    /// A file exists in the directory but is not listed in the manifest
+    Extra(PathBuf),
+    /// A file's current hash differs from the one recorded in the manifest
+    Corrupted(PathBuf),
+}
+
+/// A hash algorithm supported by [`hash_file_with`]
+///
+/// SHA-256 is always available; the other algorithms are enabled by the crate feature
+/// of the same name (`xxhash` for [`Xxh3`](Self::Xxh3)).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HashAlgo {
+    /// SHA-256, as used by `sha256sum` and the checksum manifests
+    #[default]
+    Sha256,
+    /// BLAKE3, a fast cryptographic hash
+    #[cfg(feature = "blake3")]
+    Blake3,
+    /// MD5, for compatibility with legacy checksums; not collision resistant
+    #[cfg(feature = "md5")]
+    Md5,
+    /// The 64-bit XXH3 hash, a fast non-cryptographic hash suited to deduplication
+    #[cfg(feature = "xxhash")]
+    Xxh3,
+}
+
+/// The text encoding of a digest returned by [`hash_file_with`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashEncoding {
+    /// Lowercase hexadecimal, as printed by `sha256sum` and friends
+    #[default]
+    Hex,
+    /// Standard base64 with padding
+    Base64,
+}
+
+/// The running state of one of the [`HashAlgo`] implementations
+enum Hasher {
+    Sha256(Sha256),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+    #[cfg(feature = "md5")]
+    Md5(md5::Md5),
+    #[cfg(feature = "xxhash")]
+    Xxh3(Box<xxhash_rust::xxh3::Xxh3>),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Sha256 => Self::Sha256(Sha256::new()),
+            #[cfg(feature = "blake3")]
+            HashAlgo::Blake3 => Self::Blake3(Box::new(blake3::Hasher::new())),
+            #[cfg(feature = "md5")]
+            HashAlgo::Md5 => Self::Md5(md5::Md5::new()),
+            #[cfg(feature = "xxhash")]
+            HashAlgo::Xxh3 => Self::Xxh3(Box::new(xxhash_rust::xxh3::Xxh3::new())),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            #[cfg(feature = "blake3")]
+            Self::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+            #[cfg(feature = "md5")]
+            Self::Md5(hasher) => hasher.update(bytes),
+            #[cfg(feature = "xxhash")]
+            Self::Xxh3(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Returns the digest bytes; XXH3's 64-bit value is big-endian, matching `xxhsum`
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            #[cfg(feature = "blake3")]
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            #[cfg(feature = "md5")]
+            Self::Md5(hasher) => hasher.finalize().to_vec(),
+            #[cfg(feature = "xxhash")]
+            Self::Xxh3(hasher) => hasher.digest().to_be_bytes().to_vec(),
+        }
+    }
+}
+
+/// Computes the SHA-256 digest of a file as a lowercase hex string.
+///
+/// The file is read in chunks, so large files are hashed without loading them
+/// into memory.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be opened or read.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::hash::hash_file;
+///
+/// async fn print_hash() -> std::io::Result<()> {
+///     println!("{}", hash_file(Path::new("Cargo.toml")).await?);
+///     Ok(())
+/// }
+/// ```
+pub async fn hash_file(path: &Path) -> io::Result<String> {
+    hash_file_with(path, HashAlgo::Sha256, HashEncoding::Hex).await
+}
+
+/// Computes the digest of a file with the given algorithm and encoding.
+///
+/// Like [`hash_file`], the file is read in chunks. Hex output is lowercase; the
+/// 64-bit XXH3 value is encoded big-endian, so its hex form matches `xxhsum -H3`.
+///
+/// # Arguments
+///
+/// * `path` - The file to hash
+/// * `algo` - The hash algorithm to use
+/// * `encoding` - The text encoding of the returned digest
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be opened or read.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::hash::{hash_file_with, HashAlgo, HashEncoding};
+///
+/// async fn print_hash() -> std::io::Result<()> {
+///     let digest = hash_file_with(Path::new("Cargo.toml"), HashAlgo::Sha256, HashEncoding::Base64).await?;
+///     println!("{digest}");
+///     Ok(())
+/// }
+/// ```
+pub async fn hash_file_with(path: &Path, algo: HashAlgo, encoding: HashEncoding) -> io::Result<String> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Hasher::new(algo);
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    let digest = hasher.finalize();
+    Ok(match encoding {
+        HashEncoding::Hex => to_hex(&digest),
+        HashEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(digest),
+    })
+}
+
+/// Writes a `sha256sum`-compatible manifest for the files in a directory.
+///
+/// Every file under `dir` (filtered by `extension` when given) is hashed and written
+/// as a `<hash>  <relative/path>` line, sorted by path and using `/` as separator.
+/// Hidden entries, git directories and target directories are skipped, as is the
+/// manifest itself when it is written inside `dir`.
+///
+/// # Arguments
+///
+/// * `dir` - The directory whose files should be listed
+/// * `extension` - Optional extension (without the dot) restricting which files are listed
+/// * `out` - The path of the manifest to write
+///
+/// # Returns
+///
+/// Returns the number of files written to the manifest.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the directory cannot be walked, a file cannot be
+/// hashed, or the manifest cannot be written.
+pub async fn write_checksum_manifest(
+    dir: &Path,
+    extension: Option<&str>,
+    out: &Path,
+) -> io::Result<usize> {
+    let files = collect_relative_files(dir, extension, out)?;
+    let mut manifest = String::new();
+    for relative in &files {
+        let hash = hash_file(&dir.join(relative)).await?;
+        let _ = writeln!(manifest, "{hash}  {}", to_manifest_path(relative));
+    }
+    crate::write_to_file(out, &manifest).await?;
+    info!("Wrote checksums for {} files to {}", files.len(), out.display());
+    Ok(files.len())
+}
+
+/// Verifies a directory against a `sha256sum`-style checksum manifest.
+///
+/// Both `<hash>  <path>` (text mode) and `<hash> *<path>` (binary mode) lines are
+/// accepted; blank lines are ignored. Files listed in the manifest are re-hashed and
+/// compared, and every file under `dir` that is not listed (other than the manifest
+/// itself) is reported as extra, using the same filters as [`write_checksum_manifest`].
+/// Pass the `extension` the manifest was written with, so that files it deliberately
+/// left out are not reported as extra.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to verify
+/// * `extension` - Optional extension (without the dot) restricting which unlisted files are reported
+/// * `manifest` - The path of the manifest to verify against
+///
+/// # Returns
+///
+/// Returns the list of mismatches, which is empty if the directory matches the manifest.
+///
+/// # Errors
+///
+/// Returns an error if the manifest cannot be read or contains a malformed line, or if
+/// a listed file exists but cannot be hashed.
+pub async fn verify_checksum_manifest(
+    dir: &Path,
+    extension: Option<&str>,
+    manifest: &Path,
+) -> Result<Vec<Mismatch>> {
+    let content = crate::read_file_content(manifest)
+        .await
+        .with_context(|| format!("Failed to read manifest {}", manifest.display()))?;
+
+    let mut expected = BTreeMap::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (hash, path) = parse_manifest_line(line).with_context(|| {
+            format!("Malformed line {} in {}", index + 1, manifest.display())
+        })?;
+        expected.insert(PathBuf::from(path), hash.to_ascii_lowercase());
+    }
+
+    let mut mismatches = Vec::new();
+    for (relative, hash) in &expected {
+        let path = dir.join(relative);
+        if !tokio::fs::try_exists(&path).await? {
+            mismatches.push(Mismatch::Missing(relative.clone()));
+        } else if hash_file(&path).await? != *hash {
+            warn!("Checksum mismatch: {}", path.display());
+            mismatches.push(Mismatch::Corrupted(relative.clone()));
+        }
+    }
+
+    for relative in collect_relative_files(dir, extension, manifest)? {
+        if !expected.contains_key(&relative) {
+            mismatches.push(Mismatch::Extra(relative));
+        }
+    }
+
+    debug!("Verified {} against manifest: {} mismatches", dir.display(), mismatches.len());
+    Ok(mismatches)
+}
+
+/// Formats bytes as a lowercase hex string
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// Collects the sorted paths, relative to `dir`, of files to list in a manifest
+fn collect_relative_files(dir: &Path, extension: Option<&str>, manifest: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).into_iter().filter_entry(|e| !is_excluded_by_default(e)) {
+        let entry = entry?;
+        if !entry.file_type().is_file()
+            || is_manifest(entry.path(), manifest)
+            || extension.is_some_and(|ext| entry.path().extension().is_none_or(|e| e != ext))
+        {
+            continue;
+        }
+        if let Ok(relative) = entry.path().strip_prefix(dir) {
+            files.push(relative.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns true if `path` is the manifest file, however either path is spelled
+///
+/// Only files with the manifest's name are compared by identity, so the walk does not
+/// open every file it visits.
+fn is_manifest(path: &Path, manifest: &Path) -> bool {
+    path == manifest
+        || (path.file_name() == manifest.file_name()
+            && crate::fs::is_same_file(path, manifest).unwrap_or(false))
+}
+
+/// Converts a relative path to the `/`-separated form used in manifests
+pub(crate) fn to_manifest_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Splits a manifest line into its hash and path
+fn parse_manifest_line(line: &str) -> Result<(&str, &str)> {
+    let (hash, rest) = line
+        .split_once(' ')
+        .context("expected '<hash>  <path>'")?;
+    let path = rest
+        .strip_prefix(' ')
+        .or_else(|| rest.strip_prefix('*'))
+        .context("expected two spaces or ' *' between hash and path")?;
+    if hash.is_empty() || !hash.bytes().all(|b| b.is_ascii_hexdigit()) || path.is_empty() {
+        anyhow::bail!("expected '<hash>  <path>'");
+    }
+    Ok((hash, path))
+}
@@ -57,12 +57,25 @@
 //! }
 //! ```
 
+pub mod diff;
 pub mod fs;
+pub mod hash;
+pub mod lock;
+pub mod progress;
+pub mod read;
 pub mod split;
+pub mod staging;
+pub mod vfs;
+pub mod walk;
+pub mod watch;
 
 pub use anyhow;
 pub use log;
 pub use walkdir;
+pub use tokio_util::sync::CancellationToken;
+pub use tempfile::TempPath;
+#[cfg(feature = "mmap")]
+pub use memmap2::Mmap;
 
 // Re-export commonly used types and traits
 pub use std::{
@@ -70,13 +83,33 @@ pub use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-pub use split::{DirectorySplitter, FileMatcher, RegexFileMatcher, SplitConfig};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+pub use split::{
+    verify_split, CopyMode, DirectorySplitter, FileMatcher, FnMatcher, Holdout, MagicMatcher,
+    MagicSignature, RegexFileMatcher, ResumeCheck, SearchScope, ShardHook, SplitConfig,
+    SplitReport, SymlinkStyle, VerifyReport,
+};
+#[cfg(feature = "json")]
+pub use split::ShardInfo;
+pub use diff::{diff_directories, DirDiff};
+#[cfg(feature = "line-diff")]
+pub use diff::{diff_files_lines, LineChange};
+pub use lock::{acquire_lock, try_acquire_lock, FileLock};
+pub use progress::{NoProgress, Progress};
+pub use read::{FileTooLarge, ReadOptions, TrimMode, DEFAULT_BUFFER_CAPACITY};
+pub use staging::StagingFile;
+pub use walk::{IncludedDirs, PathMode, ProcessOrder, WalkAction, WalkOptions, WalkReport};
+use walk::IgnoreRules;
+use anyhow::Context;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use log::{debug, info, warn};
 use tokio::{
     fs::File,
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    process::Command,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter},
+    process::{Child, Command},
     sync::Mutex,
+    task::JoinSet,
 };
 use walkdir::{DirEntry, WalkDir};
 
@@ -107,9 +140,12 @@ use walkdir::{DirEntry, WalkDir};
 /// ```
 #[must_use = "Determines if the directory entry is hidden"]
 pub fn is_hidden(entry: &DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
+    is_hidden_name(entry.file_name())
+}
+
+/// Determines if a file name is hidden, following the rules of [`is_hidden`].
+fn is_hidden_name(name: &std::ffi::OsStr) -> bool {
+    name.to_str()
         .is_some_and(|s| s.starts_with('.') && s != "." && s != ".." && !s.starts_with(".tmp"))
 }
 
@@ -173,6 +209,20 @@ pub fn is_git_dir(entry: &DirEntry) -> bool {
     entry.file_name().to_string_lossy() == ".git"
 }
 
+/// Determines if a directory entry is skipped by the crate's default traversal filter.
+///
+/// Hidden entries, git directories and target directories are excluded from all walks.
+pub(crate) fn is_excluded_by_default(entry: &DirEntry) -> bool {
+    is_excluded_name(entry.file_name())
+}
+
+/// Determines if an entry with this file name is skipped by the default traversal filter.
+///
+/// This is [`is_excluded_by_default`] for traversals that do not produce `walkdir` entries.
+pub(crate) fn is_excluded_name(name: &std::ffi::OsStr) -> bool {
+    is_hidden_name(name) || name == ".git" || name == "target"
+}
+
 /// Walks through a directory and asynchronously processes files with a specific extension.
 ///
 /// This function traverses a directory tree and applies an asynchronous callback function
@@ -191,7 +241,7 @@ pub fn is_git_dir(entry: &DirEntry) -> bool {
 /// # Arguments
 ///
 /// * `dir` - The root directory to start the walk from
-/// * `extension` - The file extension to match (without the dot)
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
 /// * `callback` - An async function to process each matching file
 ///
 /// # Returns
@@ -232,63 +282,75 @@ where
     F: Fn(&Path) -> Fut + Send + Sync + 'static,
     Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
 {
-    let dir_ref = dir.as_ref();
-    debug!("Starting walk of directory: {dir_ref:?}");
-    let walker = WalkDir::new(dir_ref).follow_links(true);
-
-    let callback = Arc::new(callback);
-    let mut handles = Vec::new();
-
-    for entry in walker
-        .into_iter()
-        .filter_entry(|e| {
-            let file_name = e.file_name().to_string_lossy();
-            let keep = !(file_name.starts_with('.') && file_name != "." && file_name != ".." && !file_name.starts_with(".tmp"))
-                && file_name != ".git"
-                && file_name != "target";
-            debug!("Filtering entry: {:?}, keep: {}", e.path(), keep);
-            keep
-        })
-        .filter_map(|r| {
-            if let Ok(entry) = r {
-                debug!("Found valid entry: {:?}", entry.path());
-                Some(entry)
-            } else {
-                warn!("Invalid entry: {:?}", r.err());
-                None
-            }
-        })
-    {
-        let path = entry.path().to_owned();
-        debug!("Processing path: {path:?}");
-        if let Some(ext) = path.extension() {
-            debug!("  Extension: {ext:?}");
-            if ext.to_string_lossy() == extension {
-                info!("Processing file: {path:?}");
-                let callback = Arc::clone(&callback);
-                let handle = tokio::spawn(async move { callback(&path).await });
-                handles.push(handle);
-            }
-        }
-    }
-
-    // Wait for all tasks to complete and collect any errors
-    for handle in handles {
-        handle.await??;
-    }
-
-    Ok(())
+    walk_directory_with(dir, extension, &WalkOptions::default(), callback).await
 }
 
-/// Walks through Rust files in a directory and applies a callback function to each file.
+/// Walks through a directory like [`walk_directory`], using the given [`WalkOptions`].
 ///
-/// This specialized version of directory walking is optimized for Rust source files.
-/// It automatically skips:
-/// - Hidden folders (except "." and "..")
-/// - Git repository directories (.git)
-/// - Build output directories (target)
+/// # Type Parameters
 ///
-/// The function processes files sequentially in the order they are discovered.
+/// * `F` - The callback function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `options` - Options controlling the traversal
+/// * `callback` - An async function to process each matching file
+///
+/// # Returns
+///
+/// Returns `Ok(())` if all files were processed successfully, or an error if any
+/// operation failed.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if:
+/// - Directory traversal fails
+/// - File operations fail
+/// - The callback function returns an error
+///
+/// # Examples
+///
+/// ```
+/// use xio::{walk_directory_with, anyhow, WalkOptions};
+///
+/// async fn process_files() -> anyhow::Result<()> {
+///     let options = WalkOptions::new().with_canonicalize(true);
+///     walk_directory_with("./", "txt", &options, |path| {
+///         let path = path.to_path_buf();
+///         async move {
+///             println!("Processing: {}", path.display());
+///             Ok(())
+///         }
+///     }).await
+/// }
+/// ```
+#[must_use = "Walks through a directory and requires handling of the result to ensure proper file processing"]
+pub async fn walk_directory_with<F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    options: &WalkOptions,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&Path) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let selector = FileSelector::Extension(extension);
+    walk_matching(dir.as_ref(), &selector, options, |_| Ok(()), move |path, ()| always_continue(callback(path)))
+        .await
+        .map(|_| ())
+}
+
+/// Walks through a directory like [`walk_directory_with`], reporting skipped entries.
+///
+/// The plain walkers log entries that cannot be read during traversal (for example
+/// because of missing permissions) and carry on without them. This variant also
+/// collects those traversal errors into the returned [`WalkReport`], together with the
+/// number of files processed, so callers can tell a complete walk from one that could
+/// not see parts of the tree.
 ///
 /// # Type Parameters
 ///
@@ -298,252 +360,3437 @@ where
 /// # Arguments
 ///
 /// * `dir` - The root directory to start the walk from
-/// * `callback` - An async function to process each Rust file
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `options` - Options controlling the traversal
+/// * `callback` - An async function to process each matching file
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` if all files were processed successfully.
+/// Returns a [`WalkReport`] with the number of processed files and the traversal errors,
+/// or an error if a callback failed.
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if:
-/// * Directory traversal fails (e.g., permission denied)
-/// * The callback function returns an error while processing a file
-/// * A file or directory cannot be accessed
-/// * Path metadata cannot be read
+/// Returns an `anyhow::Error` if:
+/// - File operations fail
+/// - The callback function returns an error
 ///
 /// # Examples
 ///
 /// ```
-/// use std::path::Path;
-/// use std::io;
-/// use xio::walk_rust_files;
-/// 
-/// async fn process_rust_files() -> io::Result<()> {
-///     walk_rust_files("./src", |path| {
+/// use xio::{walk_directory_with_report, anyhow, WalkOptions};
+///
+/// async fn back_up() -> anyhow::Result<()> {
+///     let report = walk_directory_with_report("./", "*", &WalkOptions::new(), |path| {
 ///         let path = path.to_path_buf();
 ///         async move {
-///             println!("Found Rust file: {}", path.display());
+///             println!("Backing up: {}", path.display());
 ///             Ok(())
 ///         }
+///     }).await?;
+///     if !report.is_complete() {
+///         println!("Skipped {} unreadable entries", report.errors.len());
+///     }
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Walks through a directory and requires handling of the result to ensure proper file processing"]
+pub async fn walk_directory_with_report<F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    options: &WalkOptions,
+    callback: F,
+) -> anyhow::Result<WalkReport>
+where
+    F: Fn(&Path) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let selector = FileSelector::Extension(extension);
+    walk_matching(dir.as_ref(), &selector, options, |_| Ok(()), move |path, ()| always_continue(callback(path))).await
+}
+
+/// Walks through a directory like [`walk_directory_with`], letting the callback end the walk.
+///
+/// The callback returns a [`WalkAction`]. Once a callback returns
+/// [`WalkAction::Stop`], no further files are handed out and the walk returns `Ok`
+/// after the callbacks that are already running have finished. This separates
+/// "found what I need" from failure, which would otherwise have to be signalled with
+/// an error. Since callbacks run concurrently, a few more files than strictly needed
+/// may be processed. Ordered walks (see [`ProcessOrder`]) run one callback at a time,
+/// so there the stop takes effect immediately.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `options` - Options controlling the traversal
+/// * `callback` - An async function to process each matching file and decide whether to go on
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the walk finished or was stopped and all started callbacks
+/// succeeded, or an error if any operation failed.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if:
+/// - File operations fail
+/// - The callback function returns an error
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use xio::{walk_directory_until, anyhow, WalkAction, WalkOptions};
+///
+/// async fn find_three() -> anyhow::Result<()> {
+///     let found = Arc::new(AtomicUsize::new(0));
+///     walk_directory_until("./", "rs", &WalkOptions::new(), move |path| {
+///         let path = path.to_path_buf();
+///         let found = Arc::clone(&found);
+///         async move {
+///             println!("Found: {}", path.display());
+///             if found.fetch_add(1, Ordering::SeqCst) + 1 >= 3 {
+///                 Ok(WalkAction::Stop)
+///             } else {
+///                 Ok(WalkAction::Continue)
+///             }
+///         }
 ///     }).await
 /// }
 /// ```
-pub async fn walk_rust_files<F, Fut>(dir: impl AsRef<Path>, callback: F) -> io::Result<()>
+#[must_use = "Walks through a directory and requires handling of the result to ensure proper file processing"]
+pub async fn walk_directory_until<F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    options: &WalkOptions,
+    callback: F,
+) -> anyhow::Result<()>
 where
-    F: Fn(&Path) -> Fut,
-    Fut: std::future::Future<Output = io::Result<()>>,
+    F: Fn(&Path) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<WalkAction>> + Send + 'static,
 {
-    let walker = WalkDir::new(dir).follow_links(true);
+    let selector = FileSelector::Extension(extension);
+    walk_matching(dir.as_ref(), &selector, options, |_| Ok(()), move |path, ()| callback(path))
+        .await
+        .map(|_| ())
+}
 
-    for entry in walker
-        .into_iter()
-        .filter_entry(|e| {
-            let file_name = e.file_name().to_string_lossy();
-            !(file_name.starts_with('.') && file_name != "." && file_name != ".." && !file_name.starts_with(".tmp"))
-                && file_name != ".git"
-                && file_name != "target"
-        })
-        .filter_map(Result::ok)
-    {
-        let path = entry.path().to_owned();
-        if entry.file_type().is_file() && path.extension().is_some_and(|ext| ext == "rs") {
-            callback(&path).await?;
-        }
-    }
+/// Walks through a directory like [`walk_directory_with`], passing each file's metadata
+/// to the callback.
+///
+/// The metadata is taken from the directory entry produced by the traversal, so
+/// callbacks that need a file's size or modification time do not have to look it up
+/// again. With symlink following enabled (the default), this is the metadata of the
+/// link target.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&Path, &Metadata) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `options` - Options controlling the traversal
+/// * `callback` - An async function to process each matching file and its metadata
+///
+/// # Returns
+///
+/// Returns `Ok(())` if all files were processed successfully, or an error if any
+/// operation failed.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if:
+/// - The metadata of a matching file cannot be read
+/// - The callback function returns an error
+///
+/// # Examples
+///
+/// ```
+/// use xio::{walk_directory_with_metadata, anyhow, WalkOptions};
+///
+/// async fn print_sizes() -> anyhow::Result<()> {
+///     walk_directory_with_metadata("./", "txt", &WalkOptions::new(), |path, metadata| {
+///         println!("{}: {} bytes", path.display(), metadata.len());
+///         async { Ok(()) }
+///     }).await
+/// }
+/// ```
+#[must_use = "Walks through a directory and requires handling of the result to ensure proper file processing"]
+pub async fn walk_directory_with_metadata<F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    options: &WalkOptions,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&Path, &std::fs::Metadata) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    walk_matching(
+        dir.as_ref(),
+        &FileSelector::Extension(extension),
+        options,
+        |entry| {
+            entry
+                .metadata()
+                .with_context(|| format!("Failed to read metadata of {}", entry.path().display()))
+        },
+        move |path, metadata| always_continue(callback(path, &metadata)),
+    )
+    .await
+    .map(|_| ())
+}
 
-    Ok(())
+/// Walks through a directory like [`walk_directory`], passing each file's directory
+/// entry to the callback.
+///
+/// The [`walkdir::DirEntry`] gives callbacks the file type, metadata and depth below
+/// `dir` found during traversal, for decisions the path alone cannot support, without
+/// looking each file up again. The default [`WalkOptions`] apply; use
+/// [`walk_directory`] when the path is all a callback needs.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&DirEntry) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `callback` - An async function to process each matching file's entry
+///
+/// # Returns
+///
+/// Returns `Ok(())` if all files were processed successfully, or an error if any
+/// operation failed.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if the callback function returns an error.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{walk_directory_entries, anyhow};
+///
+/// async fn top_level_only() -> anyhow::Result<()> {
+///     walk_directory_entries("./", "txt", |entry| {
+///         if entry.depth() == 1 {
+///             println!("{}", entry.path().display());
+///         }
+///         async { Ok(()) }
+///     }).await
+/// }
+/// ```
+#[must_use = "Walks through a directory and requires handling of the result to ensure proper file processing"]
+pub async fn walk_directory_entries<F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&DirEntry) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    walk_matching(
+        dir.as_ref(),
+        &FileSelector::Extension(extension),
+        &WalkOptions::default(),
+        |entry| Ok(entry.clone()),
+        move |_, entry| always_continue(callback(&entry)),
+    )
+    .await
+    .map(|_| ())
 }
 
-/// Reads all lines from a file at the given path.
+/// Walks through a directory like [`walk_directory`], selecting files by a glob pattern.
 ///
-/// This function asynchronously reads a file line by line and returns a vector
-/// containing all lines. Each line is trimmed of whitespace and newline characters.
+/// The pattern is matched against each file's path relative to `dir`, so it can
+/// express selections that an extension cannot, such as `"src/**/mod.rs"` or
+/// `"**/tests/**"`. A `*` does not match across path separators; use `**` to match
+/// any number of directories. The pattern is compiled once before the walk, and the
+/// default directory pruning still applies.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the callback function
 ///
 /// # Arguments
 ///
-/// * `path` - The path to the file to read
+/// * `dir` - The root directory to start the walk from
+/// * `pattern` - The glob pattern to match relative paths against
+/// * `callback` - An async function to process each matching file
 ///
 /// # Returns
 ///
-/// Returns a vector of strings, where each string is a line from the file.
+/// Returns `Ok(())` if all files were processed successfully, or an error if any
+/// operation failed.
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if:
-/// - The file cannot be opened
-/// - The file cannot be read
-/// - The file content is not valid UTF-8
+/// Returns an `anyhow::Error` if:
+/// - The pattern is not a valid glob
+/// - File operations fail
+/// - The callback function returns an error
+///
+/// # Examples
+///
+/// ```
+/// use xio::{walk_directory_glob, anyhow};
+///
+/// async fn list_modules() -> anyhow::Result<()> {
+///     walk_directory_glob("./", "src/**/mod.rs", |path| {
+///         let path = path.to_path_buf();
+///         async move {
+///             println!("Module: {}", path.display());
+///             Ok(())
+///         }
+///     }).await
+/// }
+/// ```
+#[must_use = "Walks through a directory and requires handling of the result to ensure proper file processing"]
+pub async fn walk_directory_glob<F, Fut>(
+    dir: impl AsRef<Path>,
+    pattern: &str,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&Path) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let matcher = globset::GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("Invalid glob pattern: {pattern}"))?
+        .compile_matcher();
+    let selector = FileSelector::Glob(matcher);
+    let options = WalkOptions::default();
+    walk_matching(dir.as_ref(), &selector, &options, |_| Ok(()), move |path, ()| always_continue(callback(path)))
+        .await
+        .map(|_| ())
+}
+
+/// Walks through a directory, sending the path of each matching file into a channel.
+///
+/// Instead of invoking a callback, each match is sent on `sender` in traversal order,
+/// which decouples discovery from consumption: a consumer task can stream the paths
+/// into a database or message queue at its own pace, and a bounded channel slows the
+/// walk down when the consumer falls behind. The walk applies the same default
+/// filters as [`walk_directory`]. `sender` is dropped when the walk ends, which closes
+/// the channel once no other senders remain.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `sender` - The channel the matching paths are sent on
+///
+/// # Returns
+///
+/// Returns a [`WalkReport`] with the number of paths sent and the entries that could
+/// not be read during traversal.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if the receiver is dropped before every match was sent.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use xio::{walk_directory_to_channel, anyhow};
+///
+/// async fn index_files() -> anyhow::Result<()> {
+///     let (sender, mut receiver) = tokio::sync::mpsc::channel::<PathBuf>(64);
+///     let consumer = tokio::spawn(async move {
+///         while let Some(path) = receiver.recv().await {
+///             println!("Indexing: {}", path.display());
+///         }
+///     });
+///     let report = walk_directory_to_channel("./", "rs", sender).await?;
+///     consumer.await?;
+///     println!("Sent {} paths", report.processed);
+///     Ok(())
+/// }
+/// ```
+pub async fn walk_directory_to_channel(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    sender: tokio::sync::mpsc::Sender<PathBuf>,
+) -> anyhow::Result<WalkReport> {
+    let dir = dir.as_ref();
+    debug!("Starting walk of directory: {}", dir.display());
+    let selector = FileSelector::Extension(extension);
+    let options = WalkOptions::default();
+    let mut errors = Vec::new();
+    let mut processed = 0;
+    for entry in matching_entries(dir, &selector, &options, &mut errors) {
+        if sender.send(entry.into_path()).await.is_err() {
+            anyhow::bail!("Receiver dropped after {processed} paths were sent");
+        }
+        processed += 1;
+    }
+    if !errors.is_empty() {
+        warn!("Skipped {} unreadable entries under {}", errors.len(), dir.display());
+    }
+    Ok(WalkReport {
+        processed,
+        errors,
+        cancelled: false,
+    })
+}
+
+/// Shared implementation of the concurrent walkers.
+///
+/// `load` runs during traversal for each matching entry and its output is handed to
+/// `callback` along with the path; a `load` error is reported as that file's error.
+/// Entries that cannot be read during traversal are logged and collected into the
+/// returned report.
+async fn walk_matching<T, L, F, Fut>(
+    dir: &Path,
+    selector: &FileSelector<'_>,
+    options: &WalkOptions,
+    load: L,
+    callback: F,
+) -> anyhow::Result<WalkReport>
+where
+    T: Send + 'static,
+    L: Fn(&DirEntry) -> anyhow::Result<T> + Send,
+    F: Fn(&Path, T) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<WalkAction>> + Send + 'static,
+{
+    debug!("Starting walk of directory: {}", dir.display());
+    let callback = Arc::new(callback);
+    let progress = options.progress();
+    let mut tasks = JoinSet::new();
+    let mut first_error = None;
+    let mut report = WalkReport::default();
+
+    let matched = matching_entries(dir, selector, options, &mut report.errors);
+    let matched = dispatch_order(matched, options, &*progress);
+
+    if let Some(workers) = options.workers.filter(|_| options.order == ProcessOrder::Walk) {
+        let outcome = walk_pooled(matched, options, load, callback, workers).await;
+        progress.on_finish();
+        report.processed = outcome.processed;
+        report.cancelled = outcome.cancelled;
+        if !report.errors.is_empty() {
+            warn!("Skipped {} unreadable entries under {}", report.errors.len(), dir.display());
+        }
+        return outcome.error.map_or(Ok(report), Err);
+    }
+
+    let shared_options = Arc::new(options.clone());
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut rate_limiter = options.rate_limiter();
+    for entry in matched {
+        if stop.load(Ordering::SeqCst) {
+            debug!("Callback requested stop, not spawning further tasks");
+            break;
+        }
+        if options.is_cancelled() {
+            info!("Walk cancelled, waiting for running callbacks to finish");
+            report.cancelled = true;
+            break;
+        }
+
+        if let Some(interval) = rate_limiter.as_mut() {
+            interval.tick().await;
+        }
+
+        if options.fail_fast {
+            while let Some(result) = tasks.try_join_next() {
+                progress.on_advance(1);
+                if let Err(e) = count_task_result(result, &mut report.processed) {
+                    warn!("Aborting walk after error: {e}");
+                    tasks.abort_all();
+                    progress.on_finish();
+                    return Err(e);
+                }
+            }
+        }
+
+        let loaded = load(&entry);
+        let path = entry.into_path();
+        info!("Processing file: {}", path.display());
+        let callback = Arc::clone(&callback);
+        let task_options = Arc::clone(&shared_options);
+        let stop = Arc::clone(&stop);
+        tasks.spawn(async move {
+            if run_callback(&*callback, path, loaded, &task_options).await? == WalkAction::Stop {
+                stop.store(true, Ordering::SeqCst);
+            }
+            Ok(())
+        });
+
+        // Ordered walks run one callback at a time so the order is observable
+        if options.order != ProcessOrder::Walk
+            && let Some(result) = tasks.join_next().await
+        {
+            progress.on_advance(1);
+            if let Err(e) = count_task_result(result, &mut report.processed) {
+                if options.fail_fast {
+                    warn!("Aborting walk after error: {e}");
+                    progress.on_finish();
+                    return Err(e);
+                }
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    // Wait for all tasks to complete and collect any errors
+    while let Some(result) = tasks.join_next().await {
+        progress.on_advance(1);
+        if let Err(e) = count_task_result(result, &mut report.processed) {
+            if options.fail_fast {
+                warn!("Aborting walk after error: {e}");
+                tasks.abort_all();
+                progress.on_finish();
+                return Err(e);
+            }
+            first_error.get_or_insert(e);
+        }
+    }
+
+    progress.on_finish();
+    if !report.errors.is_empty() {
+        warn!("Skipped {} unreadable entries under {}", report.errors.len(), dir.display());
+    }
+    first_error.map_or(Ok(report), Err)
+}
+
+/// Hands one matched file to a walker's callback, applying the per-file options.
+///
+/// The path is canonicalized and converted to the configured [`PathMode`] first, and
+/// the callback is subject to the per-file timeout.
+async fn run_callback<T, F, Fut>(
+    callback: &F,
+    path: PathBuf,
+    loaded: anyhow::Result<T>,
+    options: &WalkOptions,
+) -> anyhow::Result<WalkAction>
+where
+    F: Fn(&Path, T) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<WalkAction>>,
+{
+    let loaded = loaded?;
+    let path = if options.canonicalize {
+        canonicalize_or_original(path).await
+    } else {
+        path
+    };
+    let path = options.path_mode.apply(&path);
+    match options.per_file_timeout {
+        Some(limit) => tokio::time::timeout(limit, callback(&path, loaded))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out after {limit:?} processing {}", path.display()))?,
+        None => callback(&path, loaded).await,
+    }
+}
+
+/// Number of matched files handed to a pool worker at a time
+const WORKER_BATCH_SIZE: usize = 256;
+
+/// A batch of matched files, with what the walker loaded for each, queued for a pool worker
+type WorkerBatch<T> = Vec<(PathBuf, anyhow::Result<T>)>;
+
+/// The combined result of the workers of a pooled walk
+#[derive(Default)]
+struct PoolOutcome {
+    processed: usize,
+    cancelled: bool,
+    error: Option<anyhow::Error>,
+}
+
+/// Runs the callbacks of a walk on a fixed pool of worker tasks.
+///
+/// Matched files are queued in batches on a bounded channel that `workers` tasks drain,
+/// each running its callbacks one after another, so a walk costs a few tasks instead of
+/// one per file and the traversal waits when the workers fall behind.
+async fn walk_pooled<T, L, F, Fut>(
+    matched: impl Iterator<Item = DirEntry>,
+    options: &WalkOptions,
+    load: L,
+    callback: Arc<F>,
+    workers: usize,
+) -> PoolOutcome
+where
+    T: Send + 'static,
+    L: Fn(&DirEntry) -> anyhow::Result<T>,
+    F: Fn(&Path, T) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<WalkAction>> + Send + 'static,
+{
+    debug!("Processing files on {workers} workers");
+    let (sender, receiver) = tokio::sync::mpsc::channel::<WorkerBatch<T>>(workers * 2);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let options = Arc::new(options.clone());
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut pool = JoinSet::new();
+    for _ in 0..workers {
+        pool.spawn(pool_worker(
+            Arc::clone(&receiver),
+            Arc::clone(&callback),
+            Arc::clone(&options),
+            Arc::clone(&stop),
+        ));
+    }
+    drop(receiver);
+
+    let mut outcome = PoolOutcome::default();
+    let mut rate_limiter = options.rate_limiter();
+    let mut batch = Vec::with_capacity(WORKER_BATCH_SIZE);
+    for entry in matched {
+        if stop.load(Ordering::SeqCst) {
+            debug!("Walk stopped, not queueing further files");
+            break;
+        }
+        if options.is_cancelled() {
+            info!("Walk cancelled, waiting for running callbacks to finish");
+            outcome.cancelled = true;
+            break;
+        }
+        if let Some(interval) = rate_limiter.as_mut() {
+            interval.tick().await;
+        }
+        let loaded = load(&entry);
+        batch.push((entry.into_path(), loaded));
+        if batch.len() == WORKER_BATCH_SIZE {
+            let full = std::mem::replace(&mut batch, Vec::with_capacity(WORKER_BATCH_SIZE));
+            if sender.send(full).await.is_err() {
+                break;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        // Fails only if every worker has already stopped
+        let _ = sender.send(batch).await;
+    }
+    drop(sender);
+
+    while let Some(result) = pool.join_next().await {
+        let (processed, error, cancelled) = result.unwrap_or_else(|e| (0, Some(e.into()), false));
+        outcome.processed += processed;
+        outcome.cancelled |= cancelled;
+        if let Some(e) = error {
+            if options.fail_fast {
+                warn!("Aborting walk after error: {e}");
+                pool.abort_all();
+            }
+            outcome.error.get_or_insert(e);
+        }
+    }
+    outcome
+}
+
+/// Drains batches from a pooled walk's queue.
+///
+/// Returns the worker's success count, its first error, and whether it skipped queued
+/// files because the walk was cancelled.
+async fn pool_worker<T, F, Fut>(
+    receiver: Arc<Mutex<tokio::sync::mpsc::Receiver<WorkerBatch<T>>>>,
+    callback: Arc<F>,
+    options: Arc<WalkOptions>,
+    stop: Arc<AtomicBool>,
+) -> (usize, Option<anyhow::Error>, bool)
+where
+    F: Fn(&Path, T) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<WalkAction>>,
+{
+    let progress = options.progress();
+    let mut processed = 0;
+    let mut first_error = None;
+    let mut cancelled = false;
+    loop {
+        let Some(batch) = receiver.lock().await.recv().await else {
+            break;
+        };
+        for (path, loaded) in batch {
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            if options.is_cancelled() {
+                debug!("Walk cancelled, skipping queued file: {}", path.display());
+                cancelled = true;
+                break;
+            }
+            info!("Processing file: {}", path.display());
+            let result = run_callback(&*callback, path, loaded, &options).await;
+            progress.on_advance(1);
+            match result {
+                Ok(action) => {
+                    processed += 1;
+                    if action == WalkAction::Stop {
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                }
+                Err(e) => {
+                    if options.fail_fast {
+                        stop.store(true, Ordering::SeqCst);
+                        return (processed, Some(e), cancelled);
+                    }
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+    }
+    (processed, first_error, cancelled)
+}
+
+/// Adapts a callback future that cannot stop the walk to the shared walker.
+async fn always_continue(
+    callback: impl std::future::Future<Output = anyhow::Result<()>>,
+) -> anyhow::Result<WalkAction> {
+    callback.await.map(|()| WalkAction::Continue)
+}
+
+/// Walks `dir` with the default filters, yielding the entries the walk should process.
+///
+/// Traversal errors are logged and pushed onto `errors`.
+fn matching_entries<'a>(
+    dir: &'a Path,
+    selector: &'a FileSelector<'a>,
+    options: &'a WalkOptions,
+    errors: &'a mut Vec<walkdir::Error>,
+) -> impl Iterator<Item = DirEntry> + Send + 'a {
+    let mut visited = HashSet::new();
+    let contents_first = options.order == ProcessOrder::ContentsFirst;
+    let mut ignore_rules = options
+        .ignore_file
+        .as_deref()
+        .map(|name| IgnoreRules::new(dir, name));
+    WalkDir::new(dir)
+        .follow_links(!contents_first)
+        .contents_first(contents_first)
+        .into_iter()
+        .filter_entry(move |e| {
+            let keep = !options.is_excluded(e)
+                && !ignore_rules
+                    .as_mut()
+                    .is_some_and(|rules| rules.is_ignored(e.path(), e.file_type().is_dir()));
+            debug!("Filtering entry: {}, keep: {keep}", e.path().display());
+            keep
+        })
+        .filter_map(move |r| match r {
+            Ok(entry) => {
+                debug!("Found valid entry: {}", entry.path().display());
+                Some(entry)
+            }
+            Err(e) => {
+                warn!("Invalid entry: {e}");
+                errors.push(e);
+                None
+            }
+        })
+        .filter(move |entry| {
+            selector.selects(dir, entry, options)
+                || (contents_first && entry.file_type().is_dir())
+        })
+        .filter(move |entry| !options.is_oversized(entry))
+        .filter(move |entry| !options.dedup_by_realpath || first_visit(&mut visited, entry))
+}
+
+/// Arranges matched entries in processing order and applies the limit, announcing the total.
+///
+/// Walk and contents-first order stay lazy; the other orders buffer and sort every match first.
+fn dispatch_order<'a>(
+    matched: impl Iterator<Item = DirEntry> + Send + 'a,
+    options: &WalkOptions,
+    progress: &dyn Progress,
+) -> Box<dyn Iterator<Item = DirEntry> + Send + 'a> {
+    let limit = options.limit.unwrap_or(usize::MAX);
+    if matches!(options.order, ProcessOrder::Walk | ProcessOrder::ContentsFirst) {
+        progress.on_start(None);
+        Box::new(matched.take(limit))
+    } else {
+        let mut entries = sort_by_modified(matched, options.order);
+        entries.truncate(limit);
+        debug!("Buffered {} files for ordered processing", entries.len());
+        progress.on_start(Some(entries.len()));
+        Box::new(entries.into_iter())
+    }
+}
+
+/// Records the real path of `entry`, returning false if it was already visited.
+///
+/// Entries whose real path cannot be resolved are always treated as new.
+fn first_visit(visited: &mut HashSet<PathBuf>, entry: &DirEntry) -> bool {
+    match std::fs::canonicalize(entry.path()) {
+        Ok(real_path) => {
+            let is_new = visited.insert(real_path);
+            if !is_new {
+                debug!("Skipping already visited file: {}", entry.path().display());
+            }
+            is_new
+        }
+        Err(e) => {
+            warn!("Failed to resolve {}: {e}", entry.path().display());
+            true
+        }
+    }
+}
+
+/// Buffers `entries` and sorts them by modification time according to `order`.
+///
+/// Entries whose modification time cannot be read sort as the oldest.
+fn sort_by_modified(entries: impl Iterator<Item = DirEntry>, order: ProcessOrder) -> Vec<DirEntry> {
+    let mut entries: Vec<_> = entries
+        .map(|entry| {
+            let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+            (modified, entry)
+        })
+        .collect();
+    entries.sort_by_key(|(modified, _)| *modified);
+    if order == ProcessOrder::ModifiedDesc {
+        entries.reverse();
+    }
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Decides which of the walked files are handed to a walker's callback.
+enum FileSelector<'a> {
+    /// Files with the given extension, or every file for `"*"`
+    Extension(&'a str),
+    /// Files whose path relative to the walk root matches the glob
+    Glob(globset::GlobMatcher),
+}
+
+impl FileSelector<'_> {
+    /// Determines if `entry`, found while walking `root`, should be processed.
+    fn selects(&self, root: &Path, entry: &DirEntry, options: &WalkOptions) -> bool {
+        match self {
+            Self::Extension("*") => {
+                options.is_walked_file(entry) && !options.is_excluded_extension(entry.path())
+            }
+            Self::Extension(extension) => matches_extension(entry, extension),
+            Self::Glob(matcher) => {
+                options.is_walked_file(entry)
+                    && entry
+                        .path()
+                        .strip_prefix(root)
+                        .is_ok_and(|relative| matcher.is_match(relative))
+            }
+        }
+    }
+}
+
+/// Determines if a walked entry matches an extension filter, where `"*"` matches every file.
+fn matches_extension(entry: &DirEntry, extension: &str) -> bool {
+    if extension == "*" {
+        entry.file_type().is_file()
+    } else {
+        fs::has_extension(entry.path(), extension)
+    }
+}
+
+/// Collects the paths of all files under `dir` matching `extension`, sorted by path.
+///
+/// The walk applies the crate's default filters and skips unreadable entries.
+fn collect_matching_paths(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = WalkDir::new(dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|e| !is_excluded_by_default(e))
+        .filter_map(Result::ok)
+        .filter(|e| matches_extension(e, extension))
+        .map(DirEntry::into_path)
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Merges the outcome of a spawned callback task into a single result.
+fn flatten_task_result(
+    result: Result<anyhow::Result<()>, tokio::task::JoinError>,
+) -> anyhow::Result<()> {
+    result.map_err(anyhow::Error::from).and_then(|r| r)
+}
+
+/// Merges the outcome of a spawned callback task, counting it in `processed` on success.
+fn count_task_result(
+    result: Result<anyhow::Result<()>, tokio::task::JoinError>,
+    processed: &mut usize,
+) -> anyhow::Result<()> {
+    let result = flatten_task_result(result);
+    if result.is_ok() {
+        *processed += 1;
+    }
+    result
+}
+
+/// Resolves `path` to its canonical form, falling back to the original path on failure.
+async fn canonicalize_or_original(path: PathBuf) -> PathBuf {
+    match tokio::fs::canonicalize(&path).await {
+        Ok(canonical) => canonical,
+        Err(e) => {
+            warn!("Failed to canonicalize {}: {e}", path.display());
+            path
+        }
+    }
+}
+
+/// Processes an explicit list of paths concurrently, collecting every failure.
+///
+/// This runs the same kind of concurrent processing as [`walk_directory`], but over a
+/// given list of paths instead of a directory walk, which suits re-processing a fixed
+/// set of files such as the ones that failed in a previous run. At most `concurrency`
+/// callbacks run at once, and every path is processed even if some fail.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `paths` - The paths to process
+/// * `concurrency` - The maximum number of callbacks running at once (at least 1)
+/// * `callback` - An async function to process each path
+///
+/// # Returns
+///
+/// Returns the paths whose callback failed (or panicked) along with the error, in the
+/// order the paths were given. An empty list means every path was processed successfully.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use xio::process_paths;
+///
+/// async fn retry(failed_last_run: Vec<PathBuf>) -> Vec<PathBuf> {
+///     let failures = process_paths(&failed_last_run, 4, |path| {
+///         let path = path.to_path_buf();
+///         async move {
+///             tokio::fs::read_to_string(&path).await?;
+///             Ok(())
+///         }
+///     }).await;
+///     failures.into_iter().map(|(path, _)| path).collect()
+/// }
+/// ```
+#[must_use = "Returns the paths that failed, which should be reported or retried"]
+pub async fn process_paths<F, Fut>(
+    paths: &[PathBuf],
+    concurrency: usize,
+    callback: F,
+) -> Vec<(PathBuf, anyhow::Error)>
+where
+    F: Fn(&Path) -> Fut + Send + Sync + 'static,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let callback = Arc::new(callback);
+    let mut tasks = JoinSet::new();
+    let mut task_paths = HashMap::new();
+    let mut failures = Vec::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        while tasks.len() >= concurrency.max(1) {
+            collect_path_failure(tasks.join_next_with_id().await, &mut task_paths, &mut failures);
+        }
+        let callback = Arc::clone(&callback);
+        let task_path = path.clone();
+        let handle = tasks.spawn(async move { callback(&task_path).await });
+        task_paths.insert(handle.id(), (index, path.clone()));
+    }
+    while !tasks.is_empty() {
+        collect_path_failure(tasks.join_next_with_id().await, &mut task_paths, &mut failures);
+    }
+
+    failures.sort_by_key(|(index, _, _)| *index);
+    failures
+        .into_iter()
+        .map(|(_, path, e)| {
+            let e = e.context(format!("Failed to process {}", path.display()));
+            (path, e)
+        })
+        .collect()
+}
+
+/// Records the outcome of a finished [`process_paths`] task if it failed.
+fn collect_path_failure(
+    joined: Option<Result<(tokio::task::Id, anyhow::Result<()>), tokio::task::JoinError>>,
+    task_paths: &mut HashMap<tokio::task::Id, (usize, PathBuf)>,
+    failures: &mut Vec<(usize, PathBuf, anyhow::Error)>,
+) {
+    let (id, result) = match joined {
+        Some(Ok((id, result))) => (id, result),
+        Some(Err(e)) => (e.id(), Err(anyhow::Error::from(e))),
+        None => return,
+    };
+    if let (Some((index, path)), Err(e)) = (task_paths.remove(&id), result) {
+        warn!("Failed to process {}: {e}", path.display());
+        failures.push((index, path, e));
+    }
+}
+
+/// Applies an async function to every matching file and collects the results.
+///
+/// This is the collecting counterpart of [`walk_directory`]: matching files are found
+/// using the same filters, then `f` is run over them with at most `concurrency` calls
+/// in flight at a time. Results are returned in path order, regardless of the order in
+/// which the calls complete.
+///
+/// # Type Parameters
+///
+/// * `T` - The per-file result type
+/// * `F` - The function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `f` - An async function producing a value for each matching file
+/// * `concurrency` - The maximum number of calls running at once (at least 1)
+///
+/// # Returns
+///
+/// Returns the values produced for each matching file, sorted by file path.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if `f` fails for any file; the error includes the path.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{map_files, anyhow};
+///
+/// async fn line_counts() -> anyhow::Result<Vec<usize>> {
+///     map_files("./", "txt", |path| {
+///         let path = path.to_path_buf();
+///         async move { Ok(tokio::fs::read_to_string(&path).await?.lines().count()) }
+///     }, 8).await
+/// }
+/// ```
+pub async fn map_files<T, F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    f: F,
+    concurrency: usize,
+) -> anyhow::Result<Vec<T>>
+where
+    F: Fn(&Path) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let paths = collect_matching_paths(dir.as_ref(), extension);
+    debug!("Mapping over {} files", paths.len());
+    let f = &f;
+
+    stream::iter(paths)
+        .map(|path| async move {
+            f(&path)
+                .await
+                .with_context(|| format!("Failed to process {}", path.display()))
+        })
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await
+}
+
+/// Splits the matching files into those that satisfy an async predicate and those that don't.
+///
+/// Matching files are found using the same filters as [`walk_directory`], then the
+/// predicate is run over them with at most `concurrency` calls in flight at a time, so
+/// an expensive check (such as parsing each file) is done once per file, in a single
+/// pass. Both groups are sorted by file path.
+///
+/// # Type Parameters
+///
+/// * `P` - The predicate type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the predicate
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `predicate` - An async check deciding which group each file belongs to
+/// * `concurrency` - The maximum number of predicate calls running at once (at least 1)
+///
+/// # Returns
+///
+/// Returns the files for which the predicate returned `true`, and those for which it
+/// returned `false`.
+///
+/// # Errors
+///
+/// Returns the first `io::Error` returned by the predicate, with the path prepended to
+/// its message.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use xio::partition_files;
+///
+/// async fn non_empty_json() -> io::Result<()> {
+///     let (non_empty, empty) = partition_files("./", "json", |path| {
+///         let path = path.to_path_buf();
+///         async move { Ok(tokio::fs::metadata(&path).await?.len() > 0) }
+///     }, 8).await?;
+///     println!("{} non-empty, {} empty", non_empty.len(), empty.len());
+///     Ok(())
+/// }
+/// ```
+pub async fn partition_files<P, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    predicate: P,
+    concurrency: usize,
+) -> io::Result<(Vec<PathBuf>, Vec<PathBuf>)>
+where
+    P: Fn(&Path) -> Fut,
+    Fut: std::future::Future<Output = io::Result<bool>>,
+{
+    let paths = collect_matching_paths(dir.as_ref(), extension);
+    debug!("Partitioning {} files", paths.len());
+    let predicate = &predicate;
+
+    let decided: Vec<(PathBuf, bool)> = stream::iter(paths)
+        .map(|path| async move {
+            match predicate(&path).await {
+                Ok(matched) => Ok((path, matched)),
+                Err(e) => Err(io::Error::new(e.kind(), format!("{}: {e}", path.display()))),
+            }
+        })
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    let (matching, rest): (Vec<_>, Vec<_>) = decided.into_iter().partition(|(_, matched)| *matched);
+    Ok((
+        matching.into_iter().map(|(path, _)| path).collect(),
+        rest.into_iter().map(|(path, _)| path).collect(),
+    ))
+}
+
+/// Threads an accumulator through every matching file.
+///
+/// Matching files are found using the same filters as [`walk_directory`] and visited
+/// in path order, passing the accumulator returned for one file into the call for the
+/// next. Because each call depends on the previous one, files are processed strictly
+/// one at a time; use [`map_files`] when the per-file work is independent.
+///
+/// # Type Parameters
+///
+/// * `S` - The accumulator type
+/// * `F` - The function type that implements `Fn(S, &Path) -> Fut`
+/// * `Fut` - The future type returned by the function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `init` - The initial accumulator value
+/// * `f` - An async function combining the accumulator with a file
+///
+/// # Returns
+///
+/// Returns the final accumulator, or `init` if no files matched.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if `f` fails for any file; the error includes the path.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{fold_files, anyhow};
+///
+/// async fn total_lines() -> anyhow::Result<usize> {
+///     fold_files("./", "txt", 0, |total, path| {
+///         let path = path.to_path_buf();
+///         async move { Ok(total + tokio::fs::read_to_string(&path).await?.lines().count()) }
+///     }).await
+/// }
+/// ```
+pub async fn fold_files<S, F, Fut>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    init: S,
+    f: F,
+) -> anyhow::Result<S>
+where
+    F: Fn(S, &Path) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<S>>,
+{
+    let mut state = init;
+    for path in collect_matching_paths(dir.as_ref(), extension) {
+        debug!("Folding file: {}", path.display());
+        state = f(state, &path)
+            .await
+            .with_context(|| format!("Failed to process {}", path.display()))?;
+    }
+    Ok(state)
+}
+
+/// Walks through a directory tree and applies a callback to each directory.
+///
+/// Unlike [`walk_directory`], which only reports files, this invokes the callback for
+/// the directories themselves. The root directory is included and is always visited
+/// first; every directory is visited before its subdirectories. Hidden directories,
+/// git repository directories and target directories are skipped along with their
+/// contents.
+///
+/// Directories are processed sequentially in the order they are discovered.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `callback` - An async function to process each directory
+///
+/// # Returns
+///
+/// Returns `Ok(())` if all directories were processed successfully.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if the callback function returns an error.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{walk_directories, anyhow};
+///
+/// async fn add_markers() -> anyhow::Result<()> {
+///     walk_directories("./data", |dir| {
+///         let marker = dir.join(".processed");
+///         async move {
+///             tokio::fs::write(marker, "").await?;
+///             Ok(())
+///         }
+///     }).await
+/// }
+/// ```
+pub async fn walk_directories<F, Fut>(dir: impl AsRef<Path>, callback: F) -> anyhow::Result<()>
+where
+    F: Fn(&Path) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>>,
+{
+    for entry in WalkDir::new(dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|e| !is_excluded_by_default(e))
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_dir())
+    {
+        debug!("Processing directory: {}", entry.path().display());
+        callback(entry.path()).await?;
+    }
+
+    Ok(())
+}
+
+/// Walks through Rust files in a directory and applies a callback function to each file.
+///
+/// This specialized version of directory walking is optimized for Rust source files.
+/// It automatically skips:
+/// - Hidden folders (except "." and "..")
+/// - Git repository directories (.git)
+/// - Build output directories (target)
+///
+/// The function processes files sequentially in the order they are discovered.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&Path) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to start the walk from
+/// * `callback` - An async function to process each Rust file
+///
+/// # Returns
+///
+/// Returns `Ok(())` if all files were processed successfully.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// * Directory traversal fails (e.g., permission denied)
+/// * The callback function returns an error while processing a file
+/// * A file or directory cannot be accessed
+/// * Path metadata cannot be read
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::walk_rust_files;
+/// 
+/// async fn process_rust_files() -> io::Result<()> {
+///     walk_rust_files("./src", |path| {
+///         let path = path.to_path_buf();
+///         async move {
+///             println!("Found Rust file: {}", path.display());
+///             Ok(())
+///         }
+///     }).await
+/// }
+/// ```
+pub async fn walk_rust_files<F, Fut>(dir: impl AsRef<Path>, callback: F) -> io::Result<()>
+where
+    F: Fn(&Path) -> Fut,
+    Fut: std::future::Future<Output = io::Result<()>>,
+{
+    let walker = WalkDir::new(dir).follow_links(true);
+
+    for entry in walker
+        .into_iter()
+        .filter_entry(|e| !is_excluded_by_default(e))
+        .filter_map(Result::ok)
+    {
+        let path = entry.path().to_owned();
+        if entry.file_type().is_file() && path.extension().is_some_and(|ext| ext == "rs") {
+            callback(&path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads all lines from a file at the given path.
+///
+/// This function asynchronously reads a file line by line and returns a vector
+/// containing all lines. Each line is trimmed of whitespace and newline characters.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+///
+/// # Returns
+///
+/// Returns a vector of strings, where each string is a line from the file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_lines;
+/// 
+/// async fn read_file_lines() -> io::Result<()> {
+///     let lines = read_lines(Path::new("example.txt")).await?;
+///     for line in lines {
+///         println!("{}", line);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Reads all lines from a file and returns them, requiring handling of the result"]
+pub async fn read_lines(path: &Path) -> io::Result<Vec<String>> {
+    read_lines_buffered(path, DEFAULT_BUFFER_CAPACITY).await
+}
+
+/// Reads all lines from a file like [`read_lines`], with a read buffer of the given capacity.
+///
+/// [`read_lines`] uses a buffer of [`DEFAULT_BUFFER_CAPACITY`] bytes, which suits
+/// ordinary files. For multi-gigabyte files on fast storage, a larger buffer such as
+/// 256 KiB to 1 MiB reduces the number of read calls and can improve throughput
+/// noticeably. The buffer is allocated once per call, so the cost is that much extra
+/// memory for the duration of the read; beyond a few MiB there is rarely any gain.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+/// * `capacity` - The size of the read buffer in bytes
+///
+/// # Returns
+///
+/// Returns a vector of strings, where each string is a trimmed line from the file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_lines_buffered;
+///
+/// async fn read_big_file() -> io::Result<usize> {
+///     let lines = read_lines_buffered(Path::new("huge.csv"), 1024 * 1024).await?;
+///     Ok(lines.len())
+/// }
+/// ```
+#[must_use = "Reads all lines from a file and returns them, requiring handling of the result"]
+pub async fn read_lines_buffered(path: &Path, capacity: usize) -> io::Result<Vec<String>> {
+    let file = File::open(path).await?;
+    let mut reader = BufReader::with_capacity(capacity, file);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 0 {
+        lines.push(line.trim().to_string());
+        line.clear();
+    }
+    Ok(lines)
+}
+
+/// Reads at most the first `max_lines` lines of a file, like `head -n`.
+///
+/// Lines are trimmed as by [`read_lines`]. Reading stops as soon as `max_lines` lines
+/// have been read, so only the start of a huge file is loaded, which suits previews and
+/// sampling. A shorter file simply yields fewer lines.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+/// * `max_lines` - The maximum number of lines to read
+///
+/// # Returns
+///
+/// Returns up to `max_lines` trimmed lines from the start of the file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The content read is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_lines_limited;
+///
+/// async fn preview() -> io::Result<()> {
+///     for line in read_lines_limited(Path::new("huge.csv"), 10).await? {
+///         println!("{line}");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Reads the first lines of a file and returns them, requiring handling of the result"]
+pub async fn read_lines_limited(path: &Path, max_lines: usize) -> io::Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(path).await?);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while lines.len() < max_lines && reader.read_line(&mut line).await? > 0 {
+        lines.push(line.trim().to_string());
+        line.clear();
+    }
+    Ok(lines)
+}
+
+/// Reads the lines of a list file, dropping comments and optionally blank lines.
+///
+/// Lines are trimmed as by [`read_lines`]. A line whose trimmed content starts with
+/// `comment_prefix` is dropped, so indented comments are recognized too; comments after
+/// content on the same line are kept as part of the line. This suits config-style
+/// list files with one entry per line. An empty prefix is ignored.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+/// * `comment_prefix` - The prefix marking comment lines, such as `"#"`, or `None`
+/// * `skip_blank` - Whether lines that are empty after trimming are dropped
+///
+/// # Returns
+///
+/// Returns the remaining trimmed lines, in file order.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_lines_filtered;
+///
+/// async fn read_include_list() -> io::Result<Vec<String>> {
+///     read_lines_filtered(Path::new("include.txt"), Some("#"), true).await
+/// }
+/// ```
+#[must_use = "Reads the filtered lines of a file and returns them, requiring handling of the result"]
+pub async fn read_lines_filtered(
+    path: &Path,
+    comment_prefix: Option<&str>,
+    skip_blank: bool,
+) -> io::Result<Vec<String>> {
+    let comment_prefix = comment_prefix.filter(|prefix| !prefix.is_empty());
+    let mut lines = read_lines(path).await?;
+    lines.retain(|line| {
+        let is_blank = skip_blank && line.is_empty();
+        let is_comment = comment_prefix.is_some_and(|prefix| line.starts_with(prefix));
+        !is_blank && !is_comment
+    });
+    Ok(lines)
+}
+
+/// Reads all lines from a file together with their 1-based line numbers.
+///
+/// Unlike [`read_lines`], only the line ending is removed, so indentation and blank
+/// lines are kept as they are. This suits diagnostic tools that report positions such
+/// as "line 42: ...". Use [`read_numbered_lines_with`] to trim more.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+///
+/// # Returns
+///
+/// Returns a vector of `(line_number, content)` pairs, numbered from 1.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_numbered_lines;
+///
+/// async fn find_todos() -> io::Result<()> {
+///     for (line_no, line) in read_numbered_lines(Path::new("main.rs")).await? {
+///         if line.contains("TODO") {
+///             println!("line {line_no}: {line}");
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Reads all lines from a file and returns them, requiring handling of the result"]
+pub async fn read_numbered_lines(path: &Path) -> io::Result<Vec<(usize, String)>> {
+    read_numbered_lines_with(path, TrimMode::LineEnding).await
+}
+
+/// Reads all lines from a file with their line numbers like [`read_numbered_lines`], using the given [`TrimMode`].
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+/// * `trim` - How much whitespace to remove from each line
+///
+/// # Returns
+///
+/// Returns a vector of `(line_number, content)` pairs, numbered from 1.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::{read_numbered_lines_with, TrimMode};
+///
+/// async fn print_trimmed() -> io::Result<()> {
+///     for (line_no, line) in read_numbered_lines_with(Path::new("notes.txt"), TrimMode::Both).await? {
+///         println!("{line_no:>4} {line}");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Reads all lines from a file and returns them, requiring handling of the result"]
+pub async fn read_numbered_lines_with(path: &Path, trim: TrimMode) -> io::Result<Vec<(usize, String)>> {
+    let file = File::open(path).await?;
+    let mut reader = BufReader::new(file);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 0 {
+        lines.push((lines.len() + 1, trim.apply(&line).to_string()));
+        line.clear();
+    }
+    Ok(lines)
+}
+
+/// Reads the entire content of a file into a string.
+///
+/// This function provides a convenient way to read an entire file into memory
+/// asynchronously. It's best suited for smaller files that can fit in memory.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+///
+/// # Returns
+///
+/// Returns the entire content of the file as a string.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be read
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_file_content;
+/// 
+/// async fn read_file() -> io::Result<()> {
+///     let content = read_file_content(Path::new("example.txt")).await?;
+///     println!("File content: {}", content);
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Reads the content of a file and requires handling of the result to ensure the content is retrieved"]
+pub async fn read_file_content(path: &Path) -> io::Result<String> {
+    tokio::fs::read_to_string(path).await
+}
+
+/// Reads the entire content of a file into a string, using the given [`ReadOptions`].
+///
+/// With [`ReadOptions::max_bytes`] set, the file's size is checked before anything is
+/// read, so oversized files are rejected cheaply. With [`ReadOptions::strip_bom`] set,
+/// a UTF-8 byte order mark at the start of the content is removed.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+/// * `options` - Options controlling the size limit and byte order mark handling
+///
+/// # Returns
+///
+/// Returns the content of the file as a string.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened or read
+/// - The file is larger than the limit; the error has kind `FileTooLarge` and wraps a
+///   [`FileTooLarge`] with the sizes
+/// - The file content is not valid UTF-8
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::{read_file_content_with, ReadOptions};
+///
+/// async fn read_config() -> io::Result<String> {
+///     let options = ReadOptions::new().with_max_bytes(64 * 1024).with_strip_bom(true);
+///     read_file_content_with(Path::new("config.ini"), &options).await
+/// }
+/// ```
+pub async fn read_file_content_with(path: &Path, options: &ReadOptions) -> io::Result<String> {
+    let file = File::open(path).await?;
+    options.check_size(path, file.metadata().await?.len())?;
+    let mut bytes = Vec::new();
+    file.take(options.read_limit()).read_to_end(&mut bytes).await?;
+    options.decode(path, bytes)
+}
+
+/// Reads the entire content of a file into a string, replacing invalid UTF-8.
+///
+/// [`read_file_content`] fails on the first invalid byte, which loses an otherwise
+/// readable file. This reader instead replaces each invalid sequence with the
+/// replacement character U+FFFD (`�`) and keeps going. The tradeoff is fidelity: the
+/// replaced bytes are gone, so the result should not be written back in place of the
+/// original. Use it for inspecting logs and similar mostly-text data.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to read
+///
+/// # Returns
+///
+/// Returns the content of the file, with invalid UTF-8 sequences replaced.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be opened or read.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::read_file_content_lossy;
+///
+/// async fn count_errors() -> io::Result<usize> {
+///     let log = read_file_content_lossy(Path::new("server.log")).await?;
+///     Ok(log.lines().filter(|line| line.contains("ERROR")).count())
+/// }
+/// ```
+pub async fn read_file_content_lossy(path: &Path) -> io::Result<String> {
+    read_file_content_with(path, &ReadOptions::new().with_lossy(true)).await
+}
+
+/// Memory-maps a file for zero-copy reading.
+///
+/// Instead of copying the file into a heap allocation like [`read_file_content`], the
+/// file is mapped into the address space and pages are loaded lazily by the operating
+/// system. This is most useful for large, read-mostly files. Use [`mmap_as_str`] to get
+/// a validated `&str` view of the mapping.
+///
+/// # Safety Caveats
+///
+/// The mapping reflects the file on disk. If another process truncates or modifies the
+/// file while it is mapped, reads through the mapping may observe the changes or, on
+/// truncation, cause the process to receive `SIGBUS`. Only map files that are not
+/// modified concurrently.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file to map
+///
+/// # Returns
+///
+/// Returns a read-only memory map of the whole file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened
+/// - The file cannot be mapped into memory
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::{mmap_as_str, read_file_mmap};
+///
+/// async fn count_lines() -> io::Result<usize> {
+///     let map = read_file_mmap(Path::new("large.log")).await?;
+///     Ok(mmap_as_str(&map)?.lines().count())
+/// }
+/// ```
+#[cfg(feature = "mmap")]
+#[must_use = "Maps a file into memory and requires handling of the result"]
+pub async fn read_file_mmap(path: &Path) -> io::Result<Mmap> {
+    let file = File::open(path).await?.into_std().await;
+    // SAFETY: the mapping is read-only; the caveats about concurrent modification of
+    // the underlying file are documented above.
+    unsafe { Mmap::map(&file) }
+}
+
+/// Returns a UTF-8 view of a memory-mapped file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` of kind `InvalidData` if the mapped content is not valid UTF-8.
+#[cfg(feature = "mmap")]
+pub fn mmap_as_str(map: &Mmap) -> io::Result<&str> {
+    std::str::from_utf8(map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes content to a file at the specified path.
+///
+/// This function asynchronously writes a string to a file. If the file already exists,
+/// it will be overwritten. If the file doesn't exist, it will be created.
+///
+/// An existing file is truncated and rewritten in place rather than replaced, so it
+/// keeps its permissions, such as the executable bit of a regenerated script.
+///
+/// # Arguments
+///
+/// * `path` - The path where the file should be written
+/// * `content` - The string content to write to the file
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the write was successful.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be created
+/// - The file cannot be written to
+/// - The parent directory doesn't exist
+/// - Permission is denied
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::write_to_file;
+/// 
+/// async fn write_file() -> io::Result<()> {
+///     write_to_file(
+///         Path::new("output.txt"),
+///         "Hello, World!"
+///     ).await
+/// }
+/// ```
+#[must_use = "Writes content to a file and requires handling of the result to ensure data is saved"]
+pub async fn write_to_file(path: &Path, content: &str) -> io::Result<()> {
+    let mut file = File::create(path).await?;
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await
+}
+
+/// Writes content to a file durably and verifies the size that landed on disk.
+///
+/// Like [`write_to_file`], but after writing and flushing, the file is synced with
+/// `sync_all`, so the content survives a crash or power loss once this returns, and
+/// its on-disk size is read back and compared with the length of `content`. Use it for
+/// output that cannot tolerate a silent short write.
+///
+/// # Arguments
+///
+/// * `path` - The path where the file should be written
+/// * `content` - The string content to write to the file
+///
+/// # Returns
+///
+/// Returns the number of bytes written.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be created, written or synced, or with
+/// `ErrorKind::WriteZero` if its size on disk does not match the content written.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::write_to_file_verified;
+///
+/// async fn save_manifest() -> std::io::Result<()> {
+///     let written = write_to_file_verified(Path::new("manifest.json"), "{}").await?;
+///     println!("Wrote {written} bytes");
+///     Ok(())
+/// }
+/// ```
+pub async fn write_to_file_verified(path: &Path, content: &str) -> io::Result<u64> {
+    let mut file = File::create(path).await?;
+    file.write_all(content.as_bytes()).await?;
+    file.flush().await?;
+    file.sync_all().await?;
+    let expected = content.len() as u64;
+    let written = file.metadata().await?.len();
+    if written != expected {
+        return Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            format!(
+                "{}: expected {expected} bytes on disk, found {written}",
+                path.display()
+            ),
+        ));
+    }
+    Ok(written)
+}
+
+/// Writes content to a new temporary file that is deleted when the returned guard drops.
+///
+/// The file is created in the system's temporary directory with a unique name ending
+/// in `suffix`, so tools that recognize files by extension handle it correctly. Pass
+/// the guard's path to an external command or an editor, and keep the guard alive
+/// until they are done with the file.
+///
+/// # Arguments
+///
+/// * `content` - The bytes to write to the file
+/// * `suffix` - Appended verbatim to the file name, such as `".json"`, or `None`
+///
+/// # Returns
+///
+/// Returns a [`TempPath`] guard that dereferences to the file's path and deletes the
+/// file when dropped.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the temporary file cannot be created or written.
+///
+/// # Examples
+///
+/// ```
+/// use xio::write_temp;
+///
+/// async fn run_formatter() -> std::io::Result<()> {
+///     let input = write_temp("fn main() {}", Some(".rs")).await?;
+///     let status = tokio::process::Command::new("rustfmt").arg(&*input).status().await?;
+///     println!("rustfmt exited with {status}");
+///     Ok(())
+/// }
+/// ```
+pub async fn write_temp(content: impl AsRef<[u8]>, suffix: Option<&str>) -> io::Result<TempPath> {
+    let temp_path = tempfile::Builder::new()
+        .suffix(suffix.unwrap_or_default())
+        .tempfile()?
+        .into_temp_path();
+    tokio::fs::write(&temp_path, content).await?;
+    debug!("Wrote temporary file {}", temp_path.display());
+    Ok(temp_path)
+}
+
+/// Writes content to a file, keeping a backup of the previous content.
+///
+/// If `path` already exists, it is first copied to a sibling path with `backup_suffix`
+/// appended to its file name (for example `notes.txt` becomes `notes.txt.bak`),
+/// replacing any older backup. The new content is then written to a temporary file and
+/// atomically renamed over `path`, so a crash leaves either the old or the new content
+/// in place, and never a partially written file next to the backup.
+///
+/// # Arguments
+///
+/// * `path` - The path where the file should be written
+/// * `content` - The string content to write to the file
+/// * `backup_suffix` - The suffix appended to the file name of the backup (e.g. ".bak")
+///
+/// # Returns
+///
+/// Returns `true` if a backup was created, or `false` if `path` did not exist yet.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The existing file cannot be copied to the backup path
+/// - The new content cannot be written or renamed into place
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::write_to_file_with_backup;
+///
+/// async fn update_config() -> io::Result<()> {
+///     let backed_up = write_to_file_with_backup(Path::new("config.toml"), "debug = true\n", ".bak").await?;
+///     if backed_up {
+///         println!("Previous config saved to config.toml.bak");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn write_to_file_with_backup(
+    path: &Path,
+    content: &str,
+    backup_suffix: &str,
+) -> io::Result<bool> {
+    let backed_up = if tokio::fs::try_exists(path).await? {
+        let mut backup_name = path.file_name().unwrap_or_default().to_os_string();
+        backup_name.push(backup_suffix);
+        let backup_path = path.with_file_name(backup_name);
+        tokio::fs::copy(path, &backup_path).await?;
+        debug!("Backed up {} to {}", path.display(), backup_path.display());
+        true
+    } else {
+        false
+    };
+
+    let mut staging = StagingFile::for_path(path)?;
+    staging.write_all(content.as_bytes()).await?;
+    staging.commit(path).await?;
+    Ok(backed_up)
+}
+
+/// Writes content to a file and hands the content back to the caller.
+///
+/// This behaves like [`write_to_file`] but takes ownership of the content and returns
+/// it once the write succeeded, so pipelines that both persist and forward their
+/// output don't need to clone it.
+///
+/// # Arguments
+///
+/// * `path` - The path where the file should be written
+/// * `content` - The content to write, returned again on success
+///
+/// # Returns
+///
+/// Returns the written content if the write was successful.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be created
+/// - The file cannot be written to
+/// - The parent directory doesn't exist
+/// - Permission is denied
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::write_and_return;
+///
+/// async fn render_report() -> io::Result<()> {
+///     let report = write_and_return(Path::new("report.txt"), format!("{} files", 42)).await?;
+///     println!("{report}");
+///     Ok(())
+/// }
+/// ```
+#[must_use = "Writes content to a file and returns it, requiring handling of the result"]
+pub async fn write_and_return(path: &Path, content: impl Into<String>) -> io::Result<String> {
+    let content = content.into();
+    write_to_file(path, &content).await?;
+    Ok(content)
+}
+
+/// Writes lines to a file, each followed by a newline.
+///
+/// This is the counterpart of [`read_lines`]. The lines are written to a temporary file
+/// next to `path` and atomically renamed into place, so readers never observe a
+/// partially written file.
+///
+/// # Arguments
+///
+/// * `path` - The path where the file should be written
+/// * `lines` - The lines to write, without line endings
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the file has been moved into place.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The parent directory doesn't exist
+/// - The temporary file cannot be written or renamed to `path`
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::write_lines;
+///
+/// async fn save_list() -> io::Result<()> {
+///     write_lines(Path::new("files.txt"), &["a.txt", "b.txt"]).await
+/// }
+/// ```
+pub async fn write_lines<S: AsRef<str>>(path: &Path, lines: &[S]) -> io::Result<()> {
+    let mut staging = StagingFile::for_path(path)?;
+    for line in lines {
+        staging.write_all(line.as_ref().as_bytes()).await?;
+        staging.write_all(b"\n").await?;
+    }
+    staging.commit(path).await
+}
+
+/// Reads a JSON file and deserializes it.
+///
+/// # Type Parameters
+///
+/// * `T` - The type to deserialize into
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON file to read
+///
+/// # Returns
+///
+/// Returns the deserialized value.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` naming the path if the file cannot be read or does not
+/// contain valid JSON for `T`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::{read_json, anyhow};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// async fn load() -> anyhow::Result<Config> {
+///     read_json(Path::new("config.json")).await
+/// }
+/// ```
+#[cfg(feature = "json")]
+pub async fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let content = read_file_content(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse JSON in {}", path.display()))
+}
+
+/// Serializes a value as JSON and writes it to a file.
+///
+/// The file ends with a newline. With `pretty` set, the JSON is indented for readability;
+/// otherwise it is written on a single line.
+///
+/// # Type Parameters
+///
+/// * `T` - The type to serialize
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to write
+/// * `value` - The value to serialize
+/// * `pretty` - Whether to indent the output
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the file has been written.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` naming the path if `value` cannot be serialized or the
+/// file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use std::path::Path;
+/// use xio::{write_json, anyhow};
+///
+/// async fn save(settings: &BTreeMap<String, u32>) -> anyhow::Result<()> {
+///     write_json(Path::new("settings.json"), settings, true).await
+/// }
+/// ```
+#[cfg(feature = "json")]
+pub async fn write_json<T: serde::Serialize + ?Sized>(
+    path: &Path,
+    value: &T,
+    pretty: bool,
+) -> anyhow::Result<()> {
+    let mut content = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+    .with_context(|| format!("Failed to serialize JSON for {}", path.display()))?;
+    content.push('\n');
+    write_to_file(path, &content)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reads a TOML file and deserializes it.
+///
+/// # Type Parameters
+///
+/// * `T` - The type to deserialize into
+///
+/// # Arguments
+///
+/// * `path` - The path of the TOML file to read
+///
+/// # Returns
+///
+/// Returns the deserialized value.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` naming the path if the file cannot be read, or naming the
+/// path, line and column if it does not contain valid TOML for `T`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::{read_toml, anyhow};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// async fn load() -> anyhow::Result<Config> {
+///     read_toml(Path::new("config.toml")).await
+/// }
+/// ```
+#[cfg(feature = "toml")]
+pub async fn read_toml<T: serde::de::DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let content = read_file_content(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).map_err(|e| {
+        let location = e.span().map_or_else(String::new, |span| {
+            let before = &content[..span.start];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+            format!(" at line {line}, column {column}")
+        });
+        anyhow::Error::new(e).context(format!("Failed to parse TOML in {}{location}", path.display()))
+    })
+}
+
+/// Serializes a value as TOML and writes it to a file.
+///
+/// # Type Parameters
+///
+/// * `T` - The type to serialize
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to write
+/// * `value` - The value to serialize
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the file has been written.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` naming the path if `value` cannot be represented as TOML
+/// (for example, a bare sequence at the top level) or the file cannot be written.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use std::path::Path;
+/// use xio::{write_toml, anyhow};
+///
+/// async fn save(settings: &BTreeMap<String, u32>) -> anyhow::Result<()> {
+///     write_toml(Path::new("settings.toml"), settings).await
+/// }
+/// ```
+#[cfg(feature = "toml")]
+pub async fn write_toml<T: serde::Serialize + ?Sized>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let content = toml::to_string(value)
+        .with_context(|| format!("Failed to serialize TOML for {}", path.display()))?;
+    write_to_file(path, &content)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Streams the lines of a file through a transformation and writes the result.
+///
+/// Each line of `src` is passed to `transform` without its line ending. Returning
+/// `Some(line)` writes the transformed line (followed by the original line ending),
+/// returning `None` drops the line. Only one line is held in memory at a time, which
+/// makes this suitable for filtering logs or rewriting very large files.
+///
+/// The output is first written to a temporary file next to `dst` and then renamed
+/// into place, so `dst` may be the same path as `src` to transform a file in place.
+///
+/// # Arguments
+///
+/// * `src` - The path of the file to read
+/// * `dst` - The path to write the transformed content to (may equal `src`)
+/// * `transform` - A function mapping each line to its replacement, or `None` to drop it
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the transformed content has been moved into place.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The source file cannot be opened or read
+/// - A line is not valid UTF-8
+/// - The temporary file cannot be written or renamed to `dst`
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::transform_file_lines;
+///
+/// async fn strip_comments() -> io::Result<()> {
+///     let path = Path::new("config.txt");
+///     transform_file_lines(path, path, |line| {
+///         (!line.starts_with('#')).then(|| line.to_string())
+///     }).await
+/// }
+/// ```
+pub async fn transform_file_lines<F>(src: &Path, dst: &Path, mut transform: F) -> io::Result<()>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut reader = BufReader::new(File::open(src).await?);
+    let temp_path = temp_path_for(dst);
+    let result = async {
+        let mut writer = BufWriter::new(File::create(&temp_path).await?);
+        let mut line = String::new();
+        while reader.read_line(&mut line).await? > 0 {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let ending = &line[content.len()..];
+            if let Some(transformed) = transform(content) {
+                writer.write_all(transformed.as_bytes()).await?;
+                writer.write_all(ending.as_bytes()).await?;
+            }
+            line.clear();
+        }
+        writer.flush().await?;
+        preserve_permissions(dst, &temp_path).await?;
+        tokio::fs::rename(&temp_path, dst).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result
+}
+
+/// Removes duplicate lines from a file.
+///
+/// With `preserve_order` set, the first occurrence of each line is kept in its original
+/// position. Otherwise the remaining lines are written back in sorted order. Lines are
+/// compared without their line endings, and the file is only rewritten (atomically, via
+/// [`write_lines`]) if its content changes; rewritten files use `\n` line endings.
+///
+/// The whole file is held in memory, and order-preserving mode additionally keeps a
+/// `HashSet` of every distinct line, so memory use grows with the size of the file. For
+/// files that are already sorted, [`dedup_sorted_lines_in_file`] streams the content and
+/// only keeps the previous line in memory.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to deduplicate in place
+/// * `preserve_order` - Whether to keep first-occurrence order instead of sorting
+///
+/// # Returns
+///
+/// Returns the number of duplicate lines removed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened or read
+/// - A line is not valid UTF-8
+/// - The deduplicated content cannot be written back
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::dedup_lines_in_file;
+///
+/// async fn clean_path_list() -> io::Result<()> {
+///     let removed = dedup_lines_in_file(Path::new("paths.txt"), true).await?;
+///     println!("Removed {removed} duplicate paths");
+///     Ok(())
+/// }
+/// ```
+pub async fn dedup_lines_in_file(path: &Path, preserve_order: bool) -> io::Result<usize> {
+    let mut reader = BufReader::new(File::open(path).await?);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 0 {
+        lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+        line.clear();
+    }
+
+    let total = lines.len();
+    let mut reordered = false;
+    if preserve_order {
+        let mut seen = HashSet::new();
+        lines.retain(|line| seen.insert(line.clone()));
+    } else {
+        reordered = !lines.is_sorted();
+        lines.sort_unstable();
+        lines.dedup();
+    }
+
+    let removed = total - lines.len();
+    if removed > 0 || reordered {
+        write_lines(path, &lines).await?;
+    }
+    Ok(removed)
+}
+
+/// Removes consecutive duplicate lines from a file, like `uniq`.
+///
+/// For a sorted file this removes every duplicate, while only holding the previous line
+/// in memory, which makes it suitable for files too large for [`dedup_lines_in_file`].
+/// Lines are compared without their line endings and copied byte for byte otherwise.
+/// The content is streamed into a temporary file, which replaces `path` only if at
+/// least one line was removed.
+///
+/// # Arguments
+///
+/// * `path` - The path of the sorted file to deduplicate in place
+///
+/// # Returns
+///
+/// Returns the number of duplicate lines removed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - The file cannot be opened or read
+/// - A line is not valid UTF-8
+/// - The temporary file cannot be written or renamed to `path`
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::dedup_sorted_lines_in_file;
+///
+/// async fn clean_sorted_index() -> io::Result<()> {
+///     let removed = dedup_sorted_lines_in_file(Path::new("index.txt")).await?;
+///     println!("Removed {removed} duplicate entries");
+///     Ok(())
+/// }
+/// ```
+pub async fn dedup_sorted_lines_in_file(path: &Path) -> io::Result<usize> {
+    let mut reader = BufReader::new(File::open(path).await?);
+    let mut staging = StagingFile::for_path(path)?;
+    let mut previous: Option<String> = None;
+    let mut removed = 0;
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 0 {
+        let content = line.trim_end_matches(['\r', '\n']);
+        if previous.as_deref() == Some(content) {
+            removed += 1;
+        } else {
+            staging.write_all(line.as_bytes()).await?;
+            previous = Some(content.to_string());
+        }
+        line.clear();
+    }
+
+    if removed > 0 {
+        staging.commit(path).await?;
+    } else {
+        staging.discard()?;
+    }
+    Ok(removed)
+}
+
+/// Makes a file end with exactly one newline.
+///
+/// Missing final newlines are added and extra blank lines at the end are removed. The
+/// newline added is `\r\n` if the file already uses CRLF line endings, `\n`
+/// otherwise. An empty file is left empty. The file is rewritten atomically, and only
+/// if its content changes, so untouched files keep their modification time.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to fix in place
+///
+/// # Returns
+///
+/// Returns `true` if the file was changed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be read, is not valid UTF-8, or cannot be
+/// rewritten.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::ensure_trailing_newline;
+///
+/// async fn fix_file() -> io::Result<()> {
+///     if ensure_trailing_newline(Path::new("src/main.rs")).await? {
+///         println!("Fixed final newline");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn ensure_trailing_newline(path: &Path) -> io::Result<bool> {
+    rewrite_if_changed(path, |content| {
+        if content.is_empty() {
+            return String::new();
+        }
+        let body = content.trim_end_matches(['\r', '\n']);
+        let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+        format!("{body}{newline}")
+    })
+    .await
+}
+
+/// Removes trailing whitespace from every line of a file.
+///
+/// Spaces, tabs and other whitespace before each line ending are removed; the line
+/// endings themselves are kept as they are. The file is rewritten atomically, and only
+/// if its content changes, so untouched files keep their modification time.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to fix in place
+///
+/// # Returns
+///
+/// Returns `true` if the file was changed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be read, is not valid UTF-8, or cannot be
+/// rewritten.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use xio::{strip_trailing_whitespace, walk_rust_files};
+///
+/// async fn fix_whitespace() -> io::Result<()> {
+///     walk_rust_files("./", |path| {
+///         let path = path.to_path_buf();
+///         async move {
+///             strip_trailing_whitespace(&path).await?;
+///             Ok(())
+///         }
+///     }).await
+/// }
+/// ```
+pub async fn strip_trailing_whitespace(path: &Path) -> io::Result<bool> {
+    rewrite_if_changed(path, |content| {
+        let mut fixed = String::with_capacity(content.len());
+        for line in content.split_inclusive('\n') {
+            let body = line.trim_end_matches(['\r', '\n']);
+            fixed.push_str(body.trim_end());
+            fixed.push_str(&line[body.len()..]);
+        }
+        fixed
+    })
+    .await
+}
+
+/// Applies `fix` to the content of a file and writes the result back only if it differs
+async fn rewrite_if_changed(path: &Path, fix: impl FnOnce(&str) -> String) -> io::Result<bool> {
+    let content = tokio::fs::read_to_string(path).await?;
+    let fixed = fix(&content);
+    if fixed == content {
+        return Ok(false);
+    }
+    let mut staging = StagingFile::for_path(path)?;
+    staging.write_all(fixed.as_bytes()).await?;
+    staging.commit(path).await?;
+    debug!("Rewrote {}", path.display());
+    Ok(true)
+}
+
+/// Splits a file into numbered chunks of at most `lines_per_chunk` lines each.
+///
+/// This is the single-file counterpart of `split -l`: `src` is streamed line by line
+/// into `out_dir/{prefix}_0000`, `out_dir/{prefix}_0001`, and so on, with the last chunk
+/// holding whatever lines remain. Lines are copied byte for byte, so line endings
+/// (`\n` or `\r\n`) and a missing final newline are preserved, and the content does not
+/// need to be valid UTF-8. `out_dir` is created if it does not exist.
+///
+/// # Arguments
+///
+/// * `src` - The path of the file to split
+/// * `lines_per_chunk` - The maximum number of lines per chunk (must be greater than zero)
+/// * `out_dir` - The directory to write the chunks into
+/// * `prefix` - The file name prefix of the chunks
+///
+/// # Returns
+///
+/// Returns the paths of the chunks written, in order. An empty source produces no chunks.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - `lines_per_chunk` is zero
+/// - The source file cannot be opened or read
+/// - `out_dir` cannot be created or a chunk cannot be written
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::split_file_by_lines;
+///
+/// async fn shard_dataset() -> io::Result<()> {
+///     let chunks = split_file_by_lines(Path::new("data.jsonl"), 10_000, Path::new("shards"), "data").await?;
+///     println!("Wrote {} shards", chunks.len());
+///     Ok(())
+/// }
+/// ```
+pub async fn split_file_by_lines(
+    src: &Path,
+    lines_per_chunk: usize,
+    out_dir: &Path,
+    prefix: &str,
+) -> io::Result<Vec<PathBuf>> {
+    if lines_per_chunk == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "lines_per_chunk must be greater than zero",
+        ));
+    }
+
+    let mut reader = BufReader::new(File::open(src).await?);
+    crate::fs::ensure_dir(out_dir).await?;
+
+    let mut chunks = Vec::new();
+    let mut writer: Option<BufWriter<File>> = None;
+    let mut lines_in_chunk = 0;
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        if lines_in_chunk == lines_per_chunk {
+            if let Some(mut full) = writer.take() {
+                full.flush().await?;
+            }
+            lines_in_chunk = 0;
+        }
+        if writer.is_none() {
+            let path = chunk_path(out_dir, prefix, chunks.len());
+            debug!("Writing chunk: {}", path.display());
+            writer = Some(BufWriter::new(File::create(&path).await?));
+            chunks.push(path);
+        }
+        if let Some(writer) = writer.as_mut() {
+            writer.write_all(&line).await?;
+        }
+        lines_in_chunk += 1;
+        line.clear();
+    }
+    if let Some(mut last) = writer {
+        last.flush().await?;
+    }
+
+    info!("Split {} into {} chunks", src.display(), chunks.len());
+    Ok(chunks)
+}
+
+/// Splits a file into numbered chunks of at most `bytes_per_chunk` bytes each.
+///
+/// This is the size-based counterpart of [`split_file_by_lines`], useful for uploading
+/// to services with a size cap. `src` is read in buffers and written to
+/// `out_dir/{prefix}_0000`, `out_dir/{prefix}_0001`, and so on; every chunk except the
+/// last is exactly `bytes_per_chunk` bytes long. The content is treated as raw bytes,
+/// so binary files are supported. `out_dir` is created if it does not exist.
+///
+/// # Arguments
+///
+/// * `src` - The path of the file to split
+/// * `bytes_per_chunk` - The maximum size of each chunk in bytes (must be greater than zero)
+/// * `out_dir` - The directory to write the chunks into
+/// * `prefix` - The file name prefix of the chunks
+///
+/// # Returns
+///
+/// Returns the paths of the chunks written, in order. An empty source produces no chunks.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - `bytes_per_chunk` is zero
+/// - The source file cannot be opened or read
+/// - `out_dir` cannot be created or a chunk cannot be written
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::split_file_by_bytes;
+///
+/// async fn prepare_upload() -> io::Result<()> {
+///     let chunks = split_file_by_bytes(Path::new("backup.tar"), 100 * 1024 * 1024, Path::new("upload"), "backup").await?;
+///     println!("Wrote {} parts", chunks.len());
+///     Ok(())
+/// }
+/// ```
+pub async fn split_file_by_bytes(
+    src: &Path,
+    bytes_per_chunk: u64,
+    out_dir: &Path,
+    prefix: &str,
+) -> io::Result<Vec<PathBuf>> {
+    if bytes_per_chunk == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "bytes_per_chunk must be greater than zero",
+        ));
+    }
+
+    let mut reader = BufReader::new(File::open(src).await?);
+    crate::fs::ensure_dir(out_dir).await?;
+
+    let mut chunks = Vec::new();
+    // Only start a chunk when there is data left, so no empty chunk is written
+    while !reader.fill_buf().await?.is_empty() {
+        let path = chunk_path(out_dir, prefix, chunks.len());
+        debug!("Writing chunk: {}", path.display());
+        let mut writer = File::create(&path).await?;
+        tokio::io::copy_buf(&mut (&mut reader).take(bytes_per_chunk), &mut writer).await?;
+        writer.flush().await?;
+        chunks.push(path);
+    }
+
+    info!("Split {} into {} chunks", src.display(), chunks.len());
+    Ok(chunks)
+}
+
+/// Concatenates files into a single output file.
+///
+/// Each input is streamed into `output` in order, as raw bytes, so this reassembles the
+/// chunks written by [`split_file_by_bytes`] or [`split_file_by_lines`] and also works
+/// for merging logs. When `separator` is given, it is written between consecutive
+/// inputs (not after the last one).
+///
+/// The output is first written to a temporary file next to `output` and then renamed
+/// into place, so `output` may also be one of the inputs.
+///
+/// # Arguments
+///
+/// * `inputs` - The files to concatenate, in order
+/// * `output` - The path of the file to write
+/// * `separator` - Optional bytes to insert between inputs, such as `b"\n"`
+///
+/// # Returns
+///
+/// Returns the number of bytes written to `output`, including separators.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if an input cannot be read or the output cannot be written
+/// or renamed into place.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use std::io;
+/// use xio::concat_files;
+///
+/// async fn merge_logs() -> io::Result<()> {
+///     let logs = vec![PathBuf::from("monday.log"), PathBuf::from("tuesday.log")];
+///     let bytes = concat_files(&logs, Path::new("week.log"), Some(b"\n")).await?;
+///     println!("Wrote {bytes} bytes");
+///     Ok(())
+/// }
+/// ```
+pub async fn concat_files(
+    inputs: &[PathBuf],
+    output: &Path,
+    separator: Option<&[u8]>,
+) -> io::Result<u64> {
+    let temp_path = temp_path_for(output);
+    let result = async {
+        let mut writer = BufWriter::new(File::create(&temp_path).await?);
+        let mut written = 0;
+        for (index, input) in inputs.iter().enumerate() {
+            if index > 0
+                && let Some(separator) = separator
+            {
+                writer.write_all(separator).await?;
+                written += separator.len() as u64;
+            }
+            let mut reader = File::open(input).await?;
+            written += tokio::io::copy(&mut reader, &mut writer).await?;
+        }
+        writer.flush().await?;
+        preserve_permissions(output, &temp_path).await?;
+        tokio::fs::rename(&temp_path, output).await?;
+        Ok(written)
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result
+}
+
+/// Copies a file, keeping the source's modification and access times and permissions.
+///
+/// `tokio::fs::copy` gives the copy the current time as its modification time, which
+/// breaks tools that go by file age. This copies the content, then sets the source's
+/// modification and access times and permissions on `dst`. An existing `dst` is
+/// overwritten.
+///
+/// # Arguments
+///
+/// * `src` - The file to copy
+/// * `dst` - The path to copy to
+///
+/// # Returns
+///
+/// Returns the number of bytes copied.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the source cannot be read, the destination cannot be
+/// written, or the times or permissions cannot be read or set.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::copy_file_preserving;
+///
+/// async fn archive_log() -> io::Result<()> {
+///     copy_file_preserving(Path::new("app.log"), Path::new("archive/app.log")).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn copy_file_preserving(src: &Path, dst: &Path) -> io::Result<u64> {
+    let copied = tokio::fs::copy(src, dst).await?;
+    let metadata = tokio::fs::metadata(src).await?;
+    let times = std::fs::FileTimes::new()
+        .set_accessed(metadata.accessed()?)
+        .set_modified(metadata.modified()?);
+    let target = dst.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        // A read-only handle suffices on Unix, so read-only copies can be updated too
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(cfg!(windows))
+            .open(target)?
+            .set_times(times)
+    })
+    .await
+    .map_err(io::Error::other)??;
+    tokio::fs::set_permissions(dst, metadata.permissions()).await?;
+    debug!("Copied {} to {} preserving times", src.display(), dst.display());
+    Ok(copied)
+}
+
+/// The size of the chunks read and written by [`copy_file_with_progress`]
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Copies a file, reporting the number of bytes copied after each chunk.
+///
+/// Unlike `tokio::fs::copy`, this gives feedback while copying large files:
+/// `on_progress` is called with the bytes copied so far and the total size taken from
+/// the source's metadata, starting with `(0, total)` and ending with
+/// `(total, total)`. An existing `dst` is overwritten, unless it is the source itself:
+/// copying a file onto itself is rejected before anything is written. After copying,
+/// the size of `dst` is checked against the source.
+///
+/// # Arguments
+///
+/// * `src` - The file to copy
+/// * `dst` - The path to copy to
+/// * `on_progress` - Called with `(copied, total)` as the copy advances
+///
+/// # Returns
+///
+/// Returns the number of bytes in the copied file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - `src` and `dst` are the same file (`ErrorKind::InvalidInput`)
+/// - The source cannot be read or the destination cannot be written
+/// - The destination's final size differs from the source's
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::copy_file_with_progress;
+///
+/// async fn copy_image() -> io::Result<()> {
+///     copy_file_with_progress(Path::new("disk.img"), Path::new("backup.img"), |copied, total| {
+///         println!("{copied}/{total} bytes");
+///     }).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn copy_file_with_progress(
+    src: &Path,
+    dst: &Path,
+    on_progress: impl Fn(u64, u64),
+) -> io::Result<u64> {
+    copy_in_chunks(src, dst, false, on_progress).await
+}
+
+/// Resumes an interrupted copy like [`copy_file_with_progress`].
+///
+/// If `dst` already holds a prefix of the source, only the remaining bytes are
+/// appended and progress starts from the existing size. The existing bytes are assumed
+/// to be a correct prefix; they are not compared with the source. A `dst` that is
+/// missing or larger than the source is copied from scratch.
+///
+/// # Arguments
+///
+/// * `src` - The file to copy
+/// * `dst` - The partially copied file to complete
+/// * `on_progress` - Called with `(copied, total)` as the copy advances
+///
+/// # Returns
+///
+/// Returns the number of bytes in the copied file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - `src` and `dst` are the same file (`ErrorKind::InvalidInput`)
+/// - The source cannot be read or the destination cannot be written
+/// - The destination's final size differs from the source's
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::resume_copy_file_with_progress;
+///
+/// async fn finish_copy() -> io::Result<()> {
+///     resume_copy_file_with_progress(Path::new("disk.img"), Path::new("backup.img"), |copied, total| {
+///         println!("{copied}/{total} bytes");
+///     }).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn resume_copy_file_with_progress(
+    src: &Path,
+    dst: &Path,
+    on_progress: impl Fn(u64, u64),
+) -> io::Result<u64> {
+    copy_in_chunks(src, dst, true, on_progress).await
+}
+
+/// Shared implementation of the progress-reporting copies.
+async fn copy_in_chunks(
+    src: &Path,
+    dst: &Path,
+    resume: bool,
+    on_progress: impl Fn(u64, u64),
+) -> io::Result<u64> {
+    // Creating `dst` would truncate the source before it is read
+    if fs::is_same_file(src, dst).unwrap_or(false) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot copy {} onto itself", src.display()),
+        ));
+    }
+
+    let mut reader = File::open(src).await?;
+    let total = reader.metadata().await?.len();
+
+    let existing = if resume {
+        match tokio::fs::metadata(dst).await {
+            Ok(metadata) if metadata.len() <= total => metadata.len(),
+            Ok(_) => 0,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        }
+    } else {
+        0
+    };
+    let mut writer = if existing > 0 {
+        debug!("Resuming copy of {} at byte {existing}", src.display());
+        reader.seek(io::SeekFrom::Start(existing)).await?;
+        tokio::fs::OpenOptions::new().append(true).open(dst).await?
+    } else {
+        File::create(dst).await?
+    };
+
+    let mut copied = existing;
+    on_progress(copied, total);
+    let mut buffer = vec![0; COPY_CHUNK_SIZE];
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read]).await?;
+        copied += read as u64;
+        on_progress(copied, total);
+    }
+    writer.flush().await?;
+
+    let written = tokio::fs::metadata(dst).await?.len();
+    if written != total {
+        return Err(io::Error::other(format!(
+            "Copied {written} bytes to {} but {} has {total} bytes",
+            dst.display(),
+            src.display()
+        )));
+    }
+    Ok(written)
+}
+
+/// Returns the path of the chunk with the given index, e.g. `out_dir/prefix_0003`.
+fn chunk_path(out_dir: &Path, prefix: &str, index: usize) -> PathBuf {
+    out_dir.join(format!("{prefix}_{index:04}"))
+}
+
+/// Returns a unique temporary path in the same directory as `path`.
+///
+/// Keeping the temporary file next to its destination guarantees both live on the
+/// same filesystem, so the final rename is atomic.
+fn temp_path_for(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path.file_name().map_or_else(Default::default, |name| name.to_string_lossy());
+    let temp_name = format!(
+        ".tmp-{}-{}-{file_name}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    path.with_file_name(temp_name)
+}
+
+/// Gives `replacement` the permissions of `original`, if `original` exists.
+///
+/// Writers that replace a file by renaming a new one over it call this first, so the
+/// replaced file's permissions survive the rename.
+pub(crate) async fn preserve_permissions(original: &Path, replacement: &Path) -> io::Result<()> {
+    match tokio::fs::metadata(original).await {
+        Ok(metadata) => tokio::fs::set_permissions(replacement, metadata.permissions()).await,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Deletes files with a specific extension in a directory and its subdirectories.
+///
+/// This function recursively walks through a directory tree and deletes all files
+/// that match the specified extension. The deletion is performed concurrently
+/// using Tokio tasks for better performance.
+///
+/// # Arguments
+///
+/// * `target_dir` - The root directory to start the deletion from
+/// * `extension` - The file extension to match (with or without the leading dot)
+///
+/// # Returns
+///
+/// Returns `Ok(())` if all matching files were successfully deleted or if no matching
+/// files were found.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if:
+/// - Directory traversal fails
+/// - File deletion fails
+/// - Permission is denied
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::delete_files_with_extension;
+/// 
+/// async fn cleanup_temp_files() -> io::Result<()> {
+///     delete_files_with_extension(Path::new("./"), "tmp").await
+/// }
+/// ```
+#[must_use = "Deletes files with a specific extension and requires handling of the result to ensure proper file deletion"]
+pub async fn delete_files_with_extension(target_dir: &Path, extension: &str) -> io::Result<()> {
+    delete_files_with_extension_with_progress(target_dir, extension, &NoProgress).await
+}
+
+/// Recursively deletes all files with a specific extension, reporting progress.
+///
+/// This behaves like [`delete_files_with_extension`], calling `progress` with the number
+/// of matching files before deletion starts and advancing it as each file is removed.
+/// Files that fail to delete are logged and not counted, so the progress stays short
+/// of the total while such files remain.
+///
+/// The total can differ from [`count_files_with_extension`](fs::count_files_with_extension):
+/// deletion matches extensions case-insensitively and, like
+/// [`delete_files_with_extension`] always has, does not skip hidden, git or target
+/// directories, while the counter is case-sensitive and applies the default filter.
+///
+/// # Arguments
+///
+/// * `target_dir` - The root directory to start the deletion from
+/// * `extension` - The file extension to match (with or without the leading dot)
+/// * `progress` - Receives progress updates
+///
+/// # Returns
+///
+/// Returns `Ok(())` once every matching file has been processed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if a deletion task panics or is cancelled.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::{delete_files_with_extension_with_progress, NoProgress};
+///
+/// async fn cleanup_temp_files() -> std::io::Result<()> {
+///     delete_files_with_extension_with_progress(Path::new("./"), "tmp", &NoProgress).await
+/// }
+/// ```
+#[must_use = "Deletes files with a specific extension and requires handling of the result to ensure proper file deletion"]
+pub async fn delete_files_with_extension_with_progress(
+    target_dir: &Path,
+    extension: &str,
+    progress: &dyn Progress,
+) -> io::Result<()> {
+    let extension = fs::strip_extension_dot(extension);
+
+    // Count the matching files before any deletion starts, so the total is known upfront
+    let paths: Vec<PathBuf> = WalkDir::new(target_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .map(DirEntry::into_path)
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|file_extension| file_extension.eq_ignore_ascii_case(extension))
+        })
+        .collect();
+    progress.on_start(Some(paths.len()));
+
+    let tasks: Vec<_> = paths
+        .into_iter()
+        .map(|path| {
+            tokio::spawn(async move {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    warn!("Failed to remove {}: {e}", path.display());
+                    false
+                } else {
+                    info!("Removed: {}", path.display());
+                    true
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        match task.await {
+            Ok(removed) => {
+                if removed {
+                    progress.on_advance(1);
+                }
+            }
+            Err(e) => {
+                progress.on_finish();
+                return Err(e.into());
+            }
+        }
+    }
+
+    progress.on_finish();
+    Ok(())
+}
+
+/// Recursively deletes all files with a specific extension, reporting progress to a closure.
+///
+/// This behaves like [`delete_files_with_extension_with_progress`], for callers that
+/// want a plain callback rather than a [`Progress`] implementation. `callback` receives
+/// the number of files deleted so far and the total number of matching files: once
+/// with zero when deletion starts, then after each file removed. Files that fail to
+/// delete are logged and not counted, so `deleted` ends below `total` if any remain.
+/// The callback must be thread-safe, as deletions run concurrently.
+///
+/// # Arguments
+///
+/// * `target_dir` - The root directory to start the deletion from
+/// * `extension` - The file extension to match (with or without the leading dot)
+/// * `callback` - Receives the number of files processed and the total
+///
+/// # Returns
+///
+/// Returns `Ok(())` once every matching file has been processed.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if a deletion task panics or is cancelled.
 ///
 /// # Examples
 ///
 /// ```
 /// use std::path::Path;
-/// use std::io;
-/// use xio::read_lines;
-/// 
-/// async fn read_file_lines() -> io::Result<()> {
-///     let lines = read_lines(Path::new("example.txt")).await?;
-///     for line in lines {
-///         println!("{}", line);
-///     }
-///     Ok(())
+/// use xio::delete_files_with_extension_with_callback;
+///
+/// async fn cleanup_temp_files() -> std::io::Result<()> {
+///     delete_files_with_extension_with_callback(Path::new("./"), "tmp", |deleted, total| {
+///         eprint!("\rDeleted {deleted}/{total}");
+///     })
+///     .await
 /// }
 /// ```
-#[must_use = "Reads all lines from a file and returns them, requiring handling of the result"]
-pub async fn read_lines(path: &Path) -> io::Result<Vec<String>> {
-    let file = File::open(path).await?;
-    let mut reader = BufReader::new(file);
-    let mut lines = Vec::new();
-    let mut line = String::new();
-    while reader.read_line(&mut line).await? > 0 {
-        lines.push(line.trim().to_string());
-        line.clear();
+#[must_use = "Deletes files with a specific extension and requires handling of the result to ensure proper file deletion"]
+pub async fn delete_files_with_extension_with_callback(
+    target_dir: &Path,
+    extension: &str,
+    callback: impl Fn(usize, usize) + Send + Sync,
+) -> io::Result<()> {
+    let progress = CallbackProgress {
+        callback,
+        done: AtomicUsize::new(0),
+        total: AtomicUsize::new(0),
+    };
+    delete_files_with_extension_with_progress(target_dir, extension, &progress).await
+}
+
+/// Adapts a `Fn(done, total)` closure to the [`Progress`] trait
+struct CallbackProgress<F> {
+    callback: F,
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl<F: Fn(usize, usize) + Send + Sync> Progress for CallbackProgress<F> {
+    fn on_start(&self, total: Option<usize>) {
+        let total = total.unwrap_or(0);
+        self.total.store(total, Ordering::SeqCst);
+        (self.callback)(0, total);
+    }
+
+    fn on_advance(&self, n: usize) {
+        let done = self.done.fetch_add(n, Ordering::SeqCst) + n;
+        (self.callback)(done, self.total.load(Ordering::SeqCst));
     }
-    Ok(lines)
 }
 
-/// Reads the entire content of a file into a string.
+/// Deletes all but the `keep` most recently modified files in a directory.
 ///
-/// This function provides a convenient way to read an entire file into memory
-/// asynchronously. It's best suited for smaller files that can fit in memory.
+/// This implements a simple retention policy for backup or snapshot directories. The
+/// files directly inside `dir` (not in its subdirectories) are listed, optionally
+/// restricted to an extension, and sorted by modification time, newest first; every
+/// file after the first `keep` is deleted. Hidden files are never considered. Files
+/// whose modification time cannot be read are treated as the oldest, and files with
+/// the same modification time are ordered by name, so the outcome is deterministic.
 ///
 /// # Arguments
 ///
-/// * `path` - The path to the file to read
+/// * `dir` - The directory to prune
+/// * `extension` - Only consider files with this extension (with or without the leading dot), or all files for `None`
+/// * `keep` - The number of newest files to keep
+/// * `dry_run` - Only report what would be deleted, without deleting anything
 ///
 /// # Returns
 ///
-/// Returns the entire content of the file as a string.
+/// Returns the paths of the deleted files, or of the files that would be deleted in a
+/// dry run, from newest to oldest.
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if:
-/// - The file cannot be opened
-/// - The file cannot be read
-/// - The file content is not valid UTF-8
+/// Returns an `io::Error` if `dir` cannot be read or a file cannot be deleted. Files
+/// deleted before the failure stay deleted.
 ///
 /// # Examples
 ///
 /// ```
 /// use std::path::Path;
-/// use std::io;
-/// use xio::read_file_content;
-/// 
-/// async fn read_file() -> io::Result<()> {
-///     let content = read_file_content(Path::new("example.txt")).await?;
-///     println!("File content: {}", content);
+/// use xio::prune_keep_latest;
+///
+/// async fn rotate_backups() -> std::io::Result<()> {
+///     let deleted = prune_keep_latest(Path::new("./backups"), Some("tar.gz"), 7, false).await?;
+///     println!("Deleted {} old backups", deleted.len());
 ///     Ok(())
 /// }
 /// ```
-#[must_use = "Reads the content of a file and requires handling of the result to ensure the content is retrieved"]
-pub async fn read_file_content(path: &Path) -> io::Result<String> {
-    tokio::fs::read_to_string(path).await
+pub async fn prune_keep_latest(
+    dir: &Path,
+    extension: Option<&str>,
+    keep: usize,
+    dry_run: bool,
+) -> io::Result<Vec<PathBuf>> {
+    let mut candidates = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if is_hidden_name(&entry.file_name())
+            || !entry.file_type().await?.is_file()
+            || extension.is_some_and(|extension| !fs::has_extension(&path, extension))
+        {
+            continue;
+        }
+        let modified = entry.metadata().await.and_then(|m| m.modified()).ok();
+        candidates.push((modified, path));
+    }
+    candidates.sort_by(|(a_time, a_path), (b_time, b_path)| {
+        b_time.cmp(a_time).then_with(|| a_path.cmp(b_path))
+    });
+
+    let expired: Vec<PathBuf> = candidates.into_iter().skip(keep).map(|(_, path)| path).collect();
+    for path in &expired {
+        if dry_run {
+            info!("Would remove: {}", path.display());
+        } else {
+            tokio::fs::remove_file(path).await?;
+            info!("Removed: {}", path.display());
+        }
+    }
+    Ok(expired)
 }
 
-/// Writes content to a file at the specified path.
+/// Number of file deletions [`remove_dir_all_reporting`] runs at once
+const REMOVE_CONCURRENCY: usize = 32;
+
+/// The outcome of [`remove_dir_all_reporting`]
+#[derive(Debug, Default)]
+pub struct RemoveReport {
+    /// Number of files, symlinks and directories removed
+    pub removed: usize,
+    /// Paths that could not be removed or read, with the error for each
+    pub failed: Vec<(PathBuf, io::Error)>,
+}
+
+impl RemoveReport {
+    /// Returns true if everything was removed
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Recursively removes a directory, carrying on past paths that cannot be removed.
 ///
-/// This function asynchronously writes a string to a file. If the file already exists,
-/// it will be overwritten. If the file doesn't exist, it will be created.
+/// Unlike [`std::fs::remove_dir_all`], which stops at the first failure, this removes
+/// everything it can and reports what it could not, which helps when some files are
+/// locked or read-only. The tree is walked contents first without following symlinks
+/// (links are removed, never their targets; if `dir` itself is a symlink, only the
+/// link is removed). Files are deleted with bounded
+/// concurrency, then the directories are removed deepest first, so a directory
+/// holding a path that could not be removed is reported as failed as well.
 ///
 /// # Arguments
 ///
-/// * `path` - The path where the file should be written
-/// * `content` - The string content to write to the file
+/// * `dir` - The directory to remove, including the directory itself
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` if the write was successful.
+/// Returns a [`RemoveReport`] with the number of entries removed and the failures.
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if:
-/// - The file cannot be created
-/// - The file cannot be written to
-/// - The parent directory doesn't exist
-/// - Permission is denied
+/// Returns an `io::Error` if `dir` itself cannot be read, for example because it does
+/// not exist. Failures below `dir` are collected in the report instead.
 ///
 /// # Examples
 ///
 /// ```
 /// use std::path::Path;
-/// use std::io;
-/// use xio::write_to_file;
-/// 
-/// async fn write_file() -> io::Result<()> {
-///     write_to_file(
-///         Path::new("output.txt"),
-///         "Hello, World!"
-///     ).await
+/// use xio::remove_dir_all_reporting;
+///
+/// async fn clear_cache() -> std::io::Result<()> {
+///     let report = remove_dir_all_reporting(Path::new("./cache")).await?;
+///     for (path, e) in &report.failed {
+///         eprintln!("Could not remove {}: {e}", path.display());
+///     }
+///     Ok(())
 /// }
 /// ```
-#[must_use = "Writes content to a file and requires handling of the result to ensure data is saved"]
-pub async fn write_to_file(path: &Path, content: &str) -> io::Result<()> {
-    let mut file = File::create(path).await?;
-    file.write_all(content.as_bytes()).await?;
-    file.flush().await
+#[must_use = "Removes a directory and requires handling of the result to see what could not be removed"]
+pub async fn remove_dir_all_reporting(dir: &Path) -> io::Result<RemoveReport> {
+    remove_dir_all_reporting_with_progress(dir, &NoProgress).await
 }
 
-/// Deletes files with a specific extension in a directory and its subdirectories.
+/// Recursively removes a directory like [`remove_dir_all_reporting`], reporting progress.
 ///
-/// This function recursively walks through a directory tree and deletes all files
-/// that match the specified extension. The deletion is performed concurrently
-/// using Tokio tasks for better performance.
+/// `progress` receives the number of entries to remove once the tree has been walked,
+/// and advances as each one is removed or fails.
 ///
 /// # Arguments
 ///
-/// * `target_dir` - The root directory to start the deletion from
-/// * `extension` - The file extension to match (without the dot)
+/// * `dir` - The directory to remove, including the directory itself
+/// * `progress` - Receives progress updates
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` if all matching files were successfully deleted or if no matching
-/// files were found.
+/// Returns a [`RemoveReport`] with the number of entries removed and the failures.
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if:
-/// - Directory traversal fails
-/// - File deletion fails
-/// - Permission is denied
+/// Returns an `io::Error` if `dir` itself cannot be read.
 ///
 /// # Examples
 ///
 /// ```
 /// use std::path::Path;
-/// use std::io;
-/// use xio::delete_files_with_extension;
-/// 
-/// async fn cleanup_temp_files() -> io::Result<()> {
-///     delete_files_with_extension(Path::new("./"), "tmp").await
+/// use xio::{remove_dir_all_reporting_with_progress, NoProgress};
+///
+/// async fn clear_cache() -> std::io::Result<usize> {
+///     let report = remove_dir_all_reporting_with_progress(Path::new("./cache"), &NoProgress).await?;
+///     Ok(report.removed)
 /// }
 /// ```
-#[must_use = "Deletes files with a specific extension and requires handling of the result to ensure proper file deletion"]
-pub async fn delete_files_with_extension(target_dir: &Path, extension: &str) -> io::Result<()> {
-    let mut tasks = Vec::new();
-
-    for entry in WalkDir::new(target_dir).into_iter().filter_map(Result::ok) {
-        let path = entry.path().to_owned();
-        if path.is_file() {
-            if let Some(file_extension) = path.extension() {
-                if file_extension.eq_ignore_ascii_case(extension) {
-                    tasks.push(tokio::spawn(async move {
-                        if let Err(e) = tokio::fs::remove_file(&path).await {
-                            warn!("Failed to remove {}: {e}", path.display());
-                        } else {
-                            info!("Removed: {}", path.display());
-                        }
-                    }));
-                }
+#[must_use = "Removes a directory and requires handling of the result to see what could not be removed"]
+pub async fn remove_dir_all_reporting_with_progress(
+    dir: &Path,
+    progress: &dyn Progress,
+) -> io::Result<RemoveReport> {
+    let mut report = RemoveReport::default();
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in WalkDir::new(dir).contents_first(true).follow_root_links(false) {
+        match entry {
+            Ok(entry) if entry.file_type().is_dir() => dirs.push(entry.into_path()),
+            Ok(entry) => files.push(entry.into_path()),
+            Err(e) if e.depth() == 0 => return Err(e.into()),
+            Err(e) => {
+                let path = e.path().map_or_else(|| dir.to_path_buf(), Path::to_path_buf);
+                warn!("Failed to read {}: {e}", path.display());
+                report.failed.push((path, e.into()));
             }
         }
     }
 
-    for task in tasks {
-        task.await?;
+    progress.on_start(Some(files.len() + dirs.len()));
+    let mut removals = stream::iter(files)
+        .map(|path| async move {
+            let result = tokio::fs::remove_file(&path).await;
+            (path, result)
+        })
+        .buffer_unordered(REMOVE_CONCURRENCY);
+    while let Some((path, result)) = removals.next().await {
+        record_removal(&mut report, path, result);
+        progress.on_advance(1);
+    }
+    for path in dirs {
+        let result = tokio::fs::remove_dir(&path).await;
+        record_removal(&mut report, path, result);
+        progress.on_advance(1);
     }
+    progress.on_finish();
 
-    Ok(())
+    if !report.is_complete() {
+        warn!("Could not remove {} paths under {}", report.failed.len(), dir.display());
+    }
+    Ok(report)
+}
+
+/// Counts a successful removal in `report`, or records its failure
+fn record_removal(report: &mut RemoveReport, path: PathBuf, result: io::Result<()>) {
+    match result {
+        Ok(()) => {
+            debug!("Removed: {}", path.display());
+            report.removed += 1;
+        }
+        Err(e) => {
+            warn!("Failed to remove {}: {e}", path.display());
+            report.failed.push((path, e));
+        }
+    }
 }
 
 /// Processes a file and adds it to a list if it contains multiple lines.
@@ -618,6 +3865,7 @@ pub async fn check_file_for_multiple_lines(
 /// # Errors
 ///
 /// Returns an `anyhow::Error` if:
+/// - The editor cannot be found on `PATH`
 /// - The editor cannot be spawned
 /// - The editor process fails to start
 /// - The process cannot be waited on
@@ -641,14 +3889,243 @@ pub async fn open_files_in_neovim(files: &[PathBuf], editor: Option<&str>) -> an
         return Ok(());
     }
 
+    editor_command(files, editor)?.spawn()?.wait().await?;
+    Ok(())
+}
+
+/// Opens a list of files in Neovim or a specified editor without waiting for it to exit.
+///
+/// Unlike [`open_files_in_neovim`], which blocks until the editor exits (the right
+/// behaviour for terminal editors), this spawns the editor and returns immediately.
+/// Use it for GUI editors such as VS Code or gvim, or when the program should keep
+/// running while the files are open.
+///
+/// On Unix, a child process that exits without being waited on stays a zombie until
+/// the returned handle is waited on or the parent process exits. Call
+/// [`Child::wait`](tokio::process::Child::wait) when the exit status matters, or drop
+/// the handle if the editor is meant to outlive the program.
+///
+/// # Arguments
+///
+/// * `files` - A slice of paths to the files to open
+/// * `editor` - Optional editor command to use instead of nvim
+///
+/// # Returns
+///
+/// Returns `Ok(Some(child))` with the spawned editor process, or `Ok(None)` if `files`
+/// is empty and no editor was launched.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if the editor cannot be found on `PATH` or cannot be spawned.
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use xio::{open_files_detached, anyhow};
+///
+/// async fn edit_in_background() -> anyhow::Result<()> {
+///     let files = vec![PathBuf::from("notes.md")];
+///     if let Some(mut editor) = open_files_detached(&files, Some("code"))? {
+///         // Do other work, then collect the exit status
+///         editor.wait().await?;
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn open_files_detached(
+    files: &[PathBuf],
+    editor: Option<&str>,
+) -> anyhow::Result<Option<Child>> {
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(editor_command(files, editor)?.spawn()?))
+}
+
+/// Collects the files whose content satisfies a predicate.
+///
+/// Matching files are found using the same filters as [`walk_directory`], read as
+/// UTF-8, and passed to `predicate` along with their path. Files that are not valid
+/// UTF-8 (typically binary files) are skipped.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to search
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `predicate` - Decides, from a file's path and content, whether it should be collected
+///
+/// # Returns
+///
+/// Returns the paths of the files satisfying `predicate`, sorted by path.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if a matching file cannot be read for any reason other
+/// than invalid UTF-8; the error includes the path.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{collect_matching_files, anyhow};
+///
+/// async fn find_fixmes() -> anyhow::Result<()> {
+///     let files = collect_matching_files("./src", "rs", |_, content| content.contains("FIXME")).await?;
+///     println!("{} files need attention", files.len());
+///     Ok(())
+/// }
+/// ```
+pub async fn collect_matching_files<P>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    predicate: P,
+) -> anyhow::Result<Vec<PathBuf>>
+where
+    P: Fn(&Path, &str) -> bool,
+{
+    let mut matches = Vec::new();
+    for path in collect_matching_paths(dir.as_ref(), extension) {
+        let content = match read_file_content(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                debug!("Skipping non-UTF-8 file: {}", path.display());
+                continue;
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        if predicate(&path, &content) {
+            matches.push(path);
+        }
+    }
+    Ok(matches)
+}
+
+/// Opens every file whose content satisfies a predicate in Neovim or a specified editor.
+///
+/// This combines [`collect_matching_files`] with [`open_files_in_neovim`] for the common
+/// "review these files" workflow, such as opening every file that contains `FIXME`.
+/// The editor is not launched if no file matches.
+///
+/// # Arguments
+///
+/// * `dir` - The root directory to search
+/// * `extension` - The file extension to match (with or without the leading dot), or `"*"` to match every file
+/// * `predicate` - Decides, from a file's path and content, whether it should be opened
+/// * `editor` - Optional editor command to use instead of nvim
+///
+/// # Returns
+///
+/// Returns the paths of the files that were opened, sorted by path.
+///
+/// # Errors
+///
+/// Returns an `anyhow::Error` if a matching file cannot be read, or if the editor
+/// cannot be found, spawned or waited on.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{open_matching_files, anyhow};
+///
+/// async fn review_fixmes() -> anyhow::Result<()> {
+///     open_matching_files("./src", "rs", |_, content| content.contains("FIXME"), None).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn open_matching_files<P>(
+    dir: impl AsRef<Path>,
+    extension: &str,
+    predicate: P,
+    editor: Option<&str>,
+) -> anyhow::Result<Vec<PathBuf>>
+where
+    P: Fn(&Path, &str) -> bool,
+{
+    let files = collect_matching_files(dir, extension, predicate).await?;
+    info!("Opening {} matching files", files.len());
+    open_files_in_neovim(&files, editor).await?;
+    Ok(files)
+}
+
+/// Builds the command that opens `files` in `editor`, defaulting to nvim
+///
+/// The editor is looked up first so that a missing editor produces an error naming it
+/// instead of a bare `NotFound` from the OS. The command runs the resolved path, so
+/// editors installed as `.cmd` or `.bat` scripts on Windows can be launched by name.
+fn editor_command(files: &[PathBuf], editor: Option<&str>) -> anyhow::Result<Command> {
     let editor = editor.unwrap_or("nvim");
-    let mut command = Command::new(editor);
-    for file in files {
-        command.arg(file);
+    let Some(program) = find_executable(editor) else {
+        anyhow::bail!(
+            "editor '{editor}' not found on PATH or not executable; set $EDITOR or install it"
+        );
+    };
+
+    let mut command = Command::new(program);
+    command.args(files);
+    Ok(command)
+}
+
+/// Resolves a program name the way a shell would, returning its path if it exists
+///
+/// Names containing a path separator are checked directly; bare names are searched
+/// for in each directory of `PATH`. On Windows each extension in `PATHEXT` is tried as
+/// well, and on Unix only files with an execute bit set are accepted.
+fn find_executable(program: &str) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.components().count() > 1 {
+        return executable_candidates(program).into_iter().find(|path| is_executable(path));
     }
 
-    command.spawn()?.wait().await?;
-    Ok(())
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var).find_map(|dir| {
+        executable_candidates(&dir.join(program))
+            .into_iter()
+            .find(|path| is_executable(path))
+    })
+}
+
+/// Returns the paths a program may be found at, in the order a shell tries them
+///
+/// On Windows a name without an extension is only tried with each extension in
+/// `PATHEXT` appended, falling back to the usual `.COM;.EXE;.BAT;.CMD`.
+fn executable_candidates(path: &Path) -> Vec<PathBuf> {
+    if !cfg!(windows) {
+        return vec![path.to_path_buf()];
+    }
+
+    let mut candidates = Vec::new();
+    if path.extension().is_some() {
+        candidates.push(path.to_path_buf());
+    }
+    let path_ext = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    for extension in path_ext.split(';').filter(|extension| !extension.is_empty()) {
+        let mut candidate = path.as_os_str().to_os_string();
+        candidate.push(extension);
+        candidates.push(PathBuf::from(candidate));
+    }
+    candidates
+}
+
+/// Returns true if `path` is a file the current user may be able to execute
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Returns true if `path` is a file the current user may be able to execute
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
 }
 
 /// Process a file with the given function.
@@ -703,11 +4180,98 @@ where
     processor(path).await
 }
 
+/// Lightweight facts about a Rust source file, as gathered by [`analyze_rust_file`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RustFileReport {
+    /// Whether an inner attribute enables `clippy::pedantic` (via `warn`, `deny` or `forbid`)
+    pub has_pedantic: bool,
+    /// Whether the file contains `#![forbid(unsafe_code)]`
+    pub has_forbid_unsafe: bool,
+    /// Whether the file has a module-level doc comment (`//!` or `#![doc = ...]`)
+    pub has_module_doc: bool,
+    /// The number of lines in the file
+    pub line_count: usize,
+    /// The inner attributes mentioning clippy lints, with whitespace collapsed
+    pub clippy_attributes: Vec<String>,
+}
+
+/// Analyzes a Rust source file without parsing it.
+///
+/// The file is scanned line by line for inner attributes (`#![...]`, which may span
+/// several lines) and module doc comments. This is a textual check: attributes inside
+/// string literals or behind `cfg_attr` are not interpreted.
+///
+/// # Arguments
+///
+/// * `path` - The path to the Rust file to analyze
+///
+/// # Returns
+///
+/// Returns a [`RustFileReport`] describing the file.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the file cannot be read or is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use std::io;
+/// use xio::analyze_rust_file;
+///
+/// async fn report() -> io::Result<()> {
+///     let report = analyze_rust_file(Path::new("src/lib.rs")).await?;
+///     println!("{} lines, pedantic: {}", report.line_count, report.has_pedantic);
+///     Ok(())
+/// }
+/// ```
+pub async fn analyze_rust_file(path: &Path) -> io::Result<RustFileReport> {
+    let content = read_file_content(path).await?;
+    let mut report = RustFileReport {
+        line_count: content.lines().count(),
+        ..RustFileReport::default()
+    };
+
+    let mut attribute = String::new();
+    for line in content.lines().map(str::trim) {
+        if attribute.is_empty() {
+            if line.starts_with("//!") {
+                report.has_module_doc = true;
+            }
+            if !line.starts_with("#![") {
+                continue;
+            }
+        }
+        if !attribute.is_empty() {
+            attribute.push(' ');
+        }
+        attribute.push_str(line);
+        if attribute.matches('[').count() > attribute.matches(']').count() {
+            continue;
+        }
+
+        let compact: String = attribute.chars().filter(|c| !c.is_whitespace()).collect();
+        report.has_module_doc |= compact.starts_with("#![doc");
+        report.has_forbid_unsafe |= compact.starts_with("#![forbid(") && compact.contains("unsafe_code");
+        if compact.contains("clippy::") {
+            report.has_pedantic |= ["#![warn(", "#![deny(", "#![forbid("]
+                .iter()
+                .any(|level| compact.starts_with(level))
+                && compact.contains("clippy::pedantic");
+            report.clippy_attributes.push(attribute.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+        attribute.clear();
+    }
+
+    Ok(report)
+}
+
 /// Process a Rust file and check for pedantic warnings.
 ///
-/// This function reads a Rust source file and checks if it contains the
-/// clippy pedantic warning directive. Files without this directive are
-/// added to a list for further processing.
+/// This function analyzes a Rust source file with [`analyze_rust_file`] and checks
+/// whether it enables clippy pedantic lints. Files that don't are added to a list for
+/// further processing.
 ///
 /// # Arguments
 ///
@@ -745,8 +4309,7 @@ pub async fn process_rust_file(
     path: &Path,
     files_without_warning: &mut Vec<PathBuf>,
 ) -> io::Result<()> {
-    let content = read_file_content(path).await?;
-    if !content.contains("#![warn(clippy::all, clippy::pedantic)]") {
+    if !analyze_rust_file(path).await?.has_pedantic {
         files_without_warning.push(path.to_path_buf());
     }
     Ok(())
@@ -0,0 +1,165 @@
+//! Exclusive file locks for coordinating concurrent tools.
+//!
+//! [`acquire_lock`] and [`try_acquire_lock`] take an exclusive lock on a lock file and
+//! return a [`FileLock`] guard that holds it until dropped. Tools that take the same
+//! lock before touching a shared resource, such as a split output directory, run one
+//! after another instead of clobbering each other's output.
+//!
+//! # Platform behavior
+//!
+//! On Unix the lock is an advisory `flock` lock: it only excludes other processes that
+//! also take the lock, and does not stop anyone from reading or writing the lock file
+//! or the resource it guards. On Windows it is a mandatory `LockFileEx` lock, which also
+//! blocks other processes from reading or writing the locked file itself. Use a
+//! dedicated lock file, never a data file, so the behavior is the same everywhere.
+//! Locks on network filesystems depend on the server and may not be honored.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//! use xio::acquire_lock;
+//!
+//! async fn split_exclusively() -> std::io::Result<()> {
+//!     let _lock = acquire_lock(Path::new("output/.xio.lock")).await?;
+//!     // Only one process at a time gets here; the lock is released when `_lock` drops
+//!     Ok(())
+//! }
+//! ```
+
+use log::debug;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A held exclusive lock, released when the guard is dropped
+///
+/// The lock file itself is left in place after release: removing it could let two
+/// processes lock different files under the same name.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Returns the path of the lock file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Releases the lock, reporting any error instead of ignoring it as a drop does
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the lock cannot be released.
+    pub fn release(self) -> io::Result<()> {
+        debug!("Releasing lock: {}", self.path.display());
+        self.file.unlock()
+    }
+}
+
+/// Acquires an exclusive lock on `path`, waiting until no one else holds it.
+///
+/// The lock file is created if it does not exist, but its parent directory must. The
+/// wait happens on Tokio's blocking thread pool, so it does not stall the runtime.
+/// See the [module documentation](self) for the platform behavior.
+///
+/// # Arguments
+///
+/// * `path` - The path of the lock file
+///
+/// # Returns
+///
+/// Returns a [`FileLock`] guard holding the lock until it is dropped.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the lock file cannot be opened or locked.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::acquire_lock;
+///
+/// async fn exclusive_section() -> std::io::Result<()> {
+///     let lock = acquire_lock(Path::new("shards.lock")).await?;
+///     println!("Holding {}", lock.path().display());
+///     Ok(())
+/// }
+/// ```
+pub async fn acquire_lock(path: &Path) -> io::Result<FileLock> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let file = open_lock_file(&path)?;
+        debug!("Waiting for lock: {}", path.display());
+        file.lock()?;
+        debug!("Acquired lock: {}", path.display());
+        Ok(FileLock { file, path })
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Acquires an exclusive lock on `path` if no one else holds it, without waiting.
+///
+/// The lock file is created if it does not exist, but its parent directory must.
+/// See the [module documentation](self) for the platform behavior.
+///
+/// # Arguments
+///
+/// * `path` - The path of the lock file
+///
+/// # Returns
+///
+/// Returns a [`FileLock`] guard, or `None` if the lock is currently held elsewhere.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the lock file cannot be opened or locking fails for a
+/// reason other than the lock being held.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::try_acquire_lock;
+///
+/// fn run_once() -> std::io::Result<()> {
+///     let Some(_lock) = try_acquire_lock(Path::new("shards.lock"))? else {
+///         eprintln!("Another instance is already running");
+///         return Ok(());
+///     };
+///     // ...
+///     Ok(())
+/// }
+/// ```
+pub fn try_acquire_lock(path: &Path) -> io::Result<Option<FileLock>> {
+    let file = open_lock_file(path)?;
+    match file.try_lock() {
+        Ok(()) => {
+            debug!("Acquired lock: {}", path.display());
+            Ok(Some(FileLock {
+                file,
+                path: path.to_path_buf(),
+            }))
+        }
+        Err(TryLockError::WouldBlock) => {
+            debug!("Lock is held elsewhere: {}", path.display());
+            Ok(None)
+        }
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Opens the lock file for writing, creating it without truncating existing content
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
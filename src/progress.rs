@@ -0,0 +1,61 @@
+//! Progress reporting for long-running operations.
+//!
+//! The crate does not depend on any progress bar library. Instead, operations that
+//! can take a while accept an implementation of the [`Progress`] trait and call it as
+//! work advances, so callers can plug in `indicatif` or any other reporter.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use xio::Progress;
+//!
+//! #[derive(Default)]
+//! struct Counter(AtomicUsize);
+//!
+//! impl Progress for Counter {
+//!     fn on_advance(&self, n: usize) {
+//!         self.0.fetch_add(n, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let counter = Counter::default();
+//! counter.on_advance(2);
+//! assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+//! ```
+
+/// Receives progress updates from long-running operations
+///
+/// All methods have empty default implementations, so implementors only need to
+/// override the events they care about. Methods take `&self` and may be called from
+/// several tasks, so implementations should use interior mutability.
+pub trait Progress: Send + Sync {
+    /// Called once before work starts, with the total number of items if known
+    fn on_start(&self, _total: Option<usize>) {}
+
+    /// Called when `n` more items have been processed
+    fn on_advance(&self, _n: usize) {}
+
+    /// Called once when the operation has finished, whether or not it succeeded
+    fn on_finish(&self) {}
+}
+
+/// A [`Progress`] implementation that ignores every update
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {}
+
+impl<P: Progress + ?Sized> Progress for std::sync::Arc<P> {
+    fn on_start(&self, total: Option<usize>) {
+        (**self).on_start(total);
+    }
+
+    fn on_advance(&self, n: usize) {
+        (**self).on_advance(n);
+    }
+
+    fn on_finish(&self) {
+        (**self).on_finish();
+    }
+}
@@ -0,0 +1,192 @@
+//! Options for the configurable file readers.
+//!
+//! [`read_file_content_with`](crate::read_file_content_with) and
+//! [`fs::read_to_string_with`](crate::fs::read_to_string_with) accept a [`ReadOptions`]
+//! that can cap the size of the file and strip a leading byte order mark. The plain
+//! readers behave like these with [`ReadOptions::default`]. The line readers take a
+//! [`TrimMode`] deciding how much whitespace is removed from each line.
+//!
+//! # Examples
+//!
+//! ```
+//! use xio::ReadOptions;
+//!
+//! let options = ReadOptions::new().with_max_bytes(1024 * 1024).with_strip_bom(true);
+//! assert_eq!(options.max_bytes, Some(1024 * 1024));
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The read buffer capacity of the line readers, matching `BufReader`'s default
+///
+/// [`read_lines_buffered`](crate::read_lines_buffered) accepts a larger capacity for
+/// very large files.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// The UTF-8 encoding of the byte order mark
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Configuration for reading a file into a string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Largest file size, in bytes, that may be read
+    pub max_bytes: Option<u64>,
+    /// Remove a UTF-8 byte order mark at the start of the content
+    pub strip_bom: bool,
+    /// Replace invalid UTF-8 sequences with U+FFFD instead of failing
+    pub lossy: bool,
+}
+
+impl ReadOptions {
+    /// Creates a new `ReadOptions` with no size limit that keeps a byte order mark
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest file size, in bytes, that may be read
+    ///
+    /// Larger files are rejected with a [`FileTooLarge`] error before their content is
+    /// read, so a stray multi-gigabyte file cannot exhaust memory. The limit is also
+    /// enforced while reading, in case the file grows in the meantime.
+    #[must_use]
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Sets whether a UTF-8 byte order mark at the start of the file is removed
+    #[must_use]
+    pub fn with_strip_bom(mut self, strip_bom: bool) -> Self {
+        self.strip_bom = strip_bom;
+        self
+    }
+
+    /// Sets whether invalid UTF-8 is replaced instead of rejected
+    ///
+    /// With lossy decoding, each invalid byte sequence becomes the replacement
+    /// character U+FFFD and the rest of the content is kept. The original bytes cannot
+    /// be recovered from the result, so this suits reading logs and other text that is
+    /// only inspected, not written back.
+    #[must_use]
+    pub fn with_lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// Fails with [`FileTooLarge`] if `size` exceeds the configured limit
+    pub(crate) fn check_size(&self, path: &Path, size: u64) -> io::Result<()> {
+        match self.max_bytes {
+            Some(limit) if size > limit => Err(FileTooLarge {
+                path: path.to_path_buf(),
+                size,
+                limit,
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of bytes to read at most, one past the limit to detect growth
+    pub(crate) fn read_limit(&self) -> u64 {
+        self.max_bytes.map_or(u64::MAX, |limit| limit.saturating_add(1))
+    }
+
+    /// Decodes the bytes read from `path` according to these options
+    pub(crate) fn decode(&self, path: &Path, mut bytes: Vec<u8>) -> io::Result<String> {
+        self.check_size(path, bytes.len() as u64)?;
+        if self.strip_bom && bytes.starts_with(UTF8_BOM) {
+            bytes.drain(..UTF8_BOM.len());
+        }
+        if self.lossy {
+            return Ok(match String::from_utf8(bytes) {
+                Ok(content) => content,
+                Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            });
+        }
+        String::from_utf8(bytes).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not valid UTF-8: {e}", path.display()),
+            )
+        })
+    }
+}
+
+/// How much of each line the line readers remove
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrimMode {
+    /// Remove only the line ending (`\n` or `\r\n`), keeping all other whitespace
+    #[default]
+    LineEnding,
+    /// Remove trailing whitespace, including the line ending
+    Trailing,
+    /// Remove leading and trailing whitespace
+    Both,
+}
+
+impl TrimMode {
+    /// Returns `line` trimmed according to this mode
+    #[must_use]
+    pub fn apply(self, line: &str) -> &str {
+        match self {
+            Self::LineEnding => line
+                .strip_suffix('\n')
+                .map_or(line, |line| line.strip_suffix('\r').unwrap_or(line)),
+            Self::Trailing => line.trim_end(),
+            Self::Both => line.trim(),
+        }
+    }
+}
+
+/// The error returned when a file exceeds [`ReadOptions::max_bytes`]
+///
+/// [`read_file_content_with`](crate::read_file_content_with) wraps it in an
+/// `io::Error` of kind [`io::ErrorKind::FileTooLarge`], and
+/// [`fs::read_to_string_with`](crate::fs::read_to_string_with) in an `anyhow::Error`;
+/// both can be downcast to get at the sizes.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use xio::{read_file_content_with, FileTooLarge, ReadOptions};
+///
+/// async fn read_small(path: &Path) -> std::io::Result<Option<String>> {
+///     match read_file_content_with(path, &ReadOptions::new().with_max_bytes(4096)).await {
+///         Ok(content) => Ok(Some(content)),
+///         Err(e) if e.get_ref().is_some_and(|inner| inner.is::<FileTooLarge>()) => Ok(None),
+///         Err(e) => Err(e),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileTooLarge {
+    /// The file that was too large
+    pub path: PathBuf,
+    /// The size of the file in bytes
+    pub size: u64,
+    /// The configured limit in bytes
+    pub limit: u64,
+}
+
+impl std::fmt::Display for FileTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is {} bytes, above the limit of {} bytes",
+            self.path.display(),
+            self.size,
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for FileTooLarge {}
+
+impl From<FileTooLarge> for io::Error {
+    fn from(error: FileTooLarge) -> Self {
+        io::Error::new(io::ErrorKind::FileTooLarge, error)
+    }
+}
@@ -1,31 +1,124 @@
-use crate::{walk_directory, Path, PathBuf};
-use anyhow::{Context, Result};
+use crate::hash::{hash_file, to_manifest_path};
+use crate::progress::{NoProgress, Progress};
+use crate::vfs::{FileSystem, RealFs};
+use crate::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
 use fancy_regex::Regex;
-use futures::future::try_join_all;
-use log::{debug, info};
-use std::collections::HashMap;
+use futures::future::{try_join_all, BoxFuture};
+use futures::FutureExt;
+use futures::stream::{self, TryStreamExt};
+use log::{debug, info, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::path::Component;
 use std::sync::Arc;
 use tokio::fs;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 /// Type alias for a matcher function that determines if a file should be processed
 pub type MatcherFn = Box<dyn Fn(&Path) -> Result<bool> + Send + Sync>;
 
+/// Type alias for a matcher function that can be shared between clones of a matcher
+pub type SharedMatcherFn = Arc<dyn Fn(&Path) -> Result<bool> + Send + Sync>;
+
+/// Type alias for a function that finds the accompanying files of a matched file
+pub type AccompanyingFn = Arc<dyn Fn(&Path) -> Result<Vec<PathBuf>> + Send + Sync>;
+
+/// Type alias for a function that computes the group key of a matched file
+pub type GroupKeyFn = Arc<dyn Fn(&Path) -> String + Send + Sync>;
+
+/// Type alias for a hook run on each output directory once it is fully populated
+pub type ShardHook = Arc<dyn Fn(&Path, &[PathBuf]) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
 /// Configuration for directory splitting operations
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SplitConfig {
     /// Source directory to split
     pub source_dir: PathBuf,
     /// Output directory (if different from source)
     pub output_dir: Option<PathBuf>,
-    /// Number of subdirectories to create
+    /// Number of subdirectories to create (0 when derived from `files_per_dir`)
     pub num_dirs: usize,
+    /// Target number of file groups per subdirectory, used to derive `num_dirs`
+    pub files_per_dir: Option<usize>,
+    /// Hard cap on the number of files placed in each subdirectory
+    pub max_files_per_dir: Option<usize>,
+    /// Whether to create subdirectories beyond `num_dirs` once every one is at the cap
+    pub overflow_dirs: bool,
     /// Format string for directory prefix (e.g., "part_{}")
     pub prefix_format: String,
     /// Format string for directory suffix (e.g., "_batch")
     pub suffix_format: String,
     /// Optional regex patterns for finding accompanying files
     pub regex_patterns: Option<Vec<Regex>>,
+    /// Minimum number of accompanying files a matched file needs to be copied
+    pub min_accompanying: usize,
+    /// Whether to skip files already present in the output directories
+    pub resume: bool,
+    /// How a file already present in an output directory is checked when resuming
+    pub resume_check: ResumeCheck,
+    /// Minimum size in bytes of a matched file to be copied
+    pub min_size: Option<u64>,
+    /// Maximum size in bytes of a matched file to be copied
+    pub max_size: Option<u64>,
+    /// Optional function computing the group key of a matched file
+    ///
+    /// Files sharing a key are always placed in the same output directory. When unset,
+    /// every matched file forms its own group together with its accompanying files.
+    pub group_key_fn: Option<GroupKeyFn>,
+    /// Seed for shuffling the file groups before they are distributed
+    pub shuffle_seed: Option<u64>,
+    /// Whether to write a [`ShardInfo`] file into each output directory
+    #[cfg(feature = "json")]
+    pub write_shard_info: bool,
+    /// File name of the shard info files, which are never treated as dataset files
+    pub shard_info_name: String,
+    /// Token that stops the split gracefully once cancelled
+    pub cancellation: Option<CancellationToken>,
+    /// Share of the file groups to place in the holdout directory instead of the shards
+    pub holdout: Option<Holdout>,
+    /// Name of the holdout directory inside the output directory
+    pub holdout_dir_name: String,
+    /// How files are placed in the output directories
+    pub copy_mode: CopyMode,
+}
+
+impl std::fmt::Debug for SplitConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("SplitConfig");
+        debug
+            .field("source_dir", &self.source_dir)
+            .field("output_dir", &self.output_dir)
+            .field("num_dirs", &self.num_dirs)
+            .field("files_per_dir", &self.files_per_dir)
+            .field("max_files_per_dir", &self.max_files_per_dir)
+            .field("overflow_dirs", &self.overflow_dirs)
+            .field("prefix_format", &self.prefix_format)
+            .field("suffix_format", &self.suffix_format)
+            .field("regex_patterns", &self.regex_patterns)
+            .field("min_accompanying", &self.min_accompanying)
+            .field("resume", &self.resume)
+            .field("resume_check", &self.resume_check)
+            .field("min_size", &self.min_size)
+            .field("max_size", &self.max_size)
+            .field("group_key_fn", &self.group_key_fn.as_ref().map(|_| "<fn>"))
+            .field("shuffle_seed", &self.shuffle_seed);
+        #[cfg(feature = "json")]
+        debug.field("write_shard_info", &self.write_shard_info);
+        debug
+            .field("shard_info_name", &self.shard_info_name)
+            .field("cancellation", &self.cancellation)
+            .field("holdout", &self.holdout)
+            .field("holdout_dir_name", &self.holdout_dir_name)
+            .field("copy_mode", &self.copy_mode)
+            .finish()
+    }
 }
 
 impl SplitConfig {
@@ -35,9 +128,26 @@ impl SplitConfig {
             source_dir: source_dir.into(),
             output_dir: None,
             num_dirs,
+            files_per_dir: None,
+            max_files_per_dir: None,
+            overflow_dirs: false,
             prefix_format: "part_{}".to_string(),
             suffix_format: String::new(),
             regex_patterns: None,
+            min_accompanying: 0,
+            resume: false,
+            resume_check: ResumeCheck::default(),
+            min_size: None,
+            max_size: None,
+            group_key_fn: None,
+            shuffle_seed: None,
+            #[cfg(feature = "json")]
+            write_shard_info: false,
+            shard_info_name: DEFAULT_SHARD_INFO_NAME.to_string(),
+            cancellation: None,
+            holdout: None,
+            holdout_dir_name: DEFAULT_HOLDOUT_DIR_NAME.to_string(),
+            copy_mode: CopyMode::default(),
         }
     }
 
@@ -48,6 +158,46 @@ impl SplitConfig {
         self
     }
 
+    /// Derives the number of subdirectories from a target number of groups per directory
+    ///
+    /// After scanning, `num_dirs` is computed as `ceil(total_groups / files_per_dir)`.
+    /// This is mutually exclusive with an explicit `num_dirs`: create the config with
+    /// `SplitConfig::new(source_dir, 0)` when using it, otherwise splitting fails.
+    #[must_use]
+    pub fn with_files_per_dir(mut self, files_per_dir: usize) -> Self {
+        self.files_per_dir = Some(files_per_dir);
+        self
+    }
+
+    /// Caps the number of files placed in each subdirectory
+    ///
+    /// Groups are still distributed round-robin, but a directory that cannot take the
+    /// next group without exceeding `max_files` is skipped in favour of the next one
+    /// with room. Groups are never split, so a group with more files than the cap fails
+    /// the split. Once every directory is full, the split fails too, unless
+    /// [`with_overflow_dirs`](Self::with_overflow_dirs) allows extra directories.
+    ///
+    /// Skipping full directories shifts groups onto the others, so with the cap in play
+    /// the directories are no longer balanced by group count: the earlier directories
+    /// end up at the cap and the later ones take the rest. The cap counts files, while
+    /// [`with_files_per_dir`](Self::with_files_per_dir) counts groups to derive
+    /// `num_dirs`; the two can be combined. The holdout directory is not capped.
+    #[must_use]
+    pub fn with_max_files_per_dir(mut self, max_files: usize) -> Self {
+        self.max_files_per_dir = Some(max_files);
+        self
+    }
+
+    /// Creates directories beyond `num_dirs` when every directory has reached the cap
+    ///
+    /// Only takes effect with [`with_max_files_per_dir`](Self::with_max_files_per_dir).
+    /// The extra directories continue the numbering and are reported with the others.
+    #[must_use]
+    pub fn with_overflow_dirs(mut self, overflow_dirs: bool) -> Self {
+        self.overflow_dirs = overflow_dirs;
+        self
+    }
+
     /// Sets the directory naming format
     #[must_use]
     pub fn with_naming(mut self, prefix_format: impl Into<String>, suffix_format: impl Into<String>) -> Self {
@@ -62,6 +212,328 @@ impl SplitConfig {
         self.regex_patterns = Some(patterns);
         self
     }
+
+    /// Sets the minimum number of accompanying files required per matched file
+    ///
+    /// Matched files with fewer accompanying files are treated as orphans: they are
+    /// not copied and are instead listed in [`SplitReport::skipped_orphans`].
+    #[must_use]
+    pub fn with_min_accompanying(mut self, min_accompanying: usize) -> Self {
+        self.min_accompanying = min_accompanying;
+        self
+    }
+
+    /// Enables resuming an interrupted split
+    ///
+    /// When enabled, files whose copy already exists in the target directory and passes
+    /// the [`resume_check`](Self::with_resume_check) are not copied again. Since groups
+    /// are distributed in a deterministic order, re-running a split over an unchanged
+    /// source assigns every group to the same directory as before.
+    ///
+    /// A resumable split records the groups it has completed in a [`RESUME_LOG_NAME`]
+    /// file in the output directory, updated after each output directory is filled and
+    /// when the split ends. On a rerun, only the files of recorded groups are checked
+    /// and kept; the groups that were not completed are copied again in full, since
+    /// their last file may have been cut short. Without a log, for example after a run
+    /// without resume, every existing file is checked.
+    #[must_use]
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Sets how files already present are checked when resuming
+    ///
+    /// [`ResumeCheck::Size`], the default, only compares sizes, so a different file of
+    /// the same size is accepted. [`ResumeCheck::Hash`] also compares the SHA-256
+    /// digests of the copy and the source from [`hash_file`], at the cost of reading
+    /// both. Hashing reads the files from disk rather than through the splitter's
+    /// file system.
+    #[must_use]
+    pub fn with_resume_check(mut self, resume_check: ResumeCheck) -> Self {
+        self.resume_check = resume_check;
+        self
+    }
+
+    /// Sets the minimum size in bytes (inclusive) of matched files to copy
+    ///
+    /// Smaller matched files are skipped together with their accompanying files and
+    /// listed in [`SplitReport::skipped_by_size`]. Only the matched (primary) file is
+    /// size-checked; its accompanying files are never filtered by size.
+    #[must_use]
+    pub fn with_min_size(mut self, min_size: u64) -> Self {
+        self.min_size = Some(min_size);
+        self
+    }
+
+    /// Sets the maximum size in bytes (inclusive) of matched files to copy
+    ///
+    /// Larger matched files are skipped together with their accompanying files and
+    /// listed in [`SplitReport::skipped_by_size`]. As with
+    /// [`with_min_size`](Self::with_min_size), accompanying files are not size-checked.
+    #[must_use]
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets a function computing the group key of each matched file
+    ///
+    /// All files whose key is equal, along with their accompanying files, end up in
+    /// the same output directory.
+    #[must_use]
+    pub fn with_group_key_fn(mut self, group_key_fn: impl Fn(&Path) -> String + Send + Sync + 'static) -> Self {
+        self.group_key_fn = Some(Arc::new(group_key_fn));
+        self
+    }
+
+    /// Shuffles the file groups with a seeded random number generator before distributing them
+    ///
+    /// Without a seed, groups are distributed in the order of their keys, so
+    /// neighbouring file names end up clustered. With a seed, the groups are shuffled
+    /// first and the shuffled order is then distributed round-robin as usual, giving
+    /// each directory a representative sample. The same seed over the same source
+    /// produces the same directories on every run, which also keeps
+    /// [`with_resume`](Self::with_resume) working.
+    #[must_use]
+    pub fn with_shuffle_seed(mut self, seed: u64) -> Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// Writes a [`ShardInfo`] JSON file into each output directory once it is populated
+    ///
+    /// The file records the directory's index, its file count and total size, and the
+    /// split parameters, so shards describe themselves to downstream loaders. It is
+    /// named after [`shard_info_name`](Self::shard_info_name).
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub fn with_shard_info(mut self, write_shard_info: bool) -> Self {
+        self.write_shard_info = write_shard_info;
+        self
+    }
+
+    /// Sets the file name of the shard info files
+    ///
+    /// Files with this name are skipped when scanning the source directory and when
+    /// verifying a split, so shard info files are never mistaken for dataset files,
+    /// even when the shards are re-scanned. Defaults to [`DEFAULT_SHARD_INFO_NAME`].
+    #[must_use]
+    pub fn with_shard_info_name(mut self, name: impl Into<String>) -> Self {
+        self.shard_info_name = name.into();
+        self
+    }
+
+    /// Stops the split gracefully once `token` is cancelled
+    ///
+    /// The token is checked before each file is copied, and a copy that is running when
+    /// the token is cancelled is allowed to finish, so output directories never contain
+    /// truncated files. The split stops before the next file and returns
+    /// normally with [`SplitReport::cancelled`] set; the directory being filled is
+    /// neither reported as completed nor given a shard info file. Running the same split
+    /// again with [`with_resume`](Self::with_resume) completes it.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Places a share of the file groups into a dedicated holdout directory
+    ///
+    /// The holdout is taken from the front of the shuffled group order before the
+    /// remaining groups are distributed round-robin, so it requires a
+    /// [`shuffle_seed`](Self::with_shuffle_seed): the same seed over the same source
+    /// always holds out the same groups, and accompanying files stay with their group.
+    /// The directory is named after [`holdout_dir_name`](Self::with_holdout_dir_name)
+    /// and is reported in [`SplitReport::holdout_dir`], separately from the shards.
+    /// When the directory count is derived with
+    /// [`with_files_per_dir`](Self::with_files_per_dir), only the remaining groups count.
+    #[must_use]
+    pub fn with_holdout(mut self, holdout: Holdout) -> Self {
+        self.holdout = Some(holdout);
+        self
+    }
+
+    /// Sets the name of the holdout directory, such as `val` or `test`
+    ///
+    /// Defaults to [`DEFAULT_HOLDOUT_DIR_NAME`].
+    #[must_use]
+    pub fn with_holdout_dir_name(mut self, name: impl Into<String>) -> Self {
+        self.holdout_dir_name = name.into();
+        self
+    }
+
+    /// Sets how files are placed in the output directories
+    ///
+    /// [`CopyMode::CopyPreserving`] keeps the source files' modification times and
+    /// permissions on the copies. With [`CopyMode::Symlink`], each output directory
+    /// holds symlinks to the source files instead of copies, a view of the sharded
+    /// dataset that takes no extra space. Accompanying files are linked the same way,
+    /// and [`DirectorySplitter::cleanup`] removes the links without touching the files
+    /// they point to. Linking needs a [`FileSystem`] that supports symlinks; on Windows
+    /// creating them may require developer mode or elevated privileges.
+    #[must_use]
+    pub fn with_copy_mode(mut self, copy_mode: CopyMode) -> Self {
+        self.copy_mode = copy_mode;
+        self
+    }
+
+    /// Returns true if the cancellation token is set and has been cancelled
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Returns true if `path` is named like a shard info file
+    fn is_shard_info(&self, path: &Path) -> bool {
+        path.file_name().is_some_and(|name| *name == *self.shard_info_name)
+    }
+
+    /// Returns the path of the resume log of a split into `output_dir`
+    fn resume_log_path(output_dir: &Path) -> PathBuf {
+        output_dir.join(RESUME_LOG_NAME)
+    }
+}
+
+impl SplitConfig {
+    /// Checks that the configured options can be combined
+    fn validate(&self) -> Result<()> {
+        match (self.num_dirs, self.files_per_dir) {
+            (0, None) => bail!("num_dirs must be greater than zero"),
+            (_, Some(0)) => bail!("files_per_dir must be greater than zero"),
+            (n, Some(_)) if n > 0 => bail!("num_dirs and files_per_dir are mutually exclusive"),
+            _ => {}
+        }
+        if self.max_files_per_dir == Some(0) {
+            bail!("max_files_per_dir must be greater than zero");
+        }
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size)
+            && min > max
+        {
+            bail!("min_size must not be greater than max_size");
+        }
+        match self.holdout {
+            Some(_) if self.shuffle_seed.is_none() => bail!("a holdout requires a shuffle_seed"),
+            Some(Holdout::Fraction(fraction)) if !(0.0..=1.0).contains(&fraction) => {
+                bail!("the holdout fraction must be between 0 and 1");
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Orders the file groups for distribution, splitting off the holdout groups
+    ///
+    /// Returns the held out groups and the groups left for the shards.
+    fn arrange_groups<'a>(
+        &self,
+        file_groups: &'a BTreeMap<String, Vec<PathBuf>>,
+    ) -> (Vec<&'a Vec<PathBuf>>, Vec<&'a Vec<PathBuf>>) {
+        let mut groups: Vec<&Vec<PathBuf>> = file_groups.values().collect();
+        if let Some(seed) = self.shuffle_seed {
+            debug!("Shuffling file groups with seed {seed}");
+            groups.shuffle(&mut StdRng::seed_from_u64(seed));
+        }
+        let held_out = match self.holdout {
+            Some(holdout) => groups.drain(..holdout.count(groups.len())).collect(),
+            None => Vec::new(),
+        };
+        (held_out, groups)
+    }
+
+    /// Assigns the groups round-robin to `num_dirs` directories, honouring the file cap
+    ///
+    /// Returns the groups of each directory; with overflow directories there may be more
+    /// than `num_dirs`.
+    fn assign_groups<'a>(
+        &self,
+        groups: &[&'a Vec<PathBuf>],
+        num_dirs: usize,
+    ) -> Result<Vec<Vec<&'a Vec<PathBuf>>>> {
+        let mut dirs = vec![Vec::new(); num_dirs];
+        let Some(max_files) = self.max_files_per_dir else {
+            for (index, files) in groups.iter().enumerate() {
+                dirs[index % num_dirs].push(*files);
+            }
+            return Ok(dirs);
+        };
+        let mut counts = vec![0; num_dirs];
+        let mut next = 0;
+        for files in groups {
+            if files.len() > max_files {
+                bail!(
+                    "a group of {} files exceeds max_files_per_dir ({max_files}): {}",
+                    files.len(),
+                    files[0].display()
+                );
+            }
+            let with_room = (0..dirs.len())
+                .map(|offset| (next + offset) % dirs.len())
+                .find(|&index| counts[index] + files.len() <= max_files);
+            let index = match with_room {
+                Some(index) => index,
+                None if self.overflow_dirs => {
+                    dirs.push(Vec::new());
+                    counts.push(0);
+                    dirs.len() - 1
+                }
+                None => bail!(
+                    "every output directory has reached max_files_per_dir ({max_files}); \
+                     enable overflow_dirs or add directories"
+                ),
+            };
+            dirs[index].push(*files);
+            counts[index] += files.len();
+            next = (index + 1) % dirs.len();
+        }
+        Ok(dirs)
+    }
+
+    /// Returns the path of the output directory with the given index
+    fn dir_path(&self, output_dir: &Path, index: usize) -> PathBuf {
+        let dir_name = format!(
+            "{}{}",
+            self.prefix_format.replace("{}", &index.to_string()),
+            self.suffix_format
+        );
+        output_dir.join(dir_name)
+    }
+
+    /// Returns true if `path` lies inside a directory named like one of the output directories
+    fn is_in_output_dir(&self, path: &Path, output_dir: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(output_dir) else {
+            return false;
+        };
+        if self.holdout.is_some() && relative.starts_with(&self.holdout_dir_name) {
+            return true;
+        }
+        let mut components = relative.components();
+        let (Some(Component::Normal(first)), Some(_)) = (components.next(), components.next()) else {
+            return false;
+        };
+
+        let name = first.to_string_lossy();
+        let (prefix, rest) = self.prefix_format.split_once("{}").unwrap_or((&self.prefix_format, ""));
+        name.strip_prefix(prefix)
+            .and_then(|name| name.strip_suffix(self.suffix_format.as_str()))
+            .and_then(|name| name.strip_suffix(rest))
+            .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+    }
+}
+
+/// Summary of a completed split operation
+#[derive(Debug, Clone, Default)]
+pub struct SplitReport {
+    /// Directories created by the split
+    pub created_dirs: Vec<PathBuf>,
+    /// Matched files skipped for having too few accompanying files
+    pub skipped_orphans: Vec<PathBuf>,
+    /// Matched files skipped for being outside the configured size range
+    pub skipped_by_size: Vec<PathBuf>,
+    /// Target paths that were already present when resuming and were not copied again
+    pub already_present: Vec<PathBuf>,
+    /// Whether the split stopped early because its cancellation token was cancelled
+    pub cancelled: bool,
+    /// The holdout directory, if [`SplitConfig::with_holdout`] was set
+    pub holdout_dir: Option<PathBuf>,
 }
 
 /// Represents a file matcher that determines which files to process
@@ -73,16 +545,92 @@ pub trait FileMatcher: Send + Sync {
     async fn find_accompanying_files(&self, path: &Path) -> Result<Vec<PathBuf>>;
 }
 
+#[async_trait::async_trait]
+impl<M: FileMatcher + ?Sized> FileMatcher for Arc<M> {
+    async fn is_match(&self, path: &Path) -> Result<bool> {
+        (**self).is_match(path).await
+    }
+
+    async fn find_accompanying_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        (**self).find_accompanying_files(path).await
+    }
+}
+
 /// A directory splitter that distributes files across multiple directories
 pub struct DirectorySplitter<M: FileMatcher> {
     config: SplitConfig,
     matcher: M,
+    progress: Arc<dyn Progress>,
+    file_system: Arc<dyn FileSystem>,
+    on_shard_complete: Option<ShardHook>,
 }
 
 impl<M: FileMatcher + Clone + 'static> DirectorySplitter<M> {
     /// Creates a new `DirectorySplitter` with the given configuration and matcher
     pub fn new(config: SplitConfig, matcher: M) -> Self {
-        Self { config, matcher }
+        Self {
+            config,
+            matcher,
+            progress: Arc::new(NoProgress),
+            file_system: Arc::new(RealFs),
+            on_shard_complete: None,
+        }
+    }
+
+    /// Sets an async hook run on each output directory once it is fully populated
+    ///
+    /// The hook receives the directory and the paths of the files placed in it, in copy
+    /// order, and runs before the next directory is filled, so shards can be archived,
+    /// uploaded or listed as the split goes instead of in a second pass. It runs after
+    /// the shard info file is written and before the directory is sent by
+    /// [`split_streaming`](Self::split_streaming). An error from the hook aborts the
+    /// split and is returned with the directory's path as context. The hook is not run
+    /// on the holdout directory, nor on a directory left incomplete by cancellation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use xio::{DirectorySplitter, FnMatcher, SplitConfig};
+    ///
+    /// let matcher = FnMatcher::new(|path| Ok(path.extension().is_some_and(|ext| ext == "png")));
+    /// let splitter = DirectorySplitter::new(SplitConfig::new("dataset", 4), matcher)
+    ///     .with_on_shard_complete(|shard, files| {
+    ///         println!("{} holds {} files", shard.display(), files.len());
+    ///         async { Ok(()) }
+    ///     });
+    /// ```
+    #[must_use]
+    pub fn with_on_shard_complete<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path, &[PathBuf]) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
+        self.on_shard_complete = Some(Arc::new(move |shard, files| hook(shard, files).boxed()));
+        self
+    }
+
+    /// Sets a progress reporter for the split
+    ///
+    /// `on_start` receives the number of files to copy once the source directory has
+    /// been scanned, and `on_advance(1)` is called as each file is copied (or found
+    /// already present when resuming).
+    #[must_use]
+    pub fn with_progress(mut self, progress: impl Progress + 'static) -> Self {
+        self.progress = Arc::new(progress);
+        self
+    }
+
+    /// Sets the file system the splitter reads from and writes to
+    ///
+    /// By default the splitter works on the real file system through
+    /// [`RealFs`]. Passing a [`MemFs`](crate::vfs::MemFs) lets the splitting logic
+    /// run entirely in memory, which is mostly useful for tests. Scanning, size checks,
+    /// directory creation, copying and [`cleanup`](Self::cleanup) go through this file
+    /// system; the matcher does its own I/O, so it has to look at the same files.
+    #[must_use]
+    pub fn with_file_system(mut self, file_system: impl FileSystem + 'static) -> Self {
+        self.file_system = Arc::new(file_system);
+        self
     }
 
     /// Splits the directory according to the configuration
@@ -99,49 +647,418 @@ impl<M: FileMatcher + Clone + 'static> DirectorySplitter<M> {
     /// Panics if a file name cannot be extracted from a path,
     /// which should not happen for valid file paths.
     pub async fn split(&self) -> Result<Vec<PathBuf>> {
+        Ok(self.split_with_report().await?.created_dirs)
+    }
+
+    /// Splits the directory and returns a report including skipped orphan files
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Creating directories fails
+    /// - Reading from source directory fails
+    /// - Copying files fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if a file name cannot be extracted from a path,
+    /// which should not happen for valid file paths.
+    pub async fn split_with_report(&self) -> Result<SplitReport> {
+        self.run_split(None).await
+    }
+
+    /// Splits the directory, sending each output directory once it is fully populated
+    ///
+    /// Output directories are filled one after another, and each directory's path is
+    /// sent on `completed` as soon as its last file has been copied. This lets
+    /// downstream workers start on finished shards while later ones are still being
+    /// written. The assignment of groups to directories is the same as for
+    /// [`split`](Self::split). If the receiver is dropped, the split carries on without
+    /// sending further paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Creating directories fails
+    /// - Reading from source directory fails
+    /// - Copying files fails
+    ///
+    /// # Panics
+    ///
+    /// Panics if a file name cannot be extracted from a path,
+    /// which should not happen for valid file paths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    /// use xio::{anyhow, DirectorySplitter, FileMatcher};
+    ///
+    /// async fn shard_and_process<M: FileMatcher + Clone + 'static>(
+    ///     splitter: DirectorySplitter<M>,
+    /// ) -> anyhow::Result<()> {
+    ///     let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+    ///     let consumer = async move {
+    ///         while let Some(shard) = receiver.recv().await {
+    ///             println!("Shard ready: {}", shard.display());
+    ///         }
+    ///     };
+    ///     let (report, ()) = tokio::join!(splitter.split_streaming(sender), consumer);
+    ///     report?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn split_streaming(&self, completed: UnboundedSender<PathBuf>) -> Result<SplitReport> {
+        self.run_split(Some(completed)).await
+    }
+
+    /// Runs a split, optionally reporting each output directory once it is complete
+    async fn run_split(&self, completed: Option<UnboundedSender<PathBuf>>) -> Result<SplitReport> {
         let mut created_dirs = Vec::new();
+        let mut already_present = Vec::new();
         debug!("Grouping files from source directory");
-        let file_groups = Arc::new(Mutex::new(HashMap::new()));
+        let file_groups = Arc::new(Mutex::new(BTreeMap::new()));
+        let orphans = Arc::new(Mutex::new(Vec::new()));
+        let skipped_by_size = Arc::new(Mutex::new(Vec::new()));
+
+        self.config.validate()?;
+
+        let output_dir = self.config.output_dir.as_ref()
+            .unwrap_or(&self.config.source_dir);
         
         // First, find all matching files and create groups
         info!("Scanning for files...");
-        self.find_files(file_groups.clone()).await?;
+        self.find_files(
+            file_groups.clone(),
+            orphans.clone(),
+            skipped_by_size.clone(),
+            output_dir.clone(),
+        )
+        .await?;
+
+        let file_groups = file_groups.lock().await;
+        let (held_out, groups) = self.config.arrange_groups(&file_groups);
+
+        let num_dirs = match self.config.files_per_dir {
+            Some(files_per_dir) => groups.len().div_ceil(files_per_dir).max(1),
+            None => self.config.num_dirs,
+        };
+        let assigned = self.config.assign_groups(&groups, num_dirs)?;
+        let num_dirs = assigned.len();
         
         // Create output directories
-        let output_dir = self.config.output_dir.as_ref()
-            .unwrap_or(&self.config.source_dir);
-            
-        for i in 0..self.config.num_dirs {
-            let dir_name = format!(
-                "{}{}",
-                self.config.prefix_format.replace("{}", &i.to_string()),
-                self.config.suffix_format
-            );
-            let dir_path = output_dir.join(&dir_name);
-            debug!("Creating directory: {}", dir_path.display());
-            fs::create_dir_all(&dir_path).await?;
+        for i in 0..num_dirs {
+            let dir_path = self.config.dir_path(output_dir, i);
+            self.file_system.create_dir_all(&dir_path).await?;
             created_dirs.push(dir_path);
         }
+        let holdout_dir = self.config.holdout.map(|_| output_dir.join(&self.config.holdout_dir_name));
+        let mut log = self.load_resume_log(output_dir).await?;
+
+        self.progress.on_start(Some(groups.iter().chain(&held_out).map(|files| files.len()).sum()));
+        let mut cancelled = false;
+        if let Some(holdout_dir) = &holdout_dir {
+            info!("Holding out {} file groups in {}", held_out.len(), holdout_dir.display());
+            match self.fill_holdout(&held_out, holdout_dir, log.as_mut(), &mut already_present).await {
+                Ok(completed) => cancelled = !completed,
+                Err(e) => {
+                    self.progress.on_finish();
+                    return Err(e);
+                }
+            }
+        }
 
-        // Distribute files across directories
-        let mut current_dir = 0;
-        let groups = file_groups.lock().await;
+        // Distribute groups round-robin, filling one directory at a time
         info!("Distributing {} file groups across directories", groups.len());
-        
-        for files in groups.values() {
-            let target_dir = &created_dirs[current_dir];
-            debug!("Processing {} files into directory: {}", files.len(), target_dir.display());
-            
-            for file in files {
-                let file_name = file.file_name().unwrap();
-                let target_path = target_dir.join(file_name);
+        'dirs: for (dir_index, target_dir) in created_dirs.iter().enumerate() {
+            if cancelled {
+                break;
+            }
+            let mut shard_files = Vec::new();
+            for files in &assigned[dir_index] {
+                debug!("Processing {} files into directory: {}", files.len(), target_dir.display());
+                if let Err(e) = self.copy_group(files, target_dir, log.as_mut(), &mut already_present).await {
+                    self.progress.on_finish();
+                    return Err(e);
+                }
+                shard_files.extend(files.iter().map(|file| target_dir.join(file.file_name().unwrap())));
+                if self.config.is_cancelled() {
+                    info!("Split cancelled while filling {}", target_dir.display());
+                    cancelled = true;
+                    break 'dirs;
+                }
+            }
+            if let Err(e) = self
+                .finish_shard(target_dir, dir_index, num_dirs, &shard_files, completed.as_ref(), log.as_ref())
+                .await
+            {
+                self.progress.on_finish();
+                return Err(e);
+            }
+        }
+        self.progress.on_finish();
+        if cancelled {
+            self.save_resume_log(log.as_ref()).await?;
+        }
+
+        let mut skipped_orphans = std::mem::take(&mut *orphans.lock().await);
+        skipped_orphans.sort();
+        if !skipped_orphans.is_empty() {
+            info!("Skipped {} files without required accompanying files", skipped_orphans.len());
+        }
+
+        let mut skipped_by_size = std::mem::take(&mut *skipped_by_size.lock().await);
+        skipped_by_size.sort();
+        if !skipped_by_size.is_empty() {
+            info!("Skipped {} files outside the size range", skipped_by_size.len());
+        }
+
+        if !already_present.is_empty() {
+            info!("Resumed split, {} files were already present", already_present.len());
+        }
+
+        Ok(SplitReport {
+            created_dirs,
+            skipped_orphans,
+            skipped_by_size,
+            already_present,
+            cancelled,
+            holdout_dir,
+        })
+    }
+
+    /// Completes a fully populated output directory
+    ///
+    /// Writes its shard info file and the resume log, runs the shard hook and reports it
+    /// on `completed`.
+    async fn finish_shard(
+        &self,
+        target_dir: &Path,
+        dir_index: usize,
+        num_dirs: usize,
+        shard_files: &[PathBuf],
+        completed: Option<&UnboundedSender<PathBuf>>,
+        log: Option<&ResumeLog>,
+    ) -> Result<()> {
+        #[cfg(feature = "json")]
+        if self.config.write_shard_info {
+            self.write_shard_info(target_dir, dir_index, num_dirs).await?;
+        }
+        #[cfg(not(feature = "json"))]
+        let _ = (dir_index, num_dirs);
+        self.save_resume_log(log).await?;
+        if let Some(hook) = &self.on_shard_complete {
+            hook(target_dir, shard_files)
+                .await
+                .with_context(|| format!("Shard hook failed for {}", target_dir.display()))?;
+        }
+        if let Some(completed) = completed
+            && completed.send(target_dir.to_path_buf()).is_err()
+        {
+            debug!("Shard receiver dropped, continuing without notifications");
+        }
+        Ok(())
+    }
+
+    /// Creates the holdout directory and copies the held out groups into it
+    ///
+    /// Returns false if the split was cancelled before every group was copied.
+    async fn fill_holdout(
+        &self,
+        groups: &[&Vec<PathBuf>],
+        holdout_dir: &Path,
+        mut log: Option<&mut ResumeLog>,
+        already_present: &mut Vec<PathBuf>,
+    ) -> Result<bool> {
+        self.file_system.create_dir_all(holdout_dir).await?;
+        for files in groups {
+            self.copy_group(files, holdout_dir, log.as_deref_mut(), already_present).await?;
+            if self.config.is_cancelled() {
+                info!("Split cancelled while filling {}", holdout_dir.display());
+                return Ok(false);
+            }
+        }
+        self.save_resume_log(log.as_deref()).await?;
+        Ok(true)
+    }
+
+    /// Reads the resume log of a split into `output_dir`, if the split is resumable
+    async fn load_resume_log(&self, output_dir: &Path) -> Result<Option<ResumeLog>> {
+        if !self.config.resume {
+            return Ok(None);
+        }
+        let path = SplitConfig::resume_log_path(output_dir);
+        let previous = match self.file_system.read(&path).await {
+            Ok(content) => {
+                let content = String::from_utf8_lossy(&content);
+                let groups: BTreeSet<String> = content.lines().map(str::to_string).collect();
+                debug!("Resume log lists {} completed groups", groups.len());
+                Some(groups)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read resume log {}", path.display()));
+            }
+        };
+        Ok(Some(ResumeLog {
+            output_dir: output_dir.to_path_buf(),
+            completed: previous.clone().unwrap_or_default(),
+            previous,
+        }))
+    }
+
+    /// Writes the groups completed so far to the resume log
+    async fn save_resume_log(&self, log: Option<&ResumeLog>) -> Result<()> {
+        let Some(log) = log else {
+            return Ok(());
+        };
+        let path = SplitConfig::resume_log_path(&log.output_dir);
+        let mut content = String::new();
+        for key in &log.completed {
+            content.push_str(key);
+            content.push('\n');
+        }
+        self.file_system
+            .write(&path, content.as_bytes())
+            .await
+            .with_context(|| format!("Failed to write resume log {}", path.display()))
+    }
+
+    /// Copies a group of files into `target_dir`, skipping copies already present when resuming
+    ///
+    /// When resuming, existing copies are only kept if the resume log trusts the group,
+    /// and the group is recorded in the log once all of its files are in place.
+    async fn copy_group(
+        &self,
+        files: &[PathBuf],
+        target_dir: &Path,
+        log: Option<&mut ResumeLog>,
+        already_present: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        let key = log.as_ref().map(|log| log.key(files, target_dir));
+        let keep_existing = log.as_ref().zip(key.as_ref()).is_some_and(|(log, key)| log.trusts(key));
+        for file in files {
+            let file_name = file.file_name().unwrap();
+            let target_path = target_dir.join(file_name);
+            if keep_existing && self.is_already_copied(file, &target_path).await? {
+                debug!("Already present, skipping: {}", target_path.display());
+                already_present.push(target_path);
+            } else {
                 debug!("Copying {} to {}", file.display(), target_path.display());
-                fs::copy(file, &target_path).await?;
+                if !self.copy_unless_cancelled(file, &target_path).await? {
+                    return Ok(());
+                }
             }
-            current_dir = (current_dir + 1) % self.config.num_dirs;
+            self.progress.on_advance(1);
+        }
+        if let (Some(log), Some(key)) = (log, key) {
+            log.completed.insert(key);
         }
+        Ok(())
+    }
+
+    /// Returns true if `target` is an existing copy of `source` that passes the resume check
+    async fn is_already_copied(&self, source: &Path, target: &Path) -> Result<bool> {
+        let metadata = (
+            self.file_system.metadata(source).await,
+            self.file_system.metadata(target).await,
+        );
+        let same_size = match metadata {
+            (Ok(source), Ok(target)) => target.is_file() && source.len() == target.len(),
+            _ => false,
+        };
+        if !same_size || self.config.resume_check == ResumeCheck::Size {
+            return Ok(same_size);
+        }
+        let source_hash = hash_file(source)
+            .await
+            .with_context(|| format!("Failed to hash {}", source.display()))?;
+        let target_hash = hash_file(target)
+            .await
+            .with_context(|| format!("Failed to hash {}", target.display()))?;
+        Ok(source_hash == target_hash)
+    }
 
-        Ok(created_dirs)
+    /// Copies `from` to `to`, returning false if the split was cancelled first
+    ///
+    /// Cancellation is only checked before the copy starts. A copy that is running is
+    /// always finished, as the file system may keep writing on a blocking thread after
+    /// its future is dropped, and could recreate a partial file that was removed.
+    async fn copy_unless_cancelled(&self, from: &Path, to: &Path) -> Result<bool> {
+        if self.config.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Ok(false);
+        }
+        self.place_file(from, to).await?;
+        Ok(true)
+    }
+
+    /// Places `from` at `to` according to the copy mode, replacing what is there
+    async fn place_file(&self, from: &Path, to: &Path) -> Result<()> {
+        let style = match self.config.copy_mode {
+            CopyMode::Copy => {
+                self.file_system.copy(from, to).await?;
+                return Ok(());
+            }
+            CopyMode::CopyPreserving => {
+                self.file_system.copy_preserving(from, to).await?;
+                return Ok(());
+            }
+            CopyMode::Symlink(style) => style,
+        };
+        let source = std::path::absolute(from)?;
+        let target = match style {
+            SymlinkStyle::Absolute => source,
+            SymlinkStyle::Relative => {
+                let link_dir = std::path::absolute(to)?;
+                relative_link_target(&source, link_dir.parent().unwrap_or(&link_dir))
+            }
+        };
+        match self.file_system.remove_file(to).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("Failed to replace {}", to.display()));
+            }
+            _ => {}
+        }
+        self.file_system
+            .symlink(&target, to)
+            .await
+            .with_context(|| format!("Failed to link {} to {}", to.display(), target.display()))
+    }
+
+    /// Writes the [`ShardInfo`] of a populated output directory into it
+    #[cfg(feature = "json")]
+    async fn write_shard_info(&self, target_dir: &Path, index: usize, num_dirs: usize) -> Result<()> {
+        let mut file_count = 0;
+        let mut total_bytes = 0;
+        for path in self.file_system.read_dir(target_dir).await? {
+            if self.config.is_shard_info(&path) {
+                continue;
+            }
+            let metadata = self.file_system.metadata(&path).await?;
+            if !metadata.is_dir() {
+                file_count += 1;
+                total_bytes += metadata.len();
+            }
+        }
+
+        let info = ShardInfo {
+            index,
+            num_dirs,
+            file_count,
+            total_bytes,
+            source_dir: self.config.source_dir.clone(),
+            files_per_dir: self.config.files_per_dir,
+            min_accompanying: self.config.min_accompanying,
+            min_size: self.config.min_size,
+            max_size: self.config.max_size,
+            shuffle_seed: self.config.shuffle_seed,
+        };
+        let info_path = target_dir.join(&self.config.shard_info_name);
+        debug!("Writing shard info to {}", info_path.display());
+        self.file_system
+            .write(&info_path, serde_json::to_string_pretty(&info)?.as_bytes())
+            .await?;
+        Ok(())
     }
 
     /// Cleans up the created directories
@@ -153,7 +1070,8 @@ impl<M: FileMatcher + Clone + 'static> DirectorySplitter<M> {
         info!("Starting cleanup of {} directories", dirs.len());
         try_join_all(dirs.into_iter().map(|dir| async move {
             debug!("Removing directory: {}", dir.display());
-            fs::remove_dir_all(&dir)
+            self.file_system
+                .remove_dir_all(&dir)
                 .await
                 .context(format!("Failed to remove directory: {}", dir.display()))
         }))
@@ -161,44 +1079,158 @@ impl<M: FileMatcher + Clone + 'static> DirectorySplitter<M> {
         Ok(())
     }
 
-    async fn find_files(&self, file_groups: Arc<Mutex<HashMap<PathBuf, Vec<PathBuf>>>>) -> Result<()> {
-        let config = self.config.clone();
-        let matcher = self.matcher.clone();
-        
-        walk_directory(&config.source_dir, "*", move |path| {
-            let path = path.to_path_buf();
-            let file_groups = file_groups.clone();
-            let matcher = matcher.clone();
-            
-            async move {
-                if matcher.is_match(&path).await? {
-                    debug!("Found matching file: {}", path.display());
-                    let mut groups = file_groups.lock().await;
-                    let group = groups.entry(path.clone()).or_default();
-                    group.push(path.clone());
-
-                    // Find accompanying files
-                    let accompanying = matcher.find_accompanying_files(&path).await?;
-                    for accompanying_path in accompanying {
-                        debug!("Found accompanying file: {}", accompanying_path.display());
-                        group.push(accompanying_path);
+    /// Returns the files a split would select from the source directory, keyed by file name
+    ///
+    /// Files inside `excluded`, such as the holdout directory of an earlier split, are
+    /// left out even if the configuration would not skip them.
+    async fn selected_files(
+        &self,
+        excluded: Option<&Path>,
+    ) -> Result<BTreeMap<OsString, BTreeSet<PathBuf>>> {
+        let file_groups = Arc::new(Mutex::new(BTreeMap::new()));
+        let output_dir = self.config.output_dir.as_ref().unwrap_or(&self.config.source_dir);
+        self.find_files(file_groups.clone(), Arc::default(), Arc::default(), output_dir.clone())
+            .await?;
+
+        let mut selected: BTreeMap<OsString, BTreeSet<PathBuf>> = BTreeMap::new();
+        for path in file_groups.lock().await.values().flatten() {
+            if excluded.is_some_and(|dir| path.starts_with(dir)) {
+                continue;
+            }
+            if let Some(name) = path.file_name() {
+                selected.entry(name.to_os_string()).or_default().insert(path.clone());
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Scans the source directory, grouping matched files with their accompanying files
+    async fn find_files(
+        &self,
+        file_groups: Arc<Mutex<BTreeMap<String, Vec<PathBuf>>>>,
+        orphans: Arc<Mutex<Vec<PathBuf>>>,
+        skipped_by_size: Arc<Mutex<Vec<PathBuf>>>,
+        output_dir: PathBuf,
+    ) -> Result<()> {
+        let paths = self.file_system.walk(&self.config.source_dir).await?;
+        let size_range = (self.config.min_size, self.config.max_size);
+
+        stream::iter(paths.into_iter().map(Ok))
+            .try_for_each_concurrent(None, |path| {
+                let file_groups = &file_groups;
+                let orphans = &orphans;
+                let skipped_by_size = &skipped_by_size;
+                let output_dir = &output_dir;
+                async move {
+                    if self.config.is_in_output_dir(&path, output_dir) {
+                        debug!("Skipping file in output directory: {}", path.display());
+                        return Ok(());
+                    }
+                    if self.config.is_shard_info(&path) {
+                        debug!("Skipping shard info file: {}", path.display());
+                        return Ok(());
+                    }
+                    if path == SplitConfig::resume_log_path(output_dir) {
+                        debug!("Skipping resume log: {}", path.display());
+                        return Ok(());
                     }
+
+                    if self.matcher.is_match(&path).await? {
+                        debug!("Found matching file: {}", path.display());
+
+                        if size_range != (None, None) {
+                            let size = self.file_system.metadata(&path).await?.len();
+                            if let Some(reason) = size_violation(size, size_range) {
+                                warn!("Skipping {}: {reason}", path.display());
+                                skipped_by_size.lock().await.push(path);
+                                return Ok(());
+                            }
+                        }
+
+                        // Find accompanying files
+                        let accompanying: Vec<PathBuf> = self
+                            .matcher
+                            .find_accompanying_files(&path)
+                            .await?
+                            .into_iter()
+                            .filter(|p| *p != path && !self.config.is_shard_info(p))
+                            .collect();
+                        if accompanying.len() < self.config.min_accompanying {
+                            warn!(
+                                "Skipping {}: found {} accompanying files, {} required",
+                                path.display(),
+                                accompanying.len(),
+                                self.config.min_accompanying
+                            );
+                            orphans.lock().await.push(path);
+                            return Ok(());
+                        }
+
+                        let key = self
+                            .config
+                            .group_key_fn
+                            .as_ref()
+                            .map_or_else(|| path.to_string_lossy().into_owned(), |key_fn| key_fn(&path));
+                        let mut groups = file_groups.lock().await;
+                        let group = groups.entry(key).or_default();
+                        group.push(path.clone());
+                        for accompanying_path in accompanying {
+                            debug!("Found accompanying file: {}", accompanying_path.display());
+                            group.push(accompanying_path);
+                        }
+                    }
+                    Ok::<_, anyhow::Error>(())
                 }
-                Ok(())
-            }
-        })
-        .await?;
+            })
+            .await
+    }
+}
 
-        Ok(())
+/// Returns a description of why `size` is outside the `(min, max)` range, if it is
+fn size_violation(size: u64, (min, max): (Option<u64>, Option<u64>)) -> Option<String> {
+    match (min, max) {
+        (Some(min), _) if size < min => Some(format!("{size} bytes is below the minimum of {min}")),
+        (_, Some(max)) if size > max => Some(format!("{size} bytes is above the maximum of {max}")),
+        _ => None,
     }
 }
 
 /// A regex-based file matcher that can find accompanying files using patterns
+///
+/// Build it with [`new`](Self::new) and the `with_*` methods; the search scope is
+/// private, so the struct cannot be written as a literal.
 pub struct RegexFileMatcher {
     /// Function to determine if a file should be processed
     pub matcher_fn: MatcherFn,
     /// Optional regex patterns for finding accompanying files
     pub regex_patterns: Option<Vec<Regex>>,
+    /// The directories searched for accompanying files
+    search_scope: SearchScope,
+}
+
+impl RegexFileMatcher {
+    /// Creates a matcher selecting files with `matcher_fn`, searching the same directory
+    pub fn new(matcher_fn: impl Fn(&Path) -> Result<bool> + Send + Sync + 'static) -> Self {
+        Self {
+            matcher_fn: Box::new(matcher_fn),
+            regex_patterns: None,
+            search_scope: SearchScope::default(),
+        }
+    }
+
+    /// Sets the regex patterns that accompanying files' paths must match
+    #[must_use]
+    pub fn with_regex_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.regex_patterns = Some(patterns);
+        self
+    }
+
+    /// Sets the directories searched for accompanying files
+    #[must_use]
+    pub fn with_search_scope(mut self, search_scope: SearchScope) -> Self {
+        self.search_scope = search_scope;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -208,18 +1240,140 @@ impl FileMatcher for RegexFileMatcher {
     }
 
     async fn find_accompanying_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
-        let mut accompanying = Vec::new();
+        find_by_patterns(path, self.regex_patterns.as_deref(), &self.search_scope).await
+    }
+}
+
+/// A file matcher built from synchronous closures
+///
+/// Implementing [`FileMatcher`] directly needs `#[async_trait]`, which is overkill for
+/// matchers that only inspect the path. `FnMatcher` wraps a plain closure instead, and
+/// is cheap to clone, so it can be passed straight to [`DirectorySplitter::new`].
+/// Without [`with_accompanying_files`](Self::with_accompanying_files), matched files
+/// have no accompanying files.
+///
+/// # Examples
+///
+/// ```
+/// use xio::{DirectorySplitter, FnMatcher, SplitConfig};
+///
+/// let matcher = FnMatcher::new(|path| Ok(path.extension().is_some_and(|ext| ext == "png")))
+///     .with_accompanying_files(|path| {
+///         let caption = path.with_extension("txt");
+///         Ok(if caption.exists() { vec![caption] } else { vec![] })
+///     });
+/// let splitter = DirectorySplitter::new(SplitConfig::new("dataset", 4), matcher);
+/// ```
+#[derive(Clone)]
+pub struct FnMatcher {
+    is_match: SharedMatcherFn,
+    accompanying: Option<AccompanyingFn>,
+}
+
+impl FnMatcher {
+    /// Creates a matcher selecting the files for which `is_match` returns true
+    pub fn new(is_match: impl Fn(&Path) -> Result<bool> + Send + Sync + 'static) -> Self {
+        Self {
+            is_match: Arc::new(is_match),
+            accompanying: None,
+        }
+    }
+
+    /// Sets the function that finds the accompanying files of a matched file
+    #[must_use]
+    pub fn with_accompanying_files(
+        mut self,
+        accompanying: impl Fn(&Path) -> Result<Vec<PathBuf>> + Send + Sync + 'static,
+    ) -> Self {
+        self.accompanying = Some(Arc::new(accompanying));
+        self
+    }
+}
+
+impl std::fmt::Debug for FnMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnMatcher")
+            .field("accompanying", &self.accompanying.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl FileMatcher for FnMatcher {
+    async fn is_match(&self, path: &Path) -> Result<bool> {
+        (self.is_match)(path)
+    }
+
+    async fn find_accompanying_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        self.accompanying.as_ref().map_or_else(|| Ok(Vec::new()), |accompanying| accompanying(path))
+    }
+}
+
+/// The directories searched for the accompanying files of a matched file
+///
+/// Every scope includes the matched file's own directory; the wider scopes add more
+/// directories to it. Directories that do not exist are skipped. The regex patterns
+/// are matched against the full path of each file found, so they can also tell
+/// directories apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    /// Only the directory containing the matched file
+    #[default]
+    SameDir,
+    /// Also the directory with this name next to the matched file's directory
+    ///
+    /// For `images/a.png` and `SiblingDir("captions")`, `captions/` is searched too.
+    SiblingDir(String),
+    /// Also each of these directories
+    Dirs(Vec<PathBuf>),
+}
+
+impl SearchScope {
+    /// Returns the directories to search for the accompanying files of `path`
+    fn dirs(&self, path: &Path) -> Vec<PathBuf> {
+        let Some(dir) = path.parent() else {
+            return Vec::new();
+        };
+        let mut dirs = vec![dir.to_path_buf()];
+        match self {
+            Self::SameDir => {}
+            Self::SiblingDir(name) => {
+                if let Some(parent) = dir.parent() {
+                    dirs.push(parent.join(name));
+                }
+            }
+            Self::Dirs(extra) => dirs.extend(extra.iter().cloned()),
+        }
+        dirs
+    }
+}
+
+/// Finds the files in the scope of `path` whose path matches any of `patterns`
+async fn find_by_patterns(
+    path: &Path,
+    patterns: Option<&[Regex]>,
+    scope: &SearchScope,
+) -> Result<Vec<PathBuf>> {
+    let mut accompanying = Vec::new();
+    
+    if let Some(patterns) = patterns {
+        for dir in scope.dirs(path) {
+            let mut dir_entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    debug!("Skipping missing search directory: {}", dir.display());
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
         
-        if let Some(patterns) = &self.regex_patterns {
-            let dir = path.parent().unwrap();
-            let mut dir_entries = fs::read_dir(dir).await?;
-            
             while let Some(entry) = dir_entries.next_entry().await? {
                 let accompanying_path = entry.path();
-                if accompanying_path.is_file() {
-                    let file_name = accompanying_path.to_str().unwrap();
+                if accompanying_path.is_file() && !accompanying.contains(&accompanying_path) {
+                    // Non-UTF-8 names are matched with their invalid bytes replaced
+                    let file_name = accompanying_path.to_string_lossy();
                     for pattern in patterns {
-                        if pattern.is_match(file_name)? {
+                        if pattern.is_match(&file_name)? {
                             accompanying.push(accompanying_path.clone());
                             break;
                         }
@@ -227,7 +1381,472 @@ impl FileMatcher for RegexFileMatcher {
                 }
             }
         }
-        
-        Ok(accompanying)
     }
-} 
\ No newline at end of file
+    
+    Ok(accompanying)
+}
+
+/// A content signature recognized by [`MagicMatcher`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MagicSignature {
+    /// Name of the file type, such as `"png"`
+    pub kind: String,
+    /// Position of the signature from the start of the file
+    pub offset: usize,
+    /// The bytes expected at `offset`
+    pub bytes: Vec<u8>,
+}
+
+impl MagicSignature {
+    /// Creates a signature for `kind` expecting `bytes` at `offset`
+    pub fn new(kind: impl Into<String>, offset: usize, bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            kind: kind.into(),
+            offset,
+            bytes: bytes.into(),
+        }
+    }
+
+    /// Returns the built-in signatures of common image, document and archive formats
+    ///
+    /// The kinds are `png`, `jpeg`, `gif`, `webp`, `bmp`, `tiff`, `pdf`, `zip` and `gzip`.
+    #[must_use]
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::new("png", 0, *b"\x89PNG\r\n\x1a\n"),
+            Self::new("jpeg", 0, *b"\xFF\xD8\xFF"),
+            Self::new("gif", 0, *b"GIF87a"),
+            Self::new("gif", 0, *b"GIF89a"),
+            Self::new("webp", 8, *b"WEBP"),
+            Self::new("bmp", 0, *b"BM"),
+            Self::new("tiff", 0, *b"II*\0"),
+            Self::new("tiff", 0, *b"MM\0*"),
+            Self::new("pdf", 0, *b"%PDF-"),
+            Self::new("zip", 0, *b"PK\x03\x04"),
+            Self::new("gzip", 0, *b"\x1F\x8B"),
+        ]
+    }
+
+    /// Returns true if `header`, the start of a file, carries this signature
+    fn matches(&self, header: &[u8]) -> bool {
+        header
+            .get(self.offset..self.offset + self.bytes.len())
+            .is_some_and(|bytes| bytes == self.bytes)
+    }
+}
+
+/// A file matcher that recognizes file types by their content instead of their name
+///
+/// The first bytes of each file are compared against a table of [`MagicSignature`]s,
+/// and a file matches if its detected type is one of the accepted kinds. A JPEG
+/// renamed to `.png` is therefore not taken for a PNG, and a PNG without an extension
+/// is still found. The table starts with [`MagicSignature::builtin`] and can be
+/// extended with [`with_signature`](Self::with_signature). Accompanying files are
+/// found with regex patterns, as with [`RegexFileMatcher`].
+///
+/// # Examples
+///
+/// ```
+/// use xio::split::{DirectorySplitter, MagicMatcher, SplitConfig};
+///
+/// async fn split_images() -> anyhow::Result<()> {
+///     let matcher = MagicMatcher::new(["png", "jpeg"]).with_signature("qoi", 0, *b"qoif");
+///     let splitter = DirectorySplitter::new(SplitConfig::new("./images", 4), matcher);
+///     splitter.split().await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MagicMatcher {
+    /// The signatures used to detect file types
+    pub signatures: Vec<MagicSignature>,
+    /// The file types that match
+    pub kinds: Vec<String>,
+    /// Optional regex patterns for finding accompanying files
+    pub regex_patterns: Option<Vec<Regex>>,
+}
+
+impl MagicMatcher {
+    /// Creates a matcher accepting the given kinds, detected with the built-in signatures
+    pub fn new<I, S>(kinds: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            signatures: MagicSignature::builtin(),
+            kinds: kinds.into_iter().map(Into::into).collect(),
+            regex_patterns: None,
+        }
+    }
+
+    /// Adds a signature to the table, for a new kind or as an alternative for a known one
+    ///
+    /// The kind is not accepted automatically; include it in the kinds given to
+    /// [`new`](Self::new) for such files to match.
+    #[must_use]
+    pub fn with_signature(mut self, kind: impl Into<String>, offset: usize, bytes: impl Into<Vec<u8>>) -> Self {
+        self.signatures.push(MagicSignature::new(kind, offset, bytes));
+        self
+    }
+
+    /// Sets regex patterns for finding accompanying files
+    #[must_use]
+    pub fn with_regex_patterns(mut self, patterns: Vec<Regex>) -> Self {
+        self.regex_patterns = Some(patterns);
+        self
+    }
+
+    /// Detects the type of a file from its first bytes
+    ///
+    /// Returns the kind of the first signature in the table that matches, or `None` if
+    /// the content matches none of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or read.
+    pub async fn detect(&self, path: &Path) -> std::io::Result<Option<&str>> {
+        let header_len = self
+            .signatures
+            .iter()
+            .map(|signature| signature.offset + signature.bytes.len())
+            .max()
+            .unwrap_or(0);
+        let mut header = Vec::with_capacity(header_len);
+        fs::File::open(path)
+            .await?
+            .take(header_len as u64)
+            .read_to_end(&mut header)
+            .await?;
+        Ok(self
+            .signatures
+            .iter()
+            .find(|signature| signature.matches(&header))
+            .map(|signature| signature.kind.as_str()))
+    }
+}
+
+#[async_trait::async_trait]
+impl FileMatcher for MagicMatcher {
+    async fn is_match(&self, path: &Path) -> Result<bool> {
+        let kind = self
+            .detect(path)
+            .await
+            .with_context(|| format!("Failed to read the header of {}", path.display()))?;
+        Ok(kind.is_some_and(|kind| self.kinds.iter().any(|accepted| accepted == kind)))
+    }
+
+    async fn find_accompanying_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        find_by_patterns(path, self.regex_patterns.as_deref(), &SearchScope::SameDir).await
+    }
+}
+
+/// The default file name of the shard info files written by [`SplitConfig::with_shard_info`]
+pub const DEFAULT_SHARD_INFO_NAME: &str = "_shard_info.json";
+
+/// The default name of the holdout directory created by [`SplitConfig::with_holdout`]
+pub const DEFAULT_HOLDOUT_DIR_NAME: &str = "holdout";
+
+/// The file name of the log of completed groups kept by [`SplitConfig::with_resume`]
+pub const RESUME_LOG_NAME: &str = "_split_resume.txt";
+
+/// How [`SplitConfig::with_resume`] decides that a file already present needs no copy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResumeCheck {
+    /// The copy has the same size as the source
+    #[default]
+    Size,
+    /// The copy has the same size and SHA-256 digest as the source
+    Hash,
+}
+
+/// The groups completed by a resumable split, persisted in its resume log
+#[derive(Debug, Default)]
+struct ResumeLog {
+    /// The output directory holding the log, which the group paths are relative to
+    output_dir: PathBuf,
+    /// Groups recorded by earlier runs, or `None` if there was no log
+    previous: Option<BTreeSet<String>>,
+    /// Groups completed so far, including those recorded by earlier runs
+    completed: BTreeSet<String>,
+}
+
+impl ResumeLog {
+    /// Returns the line identifying a group placed in `target_dir`
+    ///
+    /// This is the `/`-separated paths of the group's files relative to the output
+    /// directory, separated by tabs.
+    fn key(&self, files: &[PathBuf], target_dir: &Path) -> String {
+        let relative_dir = target_dir.strip_prefix(&self.output_dir).unwrap_or(target_dir);
+        let mut key = String::new();
+        for file in files {
+            if !key.is_empty() {
+                key.push('\t');
+            }
+            let relative = relative_dir.join(file.file_name().unwrap_or_default());
+            key.push_str(&to_manifest_path(&relative));
+        }
+        key
+    }
+
+    /// Returns true if the existing files of the group `key` may be kept
+    fn trusts(&self, key: &str) -> bool {
+        self.previous.as_ref().is_none_or(|previous| previous.contains(key))
+    }
+}
+
+/// How [`DirectorySplitter`] places files in the output directories
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CopyMode {
+    /// Copy each file
+    #[default]
+    Copy,
+    /// Copy each file, keeping its modification and access times and permissions
+    ///
+    /// Plain copies get the time of the copy as their modification time; this keeps
+    /// the source's, for tools downstream that go by file age.
+    CopyPreserving,
+    /// Create a symlink to each source file instead of copying it
+    Symlink(SymlinkStyle),
+}
+
+/// The form of the target path stored in the symlinks of [`CopyMode::Symlink`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkStyle {
+    /// The absolute path of the source file, valid wherever the output is moved
+    #[default]
+    Absolute,
+    /// The path of the source file relative to the link's directory, valid as long as
+    /// the source and output directories are moved together
+    Relative,
+}
+
+/// Returns the path leading from the directory `base` to `path`, both absolute
+fn relative_link_target(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .chain(path[common..].iter().copied())
+        .collect()
+}
+
+/// How many file groups [`SplitConfig::with_holdout`] places in the holdout directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Holdout {
+    /// A fraction between 0 and 1 of the groups, rounded to the nearest whole group
+    Fraction(f64),
+    /// A fixed number of groups, or every group if there are fewer
+    Count(usize),
+}
+
+impl Holdout {
+    /// Returns how many of `total` groups are held out
+    // Group counts are far below 2^52, so the conversions through f64 are exact
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    fn count(self, total: usize) -> usize {
+        match self {
+            Self::Fraction(fraction) => (total as f64 * fraction).round() as usize,
+            Self::Count(count) => count,
+        }
+        .min(total)
+    }
+}
+
+/// Self-describing metadata written into each output directory of a split
+///
+/// Enabled with [`SplitConfig::with_shard_info`].
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ShardInfo {
+    /// Index of this directory among the created directories
+    pub index: usize,
+    /// Number of directories the split created
+    pub num_dirs: usize,
+    /// Number of files in this directory, excluding the shard info file
+    pub file_count: usize,
+    /// Total size in bytes of the files in this directory
+    pub total_bytes: u64,
+    /// The source directory of the split
+    pub source_dir: PathBuf,
+    /// Target number of file groups per directory, if the directory count was derived
+    pub files_per_dir: Option<usize>,
+    /// Minimum number of accompanying files a matched file needed
+    pub min_accompanying: usize,
+    /// Minimum size in bytes of the matched files
+    pub min_size: Option<u64>,
+    /// Maximum size in bytes of the matched files
+    pub max_size: Option<u64>,
+    /// Seed used to shuffle the file groups, if any
+    pub shuffle_seed: Option<u64>,
+}
+
+/// Result of verifying a split against its source directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Selected source files that were not found in any of the created directories
+    pub missing: Vec<PathBuf>,
+    /// Copies left over once every source file was matched, such as a second copy of a file
+    pub duplicated: Vec<PathBuf>,
+    /// Copies whose size differs from the corresponding source file
+    pub size_mismatched: Vec<PathBuf>,
+    /// Copies with the right size whose content hash differs from the source file
+    pub hash_mismatched: Vec<PathBuf>,
+}
+
+impl VerifyReport {
+    /// Returns true if no missing, duplicated, or mismatched files were found
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.duplicated.is_empty()
+            && self.size_mismatched.is_empty()
+            && self.hash_mismatched.is_empty()
+    }
+}
+
+/// Verifies that every file selected by a split was copied exactly once
+///
+/// The source directory is scanned again with the splitter's matcher and file system,
+/// so only the files the split selects are checked: files the matcher never matched
+/// and files skipped as orphans or for their size are not reported. The copies are read
+/// from `report.created_dirs` and `report.holdout_dir` through the same file system, so
+/// held-out files count as placed, and the holdout directory is never scanned as source. Symlinks are followed, so
+/// the links made by [`CopyMode::Symlink`] are checked against the files they point at.
+///
+/// Since a split flattens files into the created directories, sources and copies are
+/// paired by file name and then by size, so same-named files from different
+/// subdirectories are each matched to their own copy. With `compare_hashes`, copies are
+/// also compared by their SHA-256 digest from [`hash_file`](crate::hash::hash_file),
+/// which reads the files from disk rather than through the splitter's file system.
+///
+/// # Arguments
+///
+/// * `splitter` - The splitter that performed the split
+/// * `report` - The report returned by the split
+/// * `compare_hashes` - Whether to compare content hashes in addition to sizes
+///
+/// # Errors
+///
+/// Returns an error if scanning the source directory, reading a created directory, or
+/// reading the metadata or content of a file fails.
+///
+/// # Examples
+///
+/// ```
+/// use xio::split::{verify_split, DirectorySplitter, FileMatcher};
+///
+/// async fn split_and_check<M: FileMatcher + Clone + 'static>(
+///     splitter: DirectorySplitter<M>,
+/// ) -> anyhow::Result<()> {
+///     let report = splitter.split_with_report().await?;
+///     let verified = verify_split(&splitter, &report, true).await?;
+///     if !verified.is_ok() {
+///         eprintln!("Split incomplete: {verified:?}");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn verify_split<M: FileMatcher + Clone + 'static>(
+    splitter: &DirectorySplitter<M>,
+    report: &SplitReport,
+    compare_hashes: bool,
+) -> Result<VerifyReport> {
+    let file_system = splitter.file_system.as_ref();
+    let sources = splitter.selected_files(report.holdout_dir.as_deref()).await?;
+    let dirs: Vec<PathBuf> = report.created_dirs.iter().chain(&report.holdout_dir).cloned().collect();
+    let mut copies = collect_copies(file_system, &splitter.config, &dirs).await?;
+
+    let mut verified = VerifyReport::default();
+    for (name, sources) in sources {
+        let mut remaining = Vec::new();
+        for copy in copies.remove(&name).unwrap_or_default() {
+            remaining.push((fingerprint(file_system, &copy, compare_hashes).await?, copy));
+        }
+
+        let mut unmatched = Vec::new();
+        for source in sources {
+            let print = fingerprint(file_system, &source, compare_hashes).await?;
+            match remaining.iter().position(|(copy, _)| *copy == print) {
+                Some(index) => {
+                    remaining.remove(index);
+                }
+                None => unmatched.push((print.0, source)),
+            }
+        }
+
+        // Pair the sources without an identical copy with the copies left over
+        let mut leftover = remaining.into_iter();
+        for (source_len, source) in unmatched {
+            match leftover.next() {
+                Some(((len, _), copy)) if len != source_len => verified.size_mismatched.push(copy),
+                Some((_, copy)) => verified.hash_mismatched.push(copy),
+                None => {
+                    debug!("Missing from split: {}", source.display());
+                    verified.missing.push(source);
+                }
+            }
+        }
+        verified.duplicated.extend(leftover.map(|(_, copy)| copy));
+    }
+
+    info!(
+        "Verified split: {} missing, {} duplicated, {} size mismatches, {} hash mismatches",
+        verified.missing.len(),
+        verified.duplicated.len(),
+        verified.size_mismatched.len(),
+        verified.hash_mismatched.len()
+    );
+    Ok(verified)
+}
+
+/// Returns the files in `dirs` grouped by file name, skipping shard info files
+async fn collect_copies(
+    file_system: &dyn FileSystem,
+    config: &SplitConfig,
+    dirs: &[PathBuf],
+) -> Result<BTreeMap<OsString, Vec<PathBuf>>> {
+    let mut copies: BTreeMap<OsString, Vec<PathBuf>> = BTreeMap::new();
+    for dir in dirs {
+        let entries = file_system
+            .read_dir(dir)
+            .await
+            .context(format!("Failed to read directory: {}", dir.display()))?;
+        for path in entries {
+            if config.is_shard_info(&path) {
+                continue;
+            }
+            if matches!(file_system.metadata(&path).await, Ok(metadata) if metadata.is_file())
+                && let Some(name) = path.file_name()
+            {
+                copies.entry(name.to_os_string()).or_default().push(path);
+            }
+        }
+    }
+    Ok(copies)
+}
+
+/// Returns the size of a file and, if requested, its content hash
+async fn fingerprint(
+    file_system: &dyn FileSystem,
+    path: &Path,
+    compare_hashes: bool,
+) -> Result<(u64, Option<String>)> {
+    let len = file_system
+        .metadata(path)
+        .await
+        .context(format!("Failed to read metadata: {}", path.display()))?
+        .len();
+    let hash = if compare_hashes {
+        Some(
+            hash_file(path)
+                .await
+                .context(format!("Failed to hash file: {}", path.display()))?,
+        )
+    } else {
+        None
+    };
+    Ok((len, hash))
+}
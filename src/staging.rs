@@ -0,0 +1,129 @@
+//! Staged file output that is committed atomically.
+//!
+//! A [`StagingFile`] collects output in a temporary file and only moves it to its final
+//! location on [`commit`](StagingFile::commit). Readers of the final path therefore see
+//! either the previous content or the complete new content, never a partial write, and
+//! output that is abandoned (by calling [`discard`](StagingFile::discard) or dropping
+//! the handle) leaves no trace.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//! use tokio::io::AsyncWriteExt;
+//! use xio::StagingFile;
+//!
+//! async fn write_report() -> std::io::Result<()> {
+//!     let output = Path::new("report.csv");
+//!     let mut staging = StagingFile::for_path(output)?;
+//!     staging.write_all(b"name,size\n").await?;
+//!     staging.write_all(b"a.txt,42\n").await?;
+//!     staging.commit(output).await
+//! }
+//! ```
+
+use log::debug;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tempfile::{NamedTempFile, TempPath};
+use tokio::fs::File;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+
+/// A temporary file that is atomically renamed into place when committed
+///
+/// `StagingFile` implements [`AsyncWrite`], so the usual [`AsyncWriteExt`] methods can be
+/// used to write to it. The rename performed by [`commit`](Self::commit) is only atomic
+/// when the temporary file and the final path are on the same filesystem; create the
+/// staging file with [`for_path`](Self::for_path), or with [`new_in`](Self::new_in) and a
+/// directory on the destination's filesystem, to guarantee that.
+#[derive(Debug)]
+pub struct StagingFile {
+    writer: BufWriter<File>,
+    temp_path: TempPath,
+}
+
+impl StagingFile {
+    /// Creates a staging file in the directory that will contain `final_path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the temporary file cannot be created.
+    pub fn for_path(final_path: &Path) -> io::Result<Self> {
+        let dir = match final_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        Self::new_in(dir)
+    }
+
+    /// Creates a staging file in `dir`
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the temporary file cannot be created.
+    pub fn new_in(dir: &Path) -> io::Result<Self> {
+        let (file, temp_path) = NamedTempFile::new_in(dir)?.into_parts();
+        debug!("Staging output in {}", temp_path.display());
+        Ok(Self {
+            writer: BufWriter::new(File::from_std(file)),
+            temp_path,
+        })
+    }
+
+    /// Returns the path of the temporary file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// Flushes the staged content to disk and atomically renames it to `final_path`
+    ///
+    /// An existing file at `final_path` is replaced, and its permissions are carried over
+    /// to the new content. A new file keeps the restrictive permissions of the temporary
+    /// file (read and write for the owner only on Unix).
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the content cannot be flushed or synced, if the
+    /// permissions of an existing file cannot be copied, or if the rename fails (for example because `final_path` is on another filesystem). The
+    /// temporary file is removed in that case.
+    pub async fn commit(mut self, final_path: &Path) -> io::Result<()> {
+        self.writer.flush().await?;
+        self.writer.get_ref().sync_all().await?;
+        crate::preserve_permissions(final_path, &self.temp_path).await?;
+        let Self { writer, temp_path } = self;
+        drop(writer);
+        temp_path.persist(final_path).map_err(|e| e.error)?;
+        debug!("Committed staged output to {}", final_path.display());
+        Ok(())
+    }
+
+    /// Discards the staged content and removes the temporary file
+    ///
+    /// Dropping a `StagingFile` also removes the temporary file, but ignores errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the temporary file cannot be removed.
+    pub fn discard(self) -> io::Result<()> {
+        let Self { writer, temp_path } = self;
+        drop(writer);
+        temp_path.close()
+    }
+}
+
+impl AsyncWrite for StagingFile {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.writer).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,461 @@
+//! A minimal filesystem abstraction with real and in-memory implementations.
+//!
+//! The [`FileSystem`] trait covers the handful of operations the crate's higher-level
+//! tools need. [`RealFs`] forwards them to the operating system, while [`MemFs`] keeps
+//! everything in memory, which lets logic such as
+//! [`DirectorySplitter`](crate::DirectorySplitter) be exercised without touching the
+//! disk. Pass a file system to a splitter with
+//! [`with_file_system`](crate::DirectorySplitter::with_file_system).
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::Path;
+//! use xio::vfs::{FileSystem, MemFs};
+//!
+//! async fn round_trip() -> std::io::Result<()> {
+//!     let fs = MemFs::new();
+//!     fs.create_dir_all(Path::new("/data")).await?;
+//!     fs.write(Path::new("/data/a.txt"), b"hello").await?;
+//!     assert_eq!(fs.read(Path::new("/data/a.txt")).await?, b"hello");
+//!     assert_eq!(fs.walk(Path::new("/data")).await?, [Path::new("/data/a.txt")]);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::fs::normalize_path;
+use crate::is_excluded_name;
+use log::warn;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+/// The metadata of a file or directory in a [`FileSystem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    len: u64,
+    is_dir: bool,
+}
+
+impl Metadata {
+    /// Returns the size of the file in bytes, or zero for a directory
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the size is zero
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if this is the metadata of a directory
+    #[must_use]
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Returns true if this is the metadata of a file
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+}
+
+/// The filesystem operations used by the crate's higher-level tools
+///
+/// Paths are passed through as given. Errors use the same [`io::ErrorKind`]s as the
+/// standard library, so callers can match on `NotFound` and friends regardless of the
+/// implementation.
+#[async_trait::async_trait]
+pub trait FileSystem: Send + Sync {
+    /// Reads the whole content of a file
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Creates or truncates a file and writes `contents` to it
+    async fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Returns the paths of the entries directly inside a directory
+    async fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Returns the metadata of a file or directory, following symlinks
+    async fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Creates a directory and any missing parents
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes a directory and everything inside it
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes a file
+    async fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Creates a symlink at `link` pointing to `original`
+    ///
+    /// `original` is stored as given, so a relative path is resolved from the link's
+    /// directory. The default implementation fails with `ErrorKind::Unsupported`.
+    async fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+        let _ = (original, link);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "symlinks are not supported by this file system",
+        ))
+    }
+
+    /// Copies the content of a file, returning the number of bytes copied
+    async fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let contents = self.read(from).await?;
+        self.write(to, &contents).await?;
+        Ok(contents.len() as u64)
+    }
+
+    /// Copies a file like [`copy`](Self::copy), keeping its times and permissions
+    ///
+    /// The default implementation is a plain [`copy`](Self::copy), for file systems
+    /// that do not track times or permissions.
+    async fn copy_preserving(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        self.copy(from, to).await
+    }
+
+    /// Returns every file below `dir`, applying the crate's default filter
+    ///
+    /// Hidden entries, git directories and target directories are skipped, as in
+    /// [`walk_directory`](crate::walk_directory). Entries that cannot be read are
+    /// logged and skipped.
+    async fn walk(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(path) = pending.pop() {
+            if path.file_name().is_some_and(is_excluded_name) {
+                continue;
+            }
+            match self.metadata(&path).await {
+                Ok(metadata) if metadata.is_dir() => match self.read_dir(&path).await {
+                    Ok(entries) => pending.extend(entries.into_iter().rev()),
+                    Err(e) => warn!("Invalid entry: {}: {e}", path.display()),
+                },
+                Ok(_) => files.push(path),
+                Err(e) => warn!("Invalid entry: {}: {e}", path.display()),
+            }
+        }
+        Ok(files)
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: FileSystem + ?Sized> FileSystem for Arc<F> {
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        (**self).read(path).await
+    }
+
+    async fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        (**self).write(path, contents).await
+    }
+
+    async fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        (**self).read_dir(path).await
+    }
+
+    async fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        (**self).metadata(path).await
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        (**self).create_dir_all(path).await
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        (**self).remove_dir_all(path).await
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        (**self).remove_file(path).await
+    }
+
+    async fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+        (**self).symlink(original, link).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        (**self).copy(from, to).await
+    }
+
+    async fn copy_preserving(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        (**self).copy_preserving(from, to).await
+    }
+
+    async fn walk(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        (**self).walk(dir).await
+    }
+}
+
+/// A [`FileSystem`] backed by the operating system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+#[async_trait::async_trait]
+impl FileSystem for RealFs {
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        tokio::fs::read(path).await
+    }
+
+    async fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        tokio::fs::write(path, contents).await
+    }
+
+    async fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries = tokio::fs::read_dir(path).await?;
+        let mut paths = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            paths.push(entry.path());
+        }
+        Ok(paths)
+    }
+
+    async fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = tokio::fs::metadata(path).await?;
+        Ok(Metadata {
+            len: if metadata.is_dir() { 0 } else { metadata.len() },
+            is_dir: metadata.is_dir(),
+        })
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        crate::fs::ensure_dir(path).await
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        tokio::fs::remove_dir_all(path).await
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        tokio::fs::remove_file(path).await
+    }
+
+    #[cfg(unix)]
+    async fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+        tokio::fs::symlink(original, link).await
+    }
+
+    #[cfg(windows)]
+    async fn symlink(&self, original: &Path, link: &Path) -> io::Result<()> {
+        tokio::fs::symlink_file(original, link).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        tokio::fs::copy(from, to).await
+    }
+
+    async fn copy_preserving(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        crate::copy_file_preserving(from, to).await
+    }
+
+    /// Walks `dir` with `walkdir`, following symlinks and detecting symlink loops
+    async fn walk(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let dir = dir.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            WalkDir::new(dir)
+                .follow_links(true)
+                .into_iter()
+                .filter_entry(|e| !crate::is_excluded_by_default(e))
+                .filter_map(|r| r.map_err(|e| warn!("Invalid entry: {e}")).ok())
+                .filter(|e| e.file_type().is_file())
+                .map(walkdir::DirEntry::into_path)
+                .collect()
+        })
+        .await
+        .map_err(io::Error::other)
+    }
+}
+
+/// A node of the in-memory tree
+#[derive(Debug, Clone)]
+enum Node {
+    File(Vec<u8>),
+    Dir,
+}
+
+/// A [`FileSystem`] that keeps files and directories in memory
+///
+/// Paths are normalized lexically with [`normalize_path`], so `/a/./b` and `/a/b`
+/// name the same entry. The root of an absolute path (`/`) and the current
+/// directory of a relative one always exist; every other directory has to be created
+/// with [`create_dir_all`](FileSystem::create_dir_all) before files can be written
+/// into it. Symlinks are not supported.
+#[derive(Debug, Default)]
+pub struct MemFs {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+}
+
+impl MemFs {
+    /// Creates an empty in-memory file system
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` with the node map, panicking if a previous user panicked while holding it
+    fn with_nodes<T>(&self, f: impl FnOnce(&mut BTreeMap<PathBuf, Node>) -> T) -> T {
+        f(&mut self.nodes.lock().expect("MemFs lock poisoned"))
+    }
+}
+
+/// Returns true for the paths that exist implicitly in a [`MemFs`]
+fn is_root(path: &Path) -> bool {
+    path.parent().is_none() || path == Path::new(".")
+}
+
+/// Builds the error reported for a missing path
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+}
+
+/// Checks that the parent of `path` is a directory in `nodes`
+fn check_parent(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if parent.as_os_str().is_empty() || is_root(parent) => Ok(()),
+        Some(parent) => match nodes.get(parent) {
+            Some(Node::Dir) => Ok(()),
+            Some(Node::File(_)) => Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", parent.display()),
+            )),
+            None => Err(not_found(parent)),
+        },
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} has no parent directory", path.display()),
+        )),
+    }
+}
+
+#[async_trait::async_trait]
+impl FileSystem for MemFs {
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let path = normalize_path(path);
+        self.with_nodes(|nodes| match nodes.get(&path) {
+            Some(Node::File(contents)) => Ok(contents.clone()),
+            Some(Node::Dir) => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a directory", path.display()),
+            )),
+            None if is_root(&path) => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a directory", path.display()),
+            )),
+            None => Err(not_found(&path)),
+        })
+    }
+
+    async fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let path = normalize_path(path);
+        self.with_nodes(|nodes| {
+            check_parent(nodes, &path)?;
+            if matches!(nodes.get(&path), Some(Node::Dir)) || is_root(&path) {
+                return Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    format!("{} is a directory", path.display()),
+                ));
+            }
+            nodes.insert(path, Node::File(contents.to_vec()));
+            Ok(())
+        })
+    }
+
+    async fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let path = normalize_path(path);
+        let path = if path == Path::new(".") { PathBuf::new() } else { path };
+        self.with_nodes(|nodes| {
+            match nodes.get(&path) {
+                Some(Node::Dir) => {}
+                Some(Node::File(_)) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotADirectory,
+                        format!("{} is not a directory", path.display()),
+                    ));
+                }
+                None if is_root(&path) || path.as_os_str().is_empty() => {}
+                None => return Err(not_found(&path)),
+            }
+            Ok(nodes
+                .keys()
+                .filter(|child| child.parent() == Some(path.as_path()))
+                .cloned()
+                .collect())
+        })
+    }
+
+    async fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let path = normalize_path(path);
+        self.with_nodes(|nodes| match nodes.get(&path) {
+            Some(Node::File(contents)) => Ok(Metadata {
+                len: contents.len() as u64,
+                is_dir: false,
+            }),
+            Some(Node::Dir) => Ok(Metadata { len: 0, is_dir: true }),
+            None if is_root(&path) => Ok(Metadata { len: 0, is_dir: true }),
+            None => Err(not_found(&path)),
+        })
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize_path(path);
+        self.with_nodes(|nodes| {
+            for dir in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+                if is_root(dir) || dir.as_os_str().is_empty() {
+                    continue;
+                }
+                match nodes.get(dir) {
+                    Some(Node::Dir) => {}
+                    Some(Node::File(_)) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::AlreadyExists,
+                            format!("{} exists and is not a directory", dir.display()),
+                        ));
+                    }
+                    None => {
+                        nodes.insert(dir.to_path_buf(), Node::Dir);
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize_path(path);
+        self.with_nodes(|nodes| match nodes.get(&path) {
+            Some(Node::Dir) => {
+                nodes.retain(|entry, _| !entry.starts_with(&path));
+                Ok(())
+            }
+            Some(Node::File(_)) => Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", path.display()),
+            )),
+            None => Err(not_found(&path)),
+        })
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize_path(path);
+        self.with_nodes(|nodes| match nodes.get(&path) {
+            Some(Node::File(_)) => {
+                nodes.remove(&path);
+                Ok(())
+            }
+            Some(Node::Dir) => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a directory", path.display()),
+            )),
+            None if is_root(&path) => Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!("{} is a directory", path.display()),
+            )),
+            None => Err(not_found(&path)),
+        })
+    }
+}
@@ -0,0 +1,528 @@
+//! Options controlling how the directory walkers traverse and dispatch files.
+//!
+//! The plain walker functions such as [`walk_directory`](crate::walk_directory) use
+//! [`WalkOptions::default`]. The `_with` variants accept a `WalkOptions` built with the
+//! `with_*` methods to change that behavior.
+//!
+//! # Examples
+//!
+//! ```
+//! use xio::WalkOptions;
+//!
+//! let options = WalkOptions::new().with_canonicalize(true);
+//! assert!(options.canonicalize);
+//! ```
+
+use crate::fs::{has_extension, normalize_path};
+use crate::progress::{NoProgress, Progress};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{Interval, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+/// The conventional name of the ignore file read by [`WalkOptions::with_ignore_file`]
+pub const DEFAULT_IGNORE_FILE: &str = ".xioignore";
+
+/// Summary of a completed walk, returned by [`walk_directory_with_report`](crate::walk_directory_with_report)
+#[derive(Debug, Default)]
+pub struct WalkReport {
+    /// Number of files whose callback completed successfully
+    pub processed: usize,
+    /// Entries that could not be read during traversal and were skipped
+    pub errors: Vec<walkdir::Error>,
+    /// Whether the walk ended early because its cancellation token was cancelled
+    pub cancelled: bool,
+}
+
+impl WalkReport {
+    /// Returns true if no entry had to be skipped during traversal
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// What a [`walk_directory_until`](crate::walk_directory_until) callback asks the walk to do next
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WalkAction {
+    /// Keep walking and processing files
+    #[default]
+    Continue,
+    /// Stop the walk; callbacks that are already running still complete
+    Stop,
+}
+
+/// The order in which matched files are handed to the callback
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProcessOrder {
+    /// Process files as the directory walk finds them, without buffering
+    #[default]
+    Walk,
+    /// Process the most recently modified files first
+    ModifiedDesc,
+    /// Process the least recently modified files first
+    ModifiedAsc,
+    /// Walk depth-first, handing each directory to the callback after everything inside it
+    ///
+    /// The directories of the walk, including the root, are handed to the callback as
+    /// well, so a callback can delete the tree as it goes: by the time a directory is
+    /// reached it is empty. To make that safe, callbacks run one at a time, so a
+    /// directory's callback only starts once those of its contents have finished, and
+    /// symlinks are not followed, so nothing outside the tree is reached; with `"*"`
+    /// they are handed to the callback like files. Entries are not buffered. The default
+    /// filters and ignore rules still apply, so a directory holding hidden or ignored
+    /// entries is not empty when its callback runs.
+    ContentsFirst,
+}
+
+/// The form of the paths handed to walk callbacks
+///
+/// Every traversal function in the crate, including the synchronous finders in
+/// [`fs`](crate::fs) such as [`get_files_with_extension`](crate::fs::get_files_with_extension),
+/// yields paths in the [`AsWalked`](Self::AsWalked) form: the root argument joined
+/// with the path below it. [`WalkOptions::with_path_mode`] selects another form for
+/// the walkers, and [`apply`](Self::apply) converts the output of the finders the
+/// same way, so tools mixing both see one consistent format.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use xio::PathMode;
+///
+/// let mode = PathMode::RelativeTo(PathBuf::from("/data"));
+/// assert_eq!(mode.apply(Path::new("/data/a/b.txt")), Path::new("a/b.txt"));
+/// assert!(PathMode::Absolute.apply(Path::new("b.txt")).is_absolute());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PathMode {
+    /// The root argument joined with the path below it, as produced by the traversal
+    #[default]
+    AsWalked,
+    /// An absolute path, made absolute against the current directory and normalized
+    /// lexically, without resolving symlinks
+    Absolute,
+    /// A path relative to the given root; paths outside the root are left unchanged
+    RelativeTo(PathBuf),
+}
+
+impl PathMode {
+    /// Converts a path produced by a traversal into this form
+    ///
+    /// For [`RelativeTo`](Self::RelativeTo), both `path` and the root are made absolute
+    /// and normalized before comparing, so a relative walk root and an absolute base (or
+    /// the other way around) still match.
+    #[must_use]
+    pub fn apply(&self, path: &Path) -> PathBuf {
+        match self {
+            Self::AsWalked => path.to_path_buf(),
+            Self::Absolute => absolute_normalized(path),
+            Self::RelativeTo(root) => absolute_normalized(path)
+                .strip_prefix(absolute_normalized(root))
+                .map_or_else(|_| path.to_path_buf(), Path::to_path_buf),
+        }
+    }
+}
+
+/// Makes `path` absolute against the current directory and normalizes it lexically
+fn absolute_normalized(path: &Path) -> PathBuf {
+    std::path::absolute(path).map_or_else(|_| path.to_path_buf(), |p| normalize_path(&p))
+}
+
+/// Directories a walk enters despite the crate's default filter
+///
+/// Set through [`WalkOptions::with_include_target`] and
+/// [`WalkOptions::with_include_git`]. Other hidden entries stay excluded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncludedDirs {
+    /// Whether `target` directories are walked
+    pub target: bool,
+    /// Whether `.git` directories are walked
+    pub git: bool,
+}
+
+/// Configuration for directory walking operations
+#[derive(Clone, Default)]
+pub struct WalkOptions {
+    /// Resolve each matched path to its canonical absolute form before invoking the callback
+    pub canonicalize: bool,
+    /// Abort outstanding callbacks and return as soon as one callback fails
+    pub fail_fast: bool,
+    /// Maximum time a single callback may run before it is treated as failed
+    pub per_file_timeout: Option<Duration>,
+    /// Extensions to skip when matching every file with `"*"`, with or without the dot
+    pub exclude_extensions: Vec<String>,
+    /// The order in which matched files are handed to the callback
+    pub order: ProcessOrder,
+    /// Skip files whose real path (after resolving symlinks) was already processed
+    pub dedup_by_realpath: bool,
+    /// Name of the gitignore-style files whose rules exclude entries from the walk
+    pub ignore_file: Option<String>,
+    /// Maximum number of callbacks started per second
+    pub rate_limit: Option<u32>,
+    /// Receives a progress update as each callback completes
+    pub progress: Option<Arc<dyn Progress>>,
+    /// The form of the paths handed to the callback
+    pub path_mode: PathMode,
+    /// Maximum number of matching files handed to the callback
+    pub limit: Option<usize>,
+    /// Token that stops the walk from dispatching further files once cancelled
+    pub cancellation: Option<CancellationToken>,
+    /// Number of worker tasks running the callbacks, instead of one task per file
+    pub workers: Option<usize>,
+    /// Size in bytes above which matching files are skipped
+    pub max_file_size: Option<u64>,
+    /// Normally excluded directories that are walked anyway
+    pub included_dirs: IncludedDirs,
+}
+
+impl std::fmt::Debug for WalkOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalkOptions")
+            .field("canonicalize", &self.canonicalize)
+            .field("fail_fast", &self.fail_fast)
+            .field("per_file_timeout", &self.per_file_timeout)
+            .field("exclude_extensions", &self.exclude_extensions)
+            .field("order", &self.order)
+            .field("dedup_by_realpath", &self.dedup_by_realpath)
+            .field("ignore_file", &self.ignore_file)
+            .field("rate_limit", &self.rate_limit)
+            .field("progress", &self.progress.as_ref().map(|_| "<progress>"))
+            .field("path_mode", &self.path_mode)
+            .field("limit", &self.limit)
+            .field("cancellation", &self.cancellation)
+            .field("workers", &self.workers)
+            .field("max_file_size", &self.max_file_size)
+            .field("included_dirs", &self.included_dirs)
+            .finish()
+    }
+}
+
+impl WalkOptions {
+    /// Creates a new `WalkOptions` with the default walking behavior
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether matched paths are canonicalized before being passed to the callback
+    ///
+    /// Paths that cannot be canonicalized (for example broken symlinks) are logged and
+    /// passed to the callback unchanged.
+    #[must_use]
+    pub fn with_canonicalize(mut self, canonicalize: bool) -> Self {
+        self.canonicalize = canonicalize;
+        self
+    }
+
+    /// Sets whether the walk stops at the first callback error
+    ///
+    /// By default every matching file is processed and the first error is returned once
+    /// all callbacks have finished. With `fail_fast` enabled, no further callbacks are
+    /// started after an error and the ones still running are aborted.
+    #[must_use]
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Sets the maximum time a callback may spend on a single file
+    ///
+    /// A callback exceeding the limit is cancelled and reported as an error naming the
+    /// file, which is then handled like any other callback error according to
+    /// [`fail_fast`](Self::fail_fast).
+    #[must_use]
+    pub fn with_per_file_timeout(mut self, timeout: Duration) -> Self {
+        self.per_file_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets extensions to skip when walking with the `"*"` extension
+    ///
+    /// This is a blocklist for walks that match every file. When the walk is given a
+    /// specific extension, that extension takes precedence and the blocklist is not
+    /// consulted. Extensions are matched like [`has_extension`], so a leading dot is
+    /// optional and multi-part extensions such as `"tar.gz"` are supported.
+    #[must_use]
+    pub fn with_exclude_extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude_extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the order in which matched files are processed
+    ///
+    /// The modification time orders buffer every matching entry, with its modification
+    /// time, before the first callback starts, so memory use grows with the number of
+    /// matches. Files whose modification time cannot be read are treated as the oldest.
+    /// Any order other than [`ProcessOrder::Walk`] runs callbacks one at a time, in that
+    /// order, instead of concurrently.
+    #[must_use]
+    pub fn with_order(mut self, order: ProcessOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets whether files reachable through several paths are processed only once
+    ///
+    /// Walks follow symlinks, so a file linked from several places (or a directory
+    /// reachable through two paths) would otherwise reach the callback once per path.
+    /// With this enabled, each matched path is resolved with `canonicalize` and skipped
+    /// if its real path has already been seen; the first path found is the one passed to
+    /// the callback. Resolving costs a few extra syscalls per file, on top of the walk.
+    #[must_use]
+    pub fn with_dedup_by_realpath(mut self, dedup_by_realpath: bool) -> Self {
+        self.dedup_by_realpath = dedup_by_realpath;
+        self
+    }
+
+    /// Sets the name of ignore files whose rules exclude entries from the walk
+    ///
+    /// Every directory of the walk may contain a file with this name (conventionally
+    /// [`DEFAULT_IGNORE_FILE`], `.xioignore`) using `.gitignore` syntax. Its rules apply
+    /// to the directory's subtree, and rules in deeper files take precedence, so a
+    /// nested file can re-include (`!pattern`) what a parent excluded. Ignore files
+    /// above the walk root are not consulted, and `.gitignore` files are not read.
+    #[must_use]
+    pub fn with_ignore_file(mut self, name: impl Into<String>) -> Self {
+        self.ignore_file = Some(name.into());
+        self
+    }
+
+    /// Limits how many callbacks are started per second
+    ///
+    /// Callbacks are started at evenly spaced intervals of `1 / files_per_second`
+    /// seconds, which caps throughput rather than parallelism: this keeps a walk over a
+    /// shared network filesystem from saturating it, even when each callback is quick.
+    /// The first callback starts immediately. A limit of zero disables rate limiting.
+    #[must_use]
+    pub fn with_rate_limit(mut self, files_per_second: u32) -> Self {
+        self.rate_limit = Some(files_per_second).filter(|&rate| rate > 0);
+        self
+    }
+
+    /// Returns the interval gating callback starts, if a rate limit is set
+    pub(crate) fn rate_limiter(&self) -> Option<Interval> {
+        self.rate_limit.filter(|&rate| rate > 0).map(|rate| {
+            let mut interval = tokio::time::interval(Duration::from_secs(1) / rate);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        })
+    }
+
+    /// Sets a progress reporter for the walk
+    ///
+    /// The total is not known while the directory is being walked, so `on_start` is
+    /// called with `None` unless an [`order`](Self::with_order) requires buffering the
+    /// matches first. `on_advance(1)` is called as each callback completes and
+    /// `on_finish` once the walk returns.
+    #[must_use]
+    pub fn with_progress(mut self, progress: impl Progress + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    /// Sets the form of the paths handed to the callback
+    ///
+    /// The conversion happens after [`canonicalize`](Self::with_canonicalize), if enabled.
+    /// Canonical paths have their symlinks resolved, so a
+    /// [`PathMode::RelativeTo`] root should then be given in canonical form too.
+    #[must_use]
+    pub fn with_path_mode(mut self, path_mode: PathMode) -> Self {
+        self.path_mode = path_mode;
+        self
+    }
+
+    /// Stops the walk after `limit` matching files have been handed to the callback
+    ///
+    /// This is a cap, not a filter: the first `limit` matches in processing order are
+    /// dispatched and the traversal then ends without looking further. Combined with an
+    /// [`order`](Self::with_order) it selects, for example, the 1000 most recently
+    /// modified files. The limit is enforced exactly, regardless of concurrency, because
+    /// callbacks are dispatched one after another from a single loop; it counts
+    /// dispatched callbacks, including ones that later fail.
+    #[must_use]
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Stops the walk gracefully once `token` is cancelled
+    ///
+    /// The token is checked before each file is dispatched. After cancellation no new
+    /// callbacks are started, callbacks that are already running are awaited so every
+    /// file is either fully processed or not touched at all, and the walk returns
+    /// normally. [`WalkReport::cancelled`] tells a cancelled walk from a complete one.
+    /// Cancel the token from a signal handler such as [`tokio::signal::ctrl_c`] to make
+    /// a tool safe to interrupt.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Runs the callbacks on a fixed pool of `workers` tasks instead of one task per file
+    ///
+    /// By default every matched file gets its own Tokio task. On trees with millions of
+    /// small files that per-file overhead dominates, so with a pool the walk queues the
+    /// matches in batches on a bounded channel that `workers` long-lived tasks drain,
+    /// each running its callbacks one after another. At most `workers` callbacks then
+    /// run at once, and the traversal waits whenever the workers fall behind, keeping
+    /// memory use flat. With [`fail_fast`](Self::with_fail_fast), workers stop taking
+    /// files after the first error and the callbacks still running are aborted. The
+    /// pool only applies to [`ProcessOrder::Walk`]; the other orders already run one
+    /// callback at a time. A count of zero keeps the per-file tasks.
+    #[must_use]
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = Some(workers).filter(|&workers| workers > 0);
+        self
+    }
+
+    /// Skips matching files larger than `bytes` instead of handing them to the callback
+    ///
+    /// A safety valve for callbacks that assume small files, such as text processing
+    /// that would choke on a stray multi-gigabyte binary. The size comes from the walk
+    /// entry's metadata, so callbacks need not stat each file themselves. Skipped files
+    /// are logged at debug level and not counted in [`WalkReport::processed`] or against
+    /// the [`limit`](Self::with_limit). Directories are never skipped by size, so
+    /// [`ProcessOrder::ContentsFirst`] walks still visit every directory.
+    #[must_use]
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Walks `target` directories, which the default filter skips
+    ///
+    /// An escape hatch for tools that deliberately inspect build output. It applies to
+    /// every directory named `target` in the tree, not just the one at the root.
+    #[must_use]
+    pub fn with_include_target(mut self, include: bool) -> Self {
+        self.included_dirs.target = include;
+        self
+    }
+
+    /// Walks `.git` directories, which the default filter skips
+    ///
+    /// An escape hatch for tools that deliberately inspect repository internals, such
+    /// as hooks. Hidden entries inside `.git` are still skipped.
+    #[must_use]
+    pub fn with_include_git(mut self, include: bool) -> Self {
+        self.included_dirs.git = include;
+        self
+    }
+
+    /// Returns true if the default filter, minus the included directories, skips `entry`
+    pub(crate) fn is_excluded(&self, entry: &walkdir::DirEntry) -> bool {
+        let name = entry.file_name();
+        let included = (self.included_dirs.target && name == "target")
+            || (self.included_dirs.git && name == ".git");
+        !included && crate::is_excluded_by_default(entry)
+    }
+
+    /// Returns true if `entry` is a file larger than the maximum file size
+    pub(crate) fn is_oversized(&self, entry: &walkdir::DirEntry) -> bool {
+        let Some(max) = self.max_file_size else {
+            return false;
+        };
+        if entry.file_type().is_dir() {
+            return false;
+        }
+        let oversized = entry.metadata().is_ok_and(|metadata| metadata.len() > max);
+        if oversized {
+            debug!("Skipping file larger than {max} bytes: {}", entry.path().display());
+        }
+        oversized
+    }
+
+    /// Returns true if `entry` counts as a file for the `"*"` and glob selectors
+    pub(crate) fn is_walked_file(&self, entry: &walkdir::DirEntry) -> bool {
+        entry.file_type().is_file()
+            || (self.order == ProcessOrder::ContentsFirst && entry.path_is_symlink())
+    }
+
+    /// Returns true if the cancellation token is set and has been cancelled
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Returns the configured progress reporter, or one that ignores updates
+    pub(crate) fn progress(&self) -> Arc<dyn Progress> {
+        self.progress.clone().unwrap_or_else(|| Arc::new(NoProgress))
+    }
+
+    /// Returns true if `path` has one of the excluded extensions
+    pub(crate) fn is_excluded_extension(&self, path: &Path) -> bool {
+        self.exclude_extensions
+            .iter()
+            .any(|excluded| has_extension(path, excluded))
+    }
+}
+
+/// Gitignore-style rules loaded lazily from the ignore files of a walk
+pub(crate) struct IgnoreRules {
+    root: PathBuf,
+    file_name: String,
+    loaded: HashMap<PathBuf, Option<Gitignore>>,
+}
+
+impl IgnoreRules {
+    /// Creates the rules for a walk of `root` reading ignore files named `file_name`
+    pub(crate) fn new(root: &Path, file_name: &str) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            file_name: file_name.to_string(),
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Returns true if the ignore files of `path`'s ancestors exclude it
+    ///
+    /// The nearest ignore file with a matching rule decides, mirroring git.
+    pub(crate) fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(&self.root) {
+                break;
+            }
+            if let Some(rules) = self.rules_for(dir) {
+                let matched = rules.matched(path, is_dir);
+                if matched.is_ignore() {
+                    return true;
+                }
+                if matched.is_whitelist() {
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    /// Loads (once) the ignore file of `dir`, if there is one
+    fn rules_for(&mut self, dir: &Path) -> Option<&Gitignore> {
+        let file_name = &self.file_name;
+        self.loaded
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let path = dir.join(file_name);
+                if !path.is_file() {
+                    return None;
+                }
+                let mut builder = GitignoreBuilder::new(dir);
+                if let Some(e) = builder.add(&path) {
+                    warn!("Failed to parse {}: {e}", path.display());
+                }
+                builder
+                    .build()
+                    .inspect_err(|e| warn!("Failed to load {}: {e}", path.display()))
+                    .ok()
+            })
+            .as_ref()
+    }
+}
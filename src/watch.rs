@@ -0,0 +1,193 @@
+//! Batching of file change events.
+//!
+//! Tools that react to file changes usually want to act once after a burst of changes
+//! (a `git checkout`, a build writing many files) instead of once per event.
+//! [`debounced_batch`] sits between any source of changed paths, such as a file
+//! watcher feeding a channel, and such a tool: it coalesces the paths received during a
+//! debounce window and hands them to a callback as one deduplicated batch.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use std::time::Duration;
+//! use xio::watch::debounced_batch;
+//!
+//! async fn rebuild_on_change(events: tokio::sync::mpsc::Receiver<PathBuf>) -> anyhow::Result<()> {
+//!     debounced_batch(events, Duration::from_millis(200), |paths| {
+//!         println!("Rebuilding after {} changes", paths.len());
+//!         async { Ok(()) }
+//!     })
+//!     .await
+//! }
+//! ```
+
+use log::debug;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc::Receiver;
+use tokio::time::Instant;
+
+/// Collects changed paths into debounced batches and passes each batch to a callback.
+///
+/// The first path received opens a batch. Every further path received less than
+/// `window` after the previous one joins it, so the batch is only handed to `callback`
+/// once no new path has arrived for `window`. Repeated events for the same path within
+/// a batch are coalesced, and the batch is passed sorted. Batches are processed one at a
+/// time; paths arriving while the callback runs are queued in the channel and form the
+/// next batch. Under a steady stream of events the batch never closes; use
+/// [`debounced_batch_with_max_wait`] to bound how long a batch stays open.
+///
+/// The function returns once the sending side of `events` is closed, after flushing the
+/// last batch.
+///
+/// # Type Parameters
+///
+/// * `F` - The callback function type that implements `Fn(&[PathBuf]) -> Fut`
+/// * `Fut` - The future type returned by the callback function
+///
+/// # Arguments
+///
+/// * `events` - The channel delivering changed paths
+/// * `window` - The quiet period that closes a batch
+/// * `callback` - An async function processing each batch of distinct paths
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the channel is closed and every batch has been processed.
+///
+/// # Errors
+///
+/// Returns the first error returned by `callback`; later events are not processed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+/// use xio::watch::debounced_batch;
+///
+/// async fn print_batches() -> anyhow::Result<()> {
+///     let (sender, receiver) = tokio::sync::mpsc::channel(64);
+///     sender.send(PathBuf::from("a.txt")).await?;
+///     sender.send(PathBuf::from("a.txt")).await?;
+///     drop(sender);
+///     debounced_batch(receiver, Duration::from_millis(50), |paths| {
+///         assert_eq!(paths, [PathBuf::from("a.txt")]);
+///         async { Ok(()) }
+///     })
+///     .await
+/// }
+/// ```
+pub async fn debounced_batch<F, Fut>(
+    events: Receiver<PathBuf>,
+    window: Duration,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&[PathBuf]) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>>,
+{
+    run_batches(events, window, None, callback).await
+}
+
+/// Collects changed paths into debounced batches, flushing each batch after `max_wait`.
+///
+/// This behaves like [`debounced_batch`], except that a batch is also handed to
+/// `callback` once `max_wait` has passed since its first path arrived, even if paths
+/// keep arriving less than `window` apart. A steady stream of events, such as a long
+/// build or a log writer, then still produces a batch at least every `max_wait`
+/// instead of one batch that grows until the stream pauses.
+///
+/// # Arguments
+///
+/// * `events` - The channel delivering changed paths
+/// * `window` - The quiet period that closes a batch
+/// * `max_wait` - The longest time a batch stays open after its first path
+/// * `callback` - An async function processing each batch of distinct paths
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the channel is closed and every batch has been processed.
+///
+/// # Errors
+///
+/// Returns the first error returned by `callback`; later events are not processed.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::time::Duration;
+/// use xio::watch::debounced_batch_with_max_wait;
+///
+/// async fn rebuild_on_change(events: tokio::sync::mpsc::Receiver<PathBuf>) -> anyhow::Result<()> {
+///     debounced_batch_with_max_wait(events, Duration::from_millis(200), Duration::from_secs(5), |paths| {
+///         println!("Rebuilding after {} changes", paths.len());
+///         async { Ok(()) }
+///     })
+///     .await
+/// }
+/// ```
+pub async fn debounced_batch_with_max_wait<F, Fut>(
+    events: Receiver<PathBuf>,
+    window: Duration,
+    max_wait: Duration,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&[PathBuf]) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>>,
+{
+    run_batches(events, window, Some(max_wait), callback).await
+}
+
+/// Shared implementation of the debounced batching, with an optional maximum batch age
+async fn run_batches<F, Fut>(
+    mut events: Receiver<PathBuf>,
+    window: Duration,
+    max_wait: Option<Duration>,
+    callback: F,
+) -> anyhow::Result<()>
+where
+    F: Fn(&[PathBuf]) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>>,
+{
+    while let Some(first) = events.recv().await {
+        let deadline = max_wait.map(|max_wait| Instant::now() + max_wait);
+        let mut batch = BTreeSet::from([first]);
+        let mut closed = false;
+        loop {
+            let wait = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left.is_zero() {
+                        debug!("Batch reached its maximum wait, flushing");
+                        break;
+                    }
+                    window.min(left)
+                }
+                None => window,
+            };
+            match tokio::time::timeout(wait, events.recv()).await {
+                Ok(Some(path)) => {
+                    batch.insert(path);
+                }
+                Ok(None) => {
+                    closed = true;
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        let batch: Vec<PathBuf> = batch.into_iter().collect();
+        debug!("Processing batch of {} changed paths", batch.len());
+        callback(&batch).await?;
+        if closed {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use tempfile::TempDir;
+use xio::diff::diff_directories;
+
+#[tokio::test]
+async fn test_diff_directories() -> anyhow::Result<()> {
+    let a = TempDir::new()?;
+    let b = TempDir::new()?;
+
+    for dir in [a.path(), b.path()] {
+        std::fs::create_dir(dir.join("sub"))?;
+        std::fs::write(dir.join("same.txt"), "same")?;
+        std::fs::write(dir.join(".hidden"), dir.to_string_lossy().as_bytes())?;
+    }
+    std::fs::write(a.path().join("sub").join("removed.txt"), "gone")?;
+    std::fs::write(b.path().join("added.txt"), "new")?;
+    std::fs::write(a.path().join("resized.txt"), "short")?;
+    std::fs::write(b.path().join("resized.txt"), "much longer")?;
+    std::fs::write(a.path().join("sub").join("edited.txt"), "abc")?;
+    std::fs::write(b.path().join("sub").join("edited.txt"), "xyz")?;
+
+    // Size-only comparison misses same-size edits
+    let diff = diff_directories(a.path(), b.path(), false).await?;
+    assert_eq!(diff.only_in_a, vec![PathBuf::from("sub/removed.txt")]);
+    assert_eq!(diff.only_in_b, vec![PathBuf::from("added.txt")]);
+    assert_eq!(diff.differing, vec![PathBuf::from("resized.txt")]);
+
+    let diff = diff_directories(a.path(), b.path(), true).await?;
+    assert_eq!(
+        diff.differing,
+        vec![PathBuf::from("resized.txt"), PathBuf::from("sub/edited.txt")]
+    );
+
+    let diff = diff_directories(a.path(), a.path(), true).await?;
+    assert!(diff.is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "line-diff")]
+#[tokio::test]
+async fn test_diff_files_lines() -> anyhow::Result<()> {
+    use xio::diff::{diff_files_lines, LineChange};
+
+    let dir = TempDir::new()?;
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    std::fs::write(&a, "keep\nold\nshared\r\nend")?;
+    std::fs::write(&b, "keep\nshared\r\nnew\nend\n")?;
+
+    let changes = diff_files_lines(&a, &b).await?;
+    assert_eq!(
+        changes,
+        [
+            LineChange::Unchanged("keep".to_string()),
+            LineChange::Removed("old".to_string()),
+            LineChange::Unchanged("shared".to_string()),
+            LineChange::Added("new".to_string()),
+            LineChange::Unchanged("end".to_string()),
+        ]
+    );
+
+    let same = diff_files_lines(&a, &a).await?;
+    assert!(same.iter().all(|change| matches!(change, LineChange::Unchanged(_))));
+    assert!(diff_files_lines(&a, &dir.path().join("missing")).await.is_err());
+    Ok(())
+}
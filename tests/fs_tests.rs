@@ -1,7 +1,16 @@
 use std::fs::{self, File};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tempfile::TempDir;
-use xio::fs::{has_extension, get_files_with_extension, read_to_string};
+use xio::{FileTooLarge, ReadOptions};
+use xio::fs::{
+    compound_extension, count_files_with_extension, ensure_dir, extension_stats, file_extension,
+    find_broken_symlinks, find_files_by_name, find_files_by_stem, find_first, find_up, find_up_any,
+    get_files_with_extension, has_extension, is_dir, is_empty_dir, is_empty_file, is_file,
+    is_same_file, move_dir_merge, normalize_path, path_exists, read_dir_entries,
+    read_dir_entries_filtered, read_to_string, read_to_string_async, read_to_string_with,
+    relative_path, remove_broken_symlinks, sanitize_filename, sanitize_filename_with, touch,
+    touch_with_time, unique_path, ConflictPolicy, NO_EXTENSION,
+};
 
 #[test]
 fn test_has_extension() {
@@ -10,12 +19,37 @@ fn test_has_extension() {
     assert!(!has_extension(Path::new("test"), "txt"));
     assert!(!has_extension(Path::new(".txt"), "txt")); // Hidden file
     assert!(has_extension(Path::new("path/to/test.txt"), "txt"));
+    assert!(has_extension(Path::new("test.txt"), ".txt")); // Leading dot is ignored
+    assert!(!has_extension(Path::new("test.txt"), "..txt"));
     
     // Additional test cases
     assert!(!has_extension(Path::new(""), "txt")); // Empty path
     assert!(!has_extension(Path::new("test."), "txt")); // Empty extension
     assert!(!has_extension(Path::new("."), "txt")); // Just dot
     assert!(!has_extension(Path::new("test.txt.bak"), "txt")); // Multiple extensions
+
+    // Compound extensions match the end of the file name
+    assert!(has_extension(Path::new("archive.tar.gz"), "tar.gz"));
+    assert!(has_extension(Path::new("archive.tar.gz"), ".tar.gz"));
+    assert!(has_extension(Path::new("archive.tar.gz"), "gz"));
+    assert!(!has_extension(Path::new("archive.gz"), "tar.gz"));
+    assert!(!has_extension(Path::new("archive.xtar.gz"), "tar.gz"));
+    assert!(!has_extension(Path::new(".tar.gz"), "tar.gz")); // Hidden file
+}
+
+#[test]
+fn test_file_extension() {
+    assert_eq!(file_extension(Path::new("src/main.rs")), Some("rs"));
+    assert_eq!(file_extension(Path::new("archive.tar.gz")), Some("gz"));
+    assert_eq!(file_extension(Path::new(".bashrc")), None);
+    assert_eq!(file_extension(Path::new("Makefile")), None);
+
+    assert_eq!(compound_extension(Path::new("archive.tar.gz")).as_deref(), Some("tar.gz"));
+    assert_eq!(compound_extension(Path::new("dir.d/notes.txt")).as_deref(), Some("txt"));
+    assert_eq!(compound_extension(Path::new(".backup.tar.gz")).as_deref(), Some("tar.gz"));
+    assert_eq!(compound_extension(Path::new(".bashrc")), None);
+    assert_eq!(compound_extension(Path::new("Makefile")), None);
+    assert_eq!(compound_extension(Path::new("test.")), None);
 }
 
 #[test]
@@ -39,6 +73,10 @@ fn test_get_files_with_extension() -> anyhow::Result<()> {
     assert_eq!(files.len(), 3); // Should not include hidden file
     assert!(files.iter().all(|path| path.extension().unwrap() == "txt"));
 
+    // The extension may also be given with its leading dot
+    let dotted: Vec<_> = get_files_with_extension(temp_dir.path(), ".txt").collect();
+    assert_eq!(dotted.len(), 3);
+
     // Test .dat files
     let dat_files: Vec<_> = get_files_with_extension(temp_dir.path(), "dat").collect();
     assert_eq!(dat_files.len(), 2);
@@ -55,6 +93,80 @@ fn test_get_files_with_extension() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_count_files_with_extension() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    for dir in ["sub", "target", ".git", "dir.txt"] {
+        fs::create_dir(root.join(dir))?;
+    }
+    for file in ["a.txt", "b.dat", ".hidden.txt", "sub/c.txt", "target/d.txt", ".git/e.txt", "f.tar.gz"] {
+        File::create(root.join(file))?;
+    }
+
+    // Hidden, git and target entries are skipped, and so is the directory named like a file
+    assert_eq!(count_files_with_extension(root, "txt"), 2);
+    assert_eq!(count_files_with_extension(root, "dat"), 1);
+    assert_eq!(count_files_with_extension(root, "xyz"), 0);
+    assert_eq!(count_files_with_extension(root, "tar.gz"), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_extension_stats() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::create_dir_all(temp_dir.path().join("src"))?;
+    fs::create_dir_all(temp_dir.path().join("target"))?;
+    fs::write(temp_dir.path().join("src/main.rs"), "fn main() {}")?;
+    fs::write(temp_dir.path().join("src/LIB.RS"), "pub")?;
+    fs::write(temp_dir.path().join("Cargo.toml"), "[package]")?;
+    fs::write(temp_dir.path().join("LICENSE"), "MIT")?;
+    fs::write(temp_dir.path().join("target/out.rs"), "skipped")?;
+
+    let stats = extension_stats(temp_dir.path())?;
+    assert_eq!(stats.len(), 3);
+    assert_eq!(stats["rs"], (2, 15));
+    assert_eq!(stats["toml"], (1, 9));
+    assert_eq!(stats[NO_EXTENSION], (1, 3));
+
+    assert!(extension_stats(&temp_dir.path().join("missing")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_read_to_string_with() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("bom.txt");
+    fs::write(&file_path, "\u{feff}hello")?;
+
+    let options = ReadOptions::new().with_strip_bom(true);
+    assert_eq!(read_to_string_with(&file_path, &options)?, "hello");
+    assert_eq!(read_to_string_with(&file_path, &ReadOptions::new())?, "\u{feff}hello");
+
+    let err = read_to_string_with(&file_path, &options.with_max_bytes(4)).unwrap_err();
+    let too_large = err.downcast_ref::<FileTooLarge>().unwrap();
+    assert_eq!((too_large.size, too_large.limit), (8, 4));
+
+    let missing = temp_dir.path().join("missing.txt");
+    assert!(read_to_string_with(&missing, &options).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_to_string_async() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    fs::write(&file_path, "Hello, World!")?;
+    assert_eq!(read_to_string_async(&file_path).await?, "Hello, World!");
+
+    // Errors name the file, like the synchronous version
+    let missing = temp_dir.path().join("missing.txt");
+    let err = read_to_string_async(&missing).await.unwrap_err();
+    assert!(err.to_string().contains(&missing.display().to_string()));
+    Ok(())
+}
+
 #[test]
 fn test_read_to_string() -> anyhow::Result<()> {
     let temp_dir = TempDir::new()?;
@@ -88,4 +200,473 @@ fn test_read_to_string() -> anyhow::Result<()> {
     assert!(read_to_string(&dir_path).is_err());
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_find_files_by_name_and_stem() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    for dir in ["crates/a", "crates/b", "target/debug", ".hidden"] {
+        fs::create_dir_all(root.join(dir))?;
+    }
+    for file in [
+        "Cargo.toml",
+        "README.md",
+        "crates/a/Cargo.toml",
+        "crates/a/README",
+        "crates/b/Cargo.toml.orig",
+        "crates/b/README.txt",
+        "target/debug/Cargo.toml",
+        ".hidden/Cargo.toml",
+    ] {
+        File::create(root.join(file))?;
+    }
+
+    let mut manifests: Vec<PathBuf> = find_files_by_name(root, "Cargo.toml").collect();
+    manifests.sort();
+    assert_eq!(manifests, [root.join("Cargo.toml"), root.join("crates/a/Cargo.toml")]);
+
+    let mut readmes: Vec<PathBuf> = find_files_by_stem(root, "README").collect();
+    readmes.sort();
+    assert_eq!(
+        readmes,
+        [root.join("README.md"), root.join("crates/a/README"), root.join("crates/b/README.txt")]
+    );
+
+    // Directories never match
+    assert_eq!(find_files_by_name(root, "crates").count(), 0);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_broken_symlinks() -> std::io::Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("nested"))?;
+    File::create(root.join("target.txt"))?;
+    symlink(root.join("target.txt"), root.join("good"))?;
+    symlink(root.join("deleted.txt"), root.join("nested/dangling"))?;
+    // A link to a dangling link is dangling too
+    symlink(root.join("nested/dangling"), root.join("chained"))?;
+
+    let expected = vec![root.join("chained"), root.join("nested/dangling")];
+    assert_eq!(find_broken_symlinks(root)?, expected);
+
+    assert_eq!(remove_broken_symlinks(root, true)?, expected);
+    assert!(root.join("chained").symlink_metadata().is_ok());
+
+    assert_eq!(remove_broken_symlinks(root, false)?, expected);
+    assert!(find_broken_symlinks(root)?.is_empty());
+    assert!(root.join("good").exists());
+    assert!(root.join("target.txt").exists());
+
+    assert!(find_broken_symlinks(&root.join("does-not-exist")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_find_first() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("a/nested"))?;
+    fs::create_dir_all(root.join("b"))?;
+    File::create(root.join("a/nested/schema.sql"))?;
+    File::create(root.join("b/schema.sql"))?;
+
+    assert_eq!(
+        find_first(root, |path| path.ends_with("schema.sql"))?,
+        Some(root.join("a/nested/schema.sql"))
+    );
+    assert_eq!(find_first(root, |path| path.ends_with("missing.sql"))?, None);
+
+    // The search stops at the first match
+    let visited = std::cell::Cell::new(0);
+    find_first(root, |_| {
+        visited.set(visited.get() + 1);
+        true
+    })?;
+    assert_eq!(visited.get(), 1);
+
+    assert!(find_first(&root.join("does-not-exist"), |_| true).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_find_up() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().canonicalize()?;
+    let nested = root.join("crates/a/src");
+    fs::create_dir_all(&nested)?;
+    fs::create_dir(root.join(".git"))?;
+    File::create(root.join("Cargo.toml"))?;
+    File::create(root.join("crates/a/Cargo.toml"))?;
+
+    // The nearest marker wins, and markers may be directories
+    assert_eq!(find_up(&nested, "Cargo.toml"), Some(root.join("crates/a")));
+    assert_eq!(find_up(&nested, ".git"), Some(root.clone()));
+    assert_eq!(find_up(&root, "Cargo.toml"), Some(root.clone()));
+    assert_eq!(find_up(&nested, "xio-no-such-marker"), None);
+
+    // Earlier markers take precedence within the same directory
+    assert_eq!(
+        find_up_any(&root, &[".git", "Cargo.toml"]),
+        Some((root.clone(), ".git"))
+    );
+    assert_eq!(
+        find_up_any(&nested, &[".git", "Cargo.toml"]),
+        Some((root.join("crates/a"), "Cargo.toml"))
+    );
+
+    // `..` components are resolved before ascending, starting the search at `crates`
+    assert_eq!(find_up(&nested.join("../../b/.."), "Cargo.toml"), Some(root.clone()));
+    Ok(())
+}
+
+#[test]
+fn test_sanitize_filename() {
+    assert_eq!(sanitize_filename("notes.txt"), "notes.txt");
+    assert_eq!(sanitize_filename("a/b\\c:d*e?f\"g<h>i|j\u{7}"), "a_b_c_d_e_f_g_h_i_j_");
+    assert_eq!(sanitize_filename("trailing. . "), "trailing");
+    assert_eq!(sanitize_filename("NUL"), "NUL_");
+    assert_eq!(sanitize_filename("com1.tar.gz"), "com1_.tar.gz");
+    assert_eq!(sanitize_filename("COM0"), "COM0");
+    assert_eq!(sanitize_filename("console"), "console");
+    assert_eq!(sanitize_filename(""), "_");
+    assert_eq!(sanitize_filename(".."), "_");
+
+    // An illegal replacement removes the characters instead
+    assert_eq!(sanitize_filename_with("a/b", '/', 255), "ab");
+    assert_eq!(sanitize_filename_with("???", '?', 255), "_");
+
+    // Truncation keeps the extension and respects character boundaries
+    assert_eq!(sanitize_filename_with("abcdefgh.txt", '_', 8), "abcd.txt");
+    assert_eq!(sanitize_filename_with("ééééé", '_', 5), "éé");
+    assert_eq!(sanitize_filename_with("abcdefgh", '_', 3), "abc");
+    assert!(sanitize_filename(&"x".repeat(300)).len() <= 255);
+
+    // The reserved-name suffix stays within the length limit
+    assert_eq!(sanitize_filename_with("CON", '_', 3), "CO_");
+    assert_eq!(sanitize_filename_with("con.txt", '_', 7), "co_.txt");
+    assert_eq!(sanitize_filename_with("nul", '\u{2026}', 4), "n\u{2026}");
+    assert_eq!(sanitize_filename_with("CON", '_', 4), "CON_");
+}
+
+#[test]
+fn test_normalize_path() {
+    let cases = [
+        ("./dir/../other/file.txt", "other/file.txt"),
+        ("a/./b/./c", "a/b/c"),
+        ("a/b/../../c", "c"),
+        ("a/..", "."),
+        ("", "."),
+        (".", "."),
+        // Leading `..` of a relative path are kept
+        ("../a/../../b", "../../b"),
+        ("..", ".."),
+        // `..` cannot climb above the root of an absolute path
+        ("/../etc/./hosts", "/etc/hosts"),
+        ("/a/b/../..", "/"),
+        // Does not require the path to exist
+        ("/does/not/exist/../yet", "/does/not/yet"),
+    ];
+
+    for (input, expected) in cases {
+        assert_eq!(normalize_path(Path::new(input)), PathBuf::from(expected), "{input}");
+    }
+}
+
+#[test]
+fn test_relative_path() {
+    // Nested paths
+    assert_eq!(
+        relative_path(Path::new("/data/images/cat.png"), Path::new("/data")),
+        Some(PathBuf::from("images/cat.png"))
+    );
+    assert_eq!(
+        relative_path(Path::new("src/split/mod.rs"), Path::new("src/")),
+        Some(PathBuf::from("split/mod.rs"))
+    );
+
+    // Equal paths
+    assert_eq!(
+        relative_path(Path::new("/data"), Path::new("/data")),
+        Some(PathBuf::new())
+    );
+
+    // Unrelated paths
+    assert_eq!(relative_path(Path::new("/other/cat.png"), Path::new("/data")), None);
+    assert_eq!(relative_path(Path::new("/database/x"), Path::new("/data")), None);
+    assert_eq!(relative_path(Path::new("data/x"), Path::new("/data")), None);
+}
+
+#[tokio::test]
+async fn test_ensure_dir() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Creates nested directories
+    let nested = temp_dir.path().join("a").join("b").join("c");
+    ensure_dir(&nested).await?;
+    assert!(nested.is_dir());
+
+    // Succeeds if the directory already exists
+    ensure_dir(&nested).await?;
+
+    // Fails if the path is a file
+    let file_path = temp_dir.path().join("file.txt");
+    File::create(&file_path)?;
+    assert!(ensure_dir(&file_path).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_is_empty_file() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let empty = temp_dir.path().join("empty.txt");
+    File::create(&empty)?;
+    assert!(is_empty_file(&empty).await?);
+
+    let non_empty = temp_dir.path().join("non_empty.txt");
+    fs::write(&non_empty, "content")?;
+    assert!(!is_empty_file(&non_empty).await?);
+
+    assert!(is_empty_file(&temp_dir.path().join("missing.txt")).await.is_err());
+    assert!(is_empty_file(temp_dir.path()).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_is_empty_dir() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    assert!(is_empty_dir(temp_dir.path()).await?);
+
+    let sub_dir = temp_dir.path().join("subdir");
+    fs::create_dir(&sub_dir)?;
+    assert!(!is_empty_dir(temp_dir.path()).await?);
+    assert!(is_empty_dir(&sub_dir).await?);
+
+    let file_path = temp_dir.path().join("file.txt");
+    File::create(&file_path)?;
+    assert!(is_empty_dir(&file_path).await.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_is_same_file() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let a = temp_dir.path().join("a.txt");
+    let b = temp_dir.path().join("b.txt");
+    fs::write(&a, "same content")?;
+    fs::write(&b, "same content")?;
+
+    assert!(is_same_file(&a, &a)?);
+    assert!(is_same_file(&a, &temp_dir.path().join(".").join("a.txt"))?);
+    assert!(!is_same_file(&a, &b)?);
+
+    let hard_link = temp_dir.path().join("hard.txt");
+    fs::hard_link(&a, &hard_link)?;
+    assert!(is_same_file(&a, &hard_link)?);
+
+    #[cfg(unix)]
+    {
+        let symlink = temp_dir.path().join("link.txt");
+        std::os::unix::fs::symlink(&a, &symlink)?;
+        assert!(is_same_file(&symlink, &a)?);
+    }
+
+    assert!(is_same_file(&a, &temp_dir.path().join("missing")).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_async_existence_checks() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file = temp_dir.path().join("file.txt");
+    let missing = temp_dir.path().join("missing");
+    File::create(&file)?;
+
+    assert!(path_exists(&file).await);
+    assert!(path_exists(temp_dir.path()).await);
+    assert!(!path_exists(&missing).await);
+
+    assert!(is_file(&file).await?);
+    assert!(!is_file(temp_dir.path()).await?);
+    assert!(!is_file(&missing).await?);
+
+    assert!(is_dir(temp_dir.path()).await?);
+    assert!(!is_dir(&file).await?);
+    assert!(!is_dir(&missing).await?);
+
+    // A path below a file cannot be inspected, which is reported as an error
+    #[cfg(unix)]
+    assert!(is_file(&file.join("child")).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_dir_entries() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    fs::create_dir(temp_dir.path().join("sub"))?;
+    fs::create_dir(temp_dir.path().join("target"))?;
+    File::create(temp_dir.path().join("sub").join("nested.txt"))?;
+    File::create(temp_dir.path().join("b.txt"))?;
+    File::create(temp_dir.path().join(".hidden"))?;
+
+    let entries = read_dir_entries(temp_dir.path()).await?;
+    let names: Vec<_> = entries.iter().map(|e| e.name.to_string_lossy().into_owned()).collect();
+    assert_eq!(names, [".hidden", "b.txt", "sub", "target"]);
+
+    let file = &entries[1];
+    assert_eq!(file.path, temp_dir.path().join("b.txt"));
+    assert!(file.is_file && !file.is_dir && !file.is_symlink);
+    let dir = &entries[2];
+    assert!(dir.is_dir && !dir.is_file && !dir.is_symlink);
+
+    let filtered = read_dir_entries_filtered(temp_dir.path()).await?;
+    let names: Vec<_> = filtered.iter().map(|e| e.name.to_string_lossy().into_owned()).collect();
+    assert_eq!(names, ["b.txt", "sub"]);
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(temp_dir.path().join("sub"), temp_dir.path().join("link"))?;
+        let entries = read_dir_entries(temp_dir.path()).await?;
+        let link = entries.iter().find(|e| e.name == "link").unwrap();
+        assert!(link.is_symlink && !link.is_dir && !link.is_file);
+    }
+
+    assert!(read_dir_entries(&temp_dir.path().join("missing")).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_touch() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("sentinel");
+
+    // Creates a missing file
+    touch(&path).await?;
+    assert_eq!(fs::read_to_string(&path)?, "");
+
+    // Updates the timestamps of an existing file without changing its content
+    fs::write(&path, "content")?;
+    let past = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+    touch_with_time(&path, past).await?;
+    assert_eq!(fs::metadata(&path)?.modified()?, past);
+    assert_eq!(fs::read_to_string(&path)?, "content");
+
+    touch(&path).await?;
+    assert!(fs::metadata(&path)?.modified()? > past);
+
+    assert!(touch(temp_dir.path()).await.is_err());
+    assert!(touch(&temp_dir.path().join("missing/sentinel")).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_move_dir_merge() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    let setup = |root: &Path| -> std::io::Result<()> {
+        for dir in ["src/shared", "src/new", "dst/shared"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        for (file, content) in [
+            ("src/top.txt", "src"),
+            ("src/shared/a.txt", "src"),
+            ("src/shared/clash.txt", "src"),
+            ("src/new/b.txt", "src"),
+            ("dst/shared/clash.txt", "dst"),
+            ("dst/shared/keep.txt", "dst"),
+        ] {
+            fs::write(root.join(file), content)?;
+        }
+        Ok(())
+    };
+
+    // Skip leaves the clashing file behind, along with its directories
+    setup(root)?;
+    let skipped = move_dir_merge(&root.join("src"), &root.join("dst"), ConflictPolicy::Skip).await?;
+    assert_eq!(skipped, [root.join("src/shared/clash.txt")]);
+    assert_eq!(fs::read_to_string(root.join("dst/shared/clash.txt"))?, "dst");
+    for file in ["dst/top.txt", "dst/shared/a.txt", "dst/shared/keep.txt", "dst/new/b.txt"] {
+        assert!(root.join(file).is_file(), "{file} missing");
+    }
+    assert!(!root.join("src/new").exists());
+    assert!(root.join("src/shared/clash.txt").exists());
+
+    // Overwrite replaces the clashing file and removes the emptied source
+    fs::remove_dir_all(root.join("dst"))?;
+    fs::remove_dir_all(root.join("src"))?;
+    setup(root)?;
+    let skipped = move_dir_merge(&root.join("src"), &root.join("dst"), ConflictPolicy::Overwrite).await?;
+    assert!(skipped.is_empty());
+    assert_eq!(fs::read_to_string(root.join("dst/shared/clash.txt"))?, "src");
+    assert!(!root.join("src").exists());
+
+    // Error stops at the clash
+    fs::remove_dir_all(root.join("dst"))?;
+    setup(root)?;
+    let err = move_dir_merge(&root.join("src"), &root.join("dst"), ConflictPolicy::Error)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+    // Rename keeps both versions of the clashing file
+    fs::remove_dir_all(root.join("dst"))?;
+    fs::remove_dir_all(root.join("src"))?;
+    setup(root)?;
+    fs::create_dir(root.join("dst/top.txt"))?;
+    let skipped = move_dir_merge(&root.join("src"), &root.join("dst"), ConflictPolicy::Rename).await?;
+    assert!(skipped.is_empty());
+    assert_eq!(fs::read_to_string(root.join("dst/shared/clash.txt"))?, "dst");
+    assert_eq!(fs::read_to_string(root.join("dst/shared/clash (1).txt"))?, "src");
+    assert!(root.join("dst/top.txt").is_dir());
+    assert!(root.join("dst/top (1).txt").is_file());
+    assert!(!root.join("src").exists());
+
+    // A missing destination is created
+    let moved = move_dir_merge(&root.join("dst"), &root.join("fresh/dst"), ConflictPolicy::Error).await?;
+    assert!(moved.is_empty());
+    assert!(root.join("fresh/dst/shared/keep.txt").is_file());
+
+    // A directory cannot be merged into itself or into one of its subdirectories
+    let fresh = root.join("fresh");
+    for dst in [fresh.clone(), fresh.join("dst").join(".."), fresh.join("dst/nested")] {
+        for policy in [ConflictPolicy::Rename, ConflictPolicy::Overwrite, ConflictPolicy::Skip] {
+            let err = move_dir_merge(&fresh, &dst, policy).await.unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+    assert!(root.join("fresh/dst/shared/keep.txt").is_file());
+    assert!(!root.join("fresh/dst/nested").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unique_path() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+
+    assert_eq!(unique_path(&root.join("report.txt")).await?, root.join("report.txt"));
+
+    File::create(root.join("report.txt"))?;
+    assert_eq!(unique_path(&root.join("report.txt")).await?, root.join("report (1).txt"));
+    File::create(root.join("report (1).txt"))?;
+    assert_eq!(unique_path(&root.join("report.txt")).await?, root.join("report (2).txt"));
+
+    fs::create_dir(root.join("notes"))?;
+    assert_eq!(unique_path(&root.join("notes")).await?, root.join("notes (1)"));
+    File::create(root.join("archive.tar.gz"))?;
+    assert_eq!(
+        unique_path(&root.join("archive.tar.gz")).await?,
+        root.join("archive.tar (1).gz")
+    );
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+use tempfile::TempDir;
+use xio::hash::{
+    hash_file, hash_file_with, verify_checksum_manifest, write_checksum_manifest, HashAlgo,
+    HashEncoding, Mismatch,
+};
+
+#[tokio::test]
+async fn test_hash_file() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "abc")?;
+
+    assert_eq!(
+        hash_file(&file_path).await?,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert!(hash_file(&temp_dir.path().join("missing.txt")).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hash_file_with() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "abc")?;
+
+    assert_eq!(
+        hash_file_with(&file_path, HashAlgo::default(), HashEncoding::Hex).await?,
+        hash_file(&file_path).await?
+    );
+    assert_eq!(
+        hash_file_with(&file_path, HashAlgo::Sha256, HashEncoding::Base64).await?,
+        "ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0="
+    );
+
+    #[cfg(feature = "blake3")]
+    assert_eq!(
+        hash_file_with(&file_path, HashAlgo::Blake3, HashEncoding::Hex).await?,
+        "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+    );
+    #[cfg(feature = "md5")]
+    assert_eq!(
+        hash_file_with(&file_path, HashAlgo::Md5, HashEncoding::Hex).await?,
+        "900150983cd24fb0d6963f7d28e17f72"
+    );
+    #[cfg(feature = "xxhash")]
+    assert_eq!(
+        hash_file_with(&file_path, HashAlgo::Xxh3, HashEncoding::Hex).await?,
+        "78af5f94892f3950"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_checksum_manifest() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let dir = temp_dir.path();
+    std::fs::create_dir(dir.join("sub"))?;
+    std::fs::write(dir.join("a.png"), "a")?;
+    std::fs::write(dir.join("sub").join("b.png"), "b")?;
+    std::fs::write(dir.join("notes.txt"), "notes")?;
+
+    let manifest = dir.join("SHA256SUMS");
+    assert_eq!(write_checksum_manifest(dir, Some("png"), &manifest).await?, 2);
+
+    let content = std::fs::read_to_string(&manifest)?;
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("  a.png"));
+    assert!(lines[1].ends_with("  sub/b.png"));
+
+    // notes.txt was excluded by the extension filter, so it is only extra without it
+    assert!(verify_checksum_manifest(dir, Some("png"), &manifest).await?.is_empty());
+    assert_eq!(
+        verify_checksum_manifest(dir, None, &manifest).await?,
+        vec![Mismatch::Extra(PathBuf::from("notes.txt"))]
+    );
+
+    std::fs::remove_file(dir.join("notes.txt"))?;
+    std::fs::write(dir.join("a.png"), "corrupted")?;
+    std::fs::remove_file(dir.join("sub").join("b.png"))?;
+    std::fs::write(dir.join("c.png"), "c")?;
+    assert_eq!(
+        verify_checksum_manifest(dir, None, &manifest).await?,
+        vec![
+            Mismatch::Corrupted(PathBuf::from("a.png")),
+            Mismatch::Missing(PathBuf::from("sub/b.png")),
+            Mismatch::Extra(PathBuf::from("c.png")),
+        ]
+    );
+
+    // A rerun does not list the previous manifest, however its path is spelled
+    let dir_name = dir.file_name().unwrap();
+    let aliased = dir.join("..").join(dir_name).join("SHA256SUMS");
+    assert_eq!(write_checksum_manifest(dir, None, &aliased).await?, 2);
+    assert_eq!(write_checksum_manifest(dir, None, &aliased).await?, 2);
+    assert!(verify_checksum_manifest(dir, None, &manifest).await?.is_empty());
+
+    // Malformed manifests are rejected
+    std::fs::write(&manifest, "not a manifest line")?;
+    assert!(verify_checksum_manifest(dir, None, &manifest).await.is_err());
+
+    Ok(())
+}
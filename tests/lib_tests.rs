@@ -1,11 +1,27 @@
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tempfile::TempDir;
 use tokio::sync::Mutex;
+use xio::fs::get_files_with_extension;
+use xio::walk::DEFAULT_IGNORE_FILE;
 use xio::{
-    check_file_for_multiple_lines, delete_files_with_extension, is_git_dir, is_hidden,
-    is_target_dir, open_files_in_neovim, process_file, process_rust_file, read_file_content,
-    read_lines, walk_directory, walk_rust_files, write_to_file,
+    analyze_rust_file, check_file_for_multiple_lines, collect_matching_files, concat_files,
+    copy_file_preserving, copy_file_with_progress, dedup_lines_in_file, dedup_sorted_lines_in_file,
+    delete_files_with_extension, delete_files_with_extension_with_callback,
+    delete_files_with_extension_with_progress, ensure_trailing_newline, fold_files, is_git_dir,
+    is_hidden, is_target_dir, map_files, open_files_detached, open_files_in_neovim,
+    open_matching_files, partition_files, process_file, process_paths, process_rust_file,
+    prune_keep_latest, read_file_content, read_file_content_lossy, read_file_content_with,
+    read_lines, read_lines_buffered, read_lines_filtered, read_lines_limited, read_numbered_lines,
+    read_numbered_lines_with, remove_dir_all_reporting, remove_dir_all_reporting_with_progress,
+    resume_copy_file_with_progress, split_file_by_bytes, split_file_by_lines,
+    strip_trailing_whitespace, transform_file_lines, walk_directories, walk_directory,
+    walk_directory_entries, walk_directory_glob, walk_directory_to_channel, walk_directory_until,
+    walk_directory_with, walk_directory_with_metadata, walk_directory_with_report, walk_rust_files,
+    write_and_return, write_lines, write_temp, write_to_file, write_to_file_verified,
+    write_to_file_with_backup, CancellationToken, DEFAULT_BUFFER_CAPACITY, FileTooLarge, PathMode,
+    ProcessOrder, Progress, ReadOptions, RustFileReport, TrimMode, WalkAction, WalkOptions,
 };
 
 fn get_dir_entry(path: &Path) -> walkdir::DirEntry {
@@ -145,6 +161,119 @@ async fn test_read_lines() -> std::io::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_read_lines_buffered() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    let content: String = (0..1000).map(|i| format!("  line {i}  \n")).collect();
+    std::fs::write(&file_path, &content)?;
+
+    let expected = read_lines(&file_path).await?;
+    assert_eq!(expected.len(), 1000);
+    // Lines spanning buffer boundaries are reassembled, whatever the capacity
+    for capacity in [1, 7, DEFAULT_BUFFER_CAPACITY, 1024 * 1024] {
+        assert_eq!(read_lines_buffered(&file_path, capacity).await?, expected);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_lines_limited() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "  one  \ntwo\nthree\n")?;
+
+    assert_eq!(read_lines_limited(&file_path, 2).await?, ["one", "two"]);
+    assert_eq!(read_lines_limited(&file_path, 10).await?, ["one", "two", "three"]);
+    assert!(read_lines_limited(&file_path, 0).await?.is_empty());
+
+    // Reading stops before invalid UTF-8 further down the file
+    std::fs::write(&file_path, b"ok\n\xff\xfe\n")?;
+    assert_eq!(read_lines_limited(&file_path, 1).await?, ["ok"]);
+    assert!(read_lines_limited(&file_path, 2).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_lines_filtered() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("include.txt");
+    std::fs::write(&file_path, "# header\nsrc/a.rs\n\n   \n  # indented\n  src/b.rs  # note\n")?;
+
+    assert_eq!(
+        read_lines_filtered(&file_path, Some("#"), true).await?,
+        ["src/a.rs", "src/b.rs  # note"]
+    );
+    assert_eq!(
+        read_lines_filtered(&file_path, Some("#"), false).await?,
+        ["src/a.rs", "", "", "src/b.rs  # note"]
+    );
+    // Without a prefix only blank lines are dropped, and an empty prefix is ignored
+    assert_eq!(read_lines_filtered(&file_path, None, true).await?.len(), 4);
+    assert_eq!(read_lines_filtered(&file_path, Some(""), false).await?, read_lines(&file_path).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_numbered_lines() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "first\r\n\n  indented  \nlast")?;
+
+    let lines = read_numbered_lines(&file_path).await?;
+    assert_eq!(
+        lines,
+        [
+            (1, "first".to_string()),
+            (2, String::new()),
+            (3, "  indented  ".to_string()),
+            (4, "last".to_string()),
+        ]
+    );
+
+    let lines = read_numbered_lines_with(&file_path, TrimMode::Trailing).await?;
+    assert_eq!(lines[2], (3, "  indented".to_string()));
+    let lines = read_numbered_lines_with(&file_path, TrimMode::Both).await?;
+    assert_eq!(lines[2], (3, "indented".to_string()));
+    assert_eq!(lines.len(), 4);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_file_content_lossy() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("mostly.log");
+    std::fs::write(&file_path, b"ok line\nbad \xFF byte\n")?;
+
+    assert!(read_file_content(&file_path).await.is_err());
+    assert_eq!(read_file_content_lossy(&file_path).await?, "ok line\nbad \u{FFFD} byte\n");
+
+    std::fs::write(&file_path, "valid")?;
+    assert_eq!(read_file_content_lossy(&file_path).await?, "valid");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_read_file_content_with() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("bom.txt");
+    std::fs::write(&file_path, "\u{feff}hello")?;
+
+    let content = read_file_content_with(&file_path, &ReadOptions::new()).await?;
+    assert_eq!(content, "\u{feff}hello");
+    let options = ReadOptions::new().with_strip_bom(true).with_max_bytes(8);
+    assert_eq!(read_file_content_with(&file_path, &options).await?, "hello");
+
+    let err = read_file_content_with(&file_path, &ReadOptions::new().with_max_bytes(7))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::FileTooLarge);
+    let too_large = err.get_ref().unwrap().downcast_ref::<FileTooLarge>().unwrap();
+    assert_eq!((too_large.size, too_large.limit), (8, 7));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_read_file_content() -> std::io::Result<()> {
     let temp_dir = TempDir::new()?;
@@ -177,98 +306,1651 @@ async fn test_write_to_file() -> std::io::Result<()> {
 }
 
 #[tokio::test]
-async fn test_delete_files_with_extension() -> std::io::Result<()> {
+async fn test_write_to_file_verified() -> std::io::Result<()> {
     let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("manifest.json");
 
-    // Create test files
-    std::fs::File::create(temp_dir.path().join("test1.txt"))?;
-    std::fs::File::create(temp_dir.path().join("test2.txt"))?;
-    std::fs::File::create(temp_dir.path().join("test.rs"))?;
+    assert_eq!(write_to_file_verified(&file_path, "{\"files\": 3}").await?, 12);
+    assert_eq!(std::fs::read_to_string(&file_path)?, "{\"files\": 3}");
 
-    delete_files_with_extension(temp_dir.path(), "txt").await?;
+    // Overwriting with shorter content truncates the old file
+    assert_eq!(write_to_file_verified(&file_path, "").await?, 0);
+    assert_eq!(std::fs::metadata(&file_path)?.len(), 0);
 
-    let entries: Vec<_> = std::fs::read_dir(temp_dir.path())?
-        .filter_map(Result::ok)
-        .collect();
+    let missing_dir = temp_dir.path().join("missing").join("out.txt");
+    assert!(write_to_file_verified(&missing_dir, "x").await.is_err());
+    Ok(())
+}
 
-    assert_eq!(entries.len(), 1);
-    assert_eq!(
-        entries[0].path().extension().unwrap().to_string_lossy(),
-        "rs"
-    );
+#[tokio::test]
+async fn test_write_temp() -> std::io::Result<()> {
+    let temp = write_temp("{\"a\": 1}", Some(".json")).await?;
+    let path = temp.to_path_buf();
+    assert!(path.to_string_lossy().ends_with(".json"));
+    assert_eq!(std::fs::read_to_string(&path)?, "{\"a\": 1}");
+
+    let other = write_temp(b"bytes", None).await?;
+    assert_ne!(other.to_path_buf(), path);
 
+    // The file is deleted when the guard drops
+    drop(temp);
+    assert!(!path.exists());
     Ok(())
 }
 
 #[tokio::test]
-async fn test_check_file_for_multiple_lines() -> anyhow::Result<()> {
+async fn test_walk_directory_with_metadata() -> anyhow::Result<()> {
     let temp_dir = TempDir::new()?;
-    let multi_line_files = Arc::new(Mutex::new(Vec::new()));
+    std::fs::write(temp_dir.path().join("a.txt"), "12345")?;
+    std::fs::write(temp_dir.path().join("b.txt"), "")?;
+    std::fs::write(temp_dir.path().join("c.rs"), "ignored")?;
 
-    // Create test files
-    let single_line = temp_dir.path().join("single.txt");
-    std::fs::write(&single_line, "Single line")?;
+    let sizes = Arc::new(Mutex::new(Vec::new()));
+    let sizes_clone = Arc::clone(&sizes);
+    let options = WalkOptions::new();
+    walk_directory_with_metadata(temp_dir.path(), "txt", &options, move |path, metadata| {
+        let sizes = Arc::clone(&sizes_clone);
+        let entry = (path.file_name().unwrap().to_string_lossy().into_owned(), metadata.len());
+        async move {
+            sizes.lock().await.push(entry);
+            Ok(())
+        }
+    })
+    .await?;
 
-    let multi_line = temp_dir.path().join("multi.txt");
-    std::fs::write(&multi_line, "Line 1\nLine 2")?;
+    let mut sizes = sizes.lock().await.clone();
+    sizes.sort();
+    assert_eq!(sizes, vec![("a.txt".to_string(), 5), ("b.txt".to_string(), 0)]);
+    Ok(())
+}
 
-    check_file_for_multiple_lines(&single_line, Arc::clone(&multi_line_files)).await?;
-    check_file_for_multiple_lines(&multi_line, Arc::clone(&multi_line_files)).await?;
+#[tokio::test]
+async fn test_walk_directory_modified_order() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let now = std::time::SystemTime::now();
+    for (name, age) in [("old.txt", 300), ("new.txt", 0), ("mid.txt", 100)] {
+        let file = std::fs::File::create(temp_dir.path().join(name))?;
+        file.set_modified(now - std::time::Duration::from_secs(age))?;
+    }
 
-    let files = multi_line_files.lock().await;
-    assert_eq!(files.len(), 1);
-    assert_eq!(files[0], multi_line);
+    for (order, expected) in [
+        (ProcessOrder::ModifiedDesc, ["new.txt", "mid.txt", "old.txt"]),
+        (ProcessOrder::ModifiedAsc, ["old.txt", "mid.txt", "new.txt"]),
+    ] {
+        let visited = Arc::new(Mutex::new(Vec::new()));
+        let visited_clone = Arc::clone(&visited);
+        let options = WalkOptions::new().with_order(order);
+        walk_directory_with(temp_dir.path(), "txt", &options, move |path| {
+            let visited = Arc::clone(&visited_clone);
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            async move {
+                visited.lock().await.push(name);
+                Ok(())
+            }
+        })
+        .await?;
+        assert_eq!(*visited.lock().await, expected);
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_walk_directory_dedup_by_realpath() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let real = temp_dir.path().join("real");
+    std::fs::create_dir(&real)?;
+    std::fs::write(real.join("file.txt"), "content")?;
+    std::os::unix::fs::symlink(real.join("file.txt"), temp_dir.path().join("link.txt"))?;
+    std::os::unix::fs::symlink(&real, temp_dir.path().join("alias"))?;
 
+    for (dedup, expected) in [(false, 3), (true, 1)] {
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = Arc::clone(&count);
+        let options = WalkOptions::new().with_dedup_by_realpath(dedup);
+        walk_directory_with(temp_dir.path(), "txt", &options, move |_| {
+            count_clone.fetch_add(1, Ordering::SeqCst);
+            async { Ok(()) }
+        })
+        .await?;
+        assert_eq!(count.load(Ordering::SeqCst), expected);
+    }
     Ok(())
 }
 
+#[cfg(unix)]
 #[tokio::test]
-async fn test_open_files_in_neovim() -> anyhow::Result<()> {
-    // Test empty file list
-    let empty_files: Vec<PathBuf> = vec![];
-    open_files_in_neovim(&empty_files, None).await?;
+async fn test_walk_directory_with_report() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::write(temp_dir.path().join("a.txt"), "a")?;
+    std::fs::write(temp_dir.path().join("b.txt"), "b")?;
 
-    // Test with files using echo instead of nvim
-    let files = vec![PathBuf::from("test1.txt"), PathBuf::from("test2.txt")];
-    open_files_in_neovim(&files, Some("echo")).await?;
+    let options = WalkOptions::new();
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, |_| async { Ok(()) }).await?;
+    assert_eq!(report.processed, 2);
+    assert!(report.is_complete());
+
+    // A dangling symlink cannot be followed and is reported instead of silently dropped
+    std::os::unix::fs::symlink(temp_dir.path().join("missing"), temp_dir.path().join("broken.txt"))?;
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, |_| async { Ok(()) }).await?;
+    assert_eq!(report.processed, 2);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].path(), Some(temp_dir.path().join("broken.txt").as_path()));
     Ok(())
 }
 
 #[tokio::test]
-async fn test_process_file() -> anyhow::Result<()> {
+async fn test_walk_directory_glob() -> anyhow::Result<()> {
     let temp_dir = TempDir::new()?;
-    let file_path = temp_dir.path().join("test.txt");
-    std::fs::write(&file_path, "Test content")?;
+    let root = temp_dir.path();
+    std::fs::create_dir_all(root.join("src").join("walk"))?;
+    std::fs::create_dir_all(root.join("crates").join("tests"))?;
+    std::fs::create_dir_all(root.join("target"))?;
+    std::fs::write(root.join("src").join("mod.rs"), "")?;
+    std::fs::write(root.join("src").join("walk").join("mod.rs"), "")?;
+    std::fs::write(root.join("src").join("walk").join("other.rs"), "")?;
+    std::fs::write(root.join("crates").join("tests").join("it.rs"), "")?;
+    std::fs::write(root.join("target").join("mod.rs"), "")?;
 
-    let processed = Arc::new(Mutex::new(false));
-    let processed_clone = Arc::clone(&processed);
+    for (pattern, expected) in [
+        ("src/**/mod.rs", vec!["src/mod.rs", "src/walk/mod.rs"]),
+        ("**/tests/**", vec!["crates/tests/it.rs"]),
+        ("src/*.rs", vec!["src/mod.rs"]),
+    ] {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = Arc::clone(&seen);
+        let root_clone = root.to_path_buf();
+        walk_directory_glob(root, pattern, move |path: &Path| {
+            let seen = Arc::clone(&seen_clone);
+            let path = path.strip_prefix(&root_clone).unwrap().to_path_buf();
+            async move {
+                seen.lock().await.push(path);
+                Ok(())
+            }
+        })
+        .await?;
 
-    process_file(&file_path, move |_| {
-        let processed = Arc::clone(&processed_clone);
+        let mut seen = seen.lock().await.clone();
+        seen.sort();
+        let expected: Vec<PathBuf> = expected.into_iter().map(PathBuf::from).collect();
+        assert_eq!(seen, expected, "pattern {pattern}");
+    }
+
+    assert!(walk_directory_glob(root, "src/[", |_| async { Ok(()) }).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_until() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..5 {
+        std::fs::write(temp_dir.path().join(format!("{i}.txt")), "x")?;
+    }
+
+    // Ordered walks run one callback at a time, so the stop takes effect immediately
+    let seen = Arc::new(AtomicUsize::new(0));
+    let seen_clone = Arc::clone(&seen);
+    let options = WalkOptions::new().with_order(ProcessOrder::ModifiedAsc);
+    walk_directory_until(temp_dir.path(), "txt", &options, move |_| {
+        let count = seen_clone.fetch_add(1, Ordering::SeqCst) + 1;
+        async move { Ok(if count == 2 { WalkAction::Stop } else { WalkAction::Continue }) }
+    })
+    .await?;
+    assert_eq!(seen.load(Ordering::SeqCst), 2);
+
+    // Stopping is not an error, even when other callbacks were already running
+    let seen = Arc::new(AtomicUsize::new(0));
+    let seen_clone = Arc::clone(&seen);
+    walk_directory_until(temp_dir.path(), "txt", &WalkOptions::new(), move |_| {
+        seen_clone.fetch_add(1, Ordering::SeqCst);
+        async { Ok(WalkAction::Stop) }
+    })
+    .await?;
+    assert!((1..=5).contains(&seen.load(Ordering::SeqCst)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_limit() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..10 {
+        std::fs::write(temp_dir.path().join(format!("{i}.txt")), "x")?;
+    }
+
+    for order in [ProcessOrder::Walk, ProcessOrder::ModifiedDesc] {
+        let options = WalkOptions::new().with_limit(3).with_order(order);
+        let report = walk_directory_with_report(temp_dir.path(), "txt", &options, |_| async { Ok(()) }).await?;
+        assert_eq!(report.processed, 3, "order {order:?}");
+    }
+
+    // A limit above the number of matches processes everything
+    let options = WalkOptions::new().with_limit(100);
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, |_| async { Ok(()) }).await?;
+    assert_eq!(report.processed, 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_cancellation() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..10 {
+        std::fs::write(temp_dir.path().join(format!("{i}.txt")), "x")?;
+    }
+
+    // Ordered walks run one callback at a time, so cancelling in the first one stops the walk
+    let token = CancellationToken::new();
+    let options = WalkOptions::new()
+        .with_order(ProcessOrder::ModifiedAsc)
+        .with_cancellation(token.clone());
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, move |_| {
+        token.cancel();
+        async { Ok(()) }
+    })
+    .await?;
+    assert!(report.cancelled);
+    assert_eq!(report.processed, 1);
+
+    // Cancelling after every file is queued still reports the files workers skipped
+    let token = CancellationToken::new();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_clone = Arc::clone(&calls);
+    let options = WalkOptions::new().with_workers(1).with_cancellation(token.clone());
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, move |_| {
+        if calls_clone.fetch_add(1, Ordering::SeqCst) == 2 {
+            token.cancel();
+        }
+        async { Ok(()) }
+    })
+    .await?;
+    assert!(report.cancelled);
+    assert_eq!(report.processed, 3);
+
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &WalkOptions::new(), |_| async { Ok(()) }).await?;
+    assert!(!report.cancelled);
+    assert_eq!(report.processed, 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_contents_first() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().join("tree");
+    std::fs::create_dir_all(root.join("a").join("b"))?;
+    for file in ["a/b/c.txt", "a/d.txt", "e.txt"] {
+        std::fs::write(root.join(file), "x")?;
+    }
+
+    // Deleting every entry as it is visited removes the whole tree
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = Arc::clone(&seen);
+    let options = WalkOptions::new().with_order(ProcessOrder::ContentsFirst);
+    walk_directory_with(&root, "*", &options, move |path: &Path| {
+        let seen = Arc::clone(&seen_clone);
+        let path = path.to_path_buf();
         async move {
-            let mut p = processed.lock().await;
-            *p = true;
+            if path.is_dir() {
+                tokio::fs::remove_dir(&path).await?;
+            } else {
+                tokio::fs::remove_file(&path).await?;
+            }
+            seen.lock().await.push(path);
             Ok(())
         }
     })
     .await?;
 
-    assert!(*processed.lock().await);
+    assert!(!root.exists());
+    let seen = seen.lock().await;
+    assert_eq!(seen.len(), 6);
+    assert_eq!(seen.last(), Some(&root));
+    let position = |path: PathBuf| seen.iter().position(|seen| *seen == path).unwrap();
+    assert!(position(root.join("a/b/c.txt")) < position(root.join("a/b")));
+    assert!(position(root.join("a/b")) < position(root.join("a")));
     Ok(())
 }
 
 #[tokio::test]
-async fn test_process_rust_file() -> std::io::Result<()> {
+async fn test_walk_directory_workers() -> anyhow::Result<()> {
     let temp_dir = TempDir::new()?;
-    let file_path = temp_dir.path().join("test.rs");
-    std::fs::write(
-        &file_path,
-        "#![warn(clippy::all, clippy::pedantic)]\nfn main() {}",
-    )?;
+    for i in 0..600 {
+        std::fs::write(temp_dir.path().join(format!("{i}.txt")), "x")?;
+    }
 
-    let mut files_without_warning = Vec::new();
-    process_rust_file(&file_path, &mut files_without_warning).await?;
+    // Every file spans several batches and is still handed to exactly one worker
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = Arc::clone(&seen);
+    let options = WalkOptions::new().with_workers(4);
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, move |path| {
+        let seen = Arc::clone(&seen_clone);
+        let path = path.to_path_buf();
+        async move {
+            seen.lock().await.push(path);
+            Ok(())
+        }
+    })
+    .await?;
+    assert_eq!(report.processed, 600);
+    let mut seen = seen.lock().await.clone();
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 600);
+
+    // Errors still surface, and fail_fast stops the workers early
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_clone = Arc::clone(&calls);
+    let options = WalkOptions::new().with_workers(2).with_fail_fast(true);
+    let result = walk_directory_with_report(temp_dir.path(), "txt", &options, move |_| {
+        calls_clone.fetch_add(1, Ordering::SeqCst);
+        async { Err(anyhow::anyhow!("boom")) }
+    })
+    .await;
+    assert!(result.is_err());
+    assert!(calls.load(Ordering::SeqCst) < 600);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_max_file_size() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::write(temp_dir.path().join("small.txt"), "x")?;
+    std::fs::write(temp_dir.path().join("exact.txt"), "x".repeat(10))?;
+    std::fs::write(temp_dir.path().join("huge.txt"), "x".repeat(11))?;
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = Arc::clone(&seen);
+    let options = WalkOptions::new().with_max_file_size(10);
+    let report = walk_directory_with_report(temp_dir.path(), "txt", &options, move |path| {
+        let seen = Arc::clone(&seen_clone);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        async move {
+            seen.lock().await.push(name);
+            Ok(())
+        }
+    })
+    .await?;
+    assert_eq!(report.processed, 2);
+    let mut seen = seen.lock().await.clone();
+    seen.sort();
+    assert_eq!(seen, ["exact.txt", "small.txt"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_entries() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir(temp_dir.path().join("nested"))?;
+    std::fs::write(temp_dir.path().join("top.txt"), "x")?;
+    std::fs::write(temp_dir.path().join("nested").join("deep.txt"), "xyz")?;
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_clone = Arc::clone(&seen);
+    walk_directory_entries(temp_dir.path(), "txt", move |entry| {
+        let seen = Arc::clone(&seen_clone);
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let depth = entry.depth();
+        let is_file = entry.file_type().is_file();
+        let len = entry.metadata().map(|metadata| metadata.len());
+        async move {
+            seen.lock().await.push((name, depth, is_file, len?));
+            Ok(())
+        }
+    })
+    .await?;
+
+    let mut seen = seen.lock().await.clone();
+    seen.sort();
+    assert_eq!(
+        seen,
+        [("deep.txt".to_string(), 2, true, 3), ("top.txt".to_string(), 1, true, 1)]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_include_target_and_git() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for file in ["src/a.txt", "target/debug/b.txt", ".git/hooks/c.txt", ".hidden/d.txt"] {
+        let path = temp_dir.path().join(file);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, "x")?;
+    }
+
+    let walk = |options: WalkOptions| {
+        let dir = temp_dir.path().to_path_buf();
+        async move {
+            let seen = Arc::new(Mutex::new(Vec::new()));
+            let seen_clone = Arc::clone(&seen);
+            walk_directory_with(&dir, "txt", &options, move |path| {
+                let seen = Arc::clone(&seen_clone);
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                async move {
+                    seen.lock().await.push(name);
+                    Ok(())
+                }
+            })
+            .await?;
+            let mut seen = seen.lock().await.clone();
+            seen.sort();
+            anyhow::Ok(seen)
+        }
+    };
+
+    assert_eq!(walk(WalkOptions::new()).await?, ["a.txt"]);
+    assert_eq!(walk(WalkOptions::new().with_include_target(true)).await?, ["a.txt", "b.txt"]);
+    // Other hidden directories stay excluded
+    assert_eq!(walk(WalkOptions::new().with_include_git(true)).await?, ["a.txt", "c.txt"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_to_channel() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir(temp_dir.path().join("sub"))?;
+    for file in ["a.txt", "b.md", "sub/c.txt", ".hidden.txt"] {
+        std::fs::write(temp_dir.path().join(file), "x")?;
+    }
+
+    // A capacity of one makes the walk wait for the consumer
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+    let consumer = tokio::spawn(async move {
+        let mut paths = Vec::new();
+        while let Some(path) = receiver.recv().await {
+            paths.push(path);
+        }
+        paths
+    });
+    let report = walk_directory_to_channel(temp_dir.path(), "txt", sender).await?;
+    let mut paths = consumer.await?;
+    paths.sort();
+
+    assert_eq!(report.processed, 2);
+    assert!(report.is_complete());
+    assert_eq!(paths, [temp_dir.path().join("a.txt"), temp_dir.path().join("sub/c.txt")]);
+
+    // A dropped receiver is an error
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+    drop(receiver);
+    assert!(walk_directory_to_channel(temp_dir.path(), "txt", sender).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prune_keep_latest() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let base = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    for (i, name) in ["b0.bak", "b1.bak", "b2.bak", "b3.bak", "notes.txt"].iter().enumerate() {
+        let path = temp_dir.path().join(name);
+        std::fs::write(&path, "x")?;
+        xio::fs::touch_with_time(&path, base + std::time::Duration::from_secs(i as u64 * 60)).await?;
+    }
+    std::fs::create_dir(temp_dir.path().join("old.bak"))?;
+
+    // A dry run reports the oldest files without deleting them
+    let expired = prune_keep_latest(temp_dir.path(), Some("bak"), 2, true).await?;
+    let expected = [temp_dir.path().join("b1.bak"), temp_dir.path().join("b0.bak")];
+    assert_eq!(expired, expected);
+    assert!(expected.iter().all(|path| path.exists()));
+
+    let deleted = prune_keep_latest(temp_dir.path(), Some(".bak"), 2, false).await?;
+    assert_eq!(deleted, expected);
+    assert!(expected.iter().all(|path| !path.exists()));
+    assert!(temp_dir.path().join("b3.bak").exists());
+    assert!(temp_dir.path().join("notes.txt").exists());
+
+    // Without an extension every file counts
+    let deleted = prune_keep_latest(temp_dir.path(), None, 1, false).await?;
+    assert_eq!(deleted, [temp_dir.path().join("b3.bak"), temp_dir.path().join("b2.bak")]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_path_mode() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir(temp_dir.path().join("sub"))?;
+    std::fs::write(temp_dir.path().join("sub").join("a.txt"), "a")?;
+    let root = temp_dir.path().join("sub").join("..");
+
+    for (mode, expected) in [
+        (PathMode::AsWalked, root.join("sub").join("a.txt")),
+        (PathMode::Absolute, temp_dir.path().join("sub").join("a.txt")),
+        (PathMode::RelativeTo(temp_dir.path().to_path_buf()), PathBuf::from("sub/a.txt")),
+    ] {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = Arc::clone(&seen);
+        let options = WalkOptions::new().with_path_mode(mode.clone());
+        walk_directory_with(&root, "txt", &options, move |path: &Path| {
+            let seen = Arc::clone(&seen_clone);
+            let path = path.to_path_buf();
+            async move {
+                seen.lock().await.push(path);
+                Ok(())
+            }
+        })
+        .await?;
+        assert_eq!(*seen.lock().await, std::slice::from_ref(&expected));
+
+        // The synchronous finders convert the same way
+        let found: Vec<_> = get_files_with_extension(&root, "txt").map(|p| mode.apply(&p)).collect();
+        assert_eq!(found, [expected]);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_ignore_file() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    std::fs::create_dir_all(root.join("build"))?;
+    std::fs::create_dir_all(root.join("sub"))?;
+    std::fs::write(root.join(".xioignore"), "*.log\nbuild/\n")?;
+    std::fs::write(root.join("sub").join(".xioignore"), "!keep.log\nskip.txt\n")?;
+    for file in ["a.txt", "b.log", "build/c.txt", "sub/d.txt", "sub/keep.log", "sub/skip.txt"] {
+        std::fs::write(root.join(file), file)?;
+    }
+
+    let walk = |options: WalkOptions| async move {
+        let visited = Arc::new(Mutex::new(Vec::new()));
+        let visited_clone = Arc::clone(&visited);
+        let base = root.to_path_buf();
+        walk_directory_with(root, "*", &options, move |path| {
+            let visited = Arc::clone(&visited_clone);
+            let relative = path.strip_prefix(&base).unwrap().to_string_lossy().replace('\\', "/");
+            async move {
+                visited.lock().await.push(relative);
+                Ok(())
+            }
+        })
+        .await?;
+        let mut visited = visited.lock().await.clone();
+        visited.sort();
+        anyhow::Ok(visited)
+    };
+
+    assert_eq!(walk(WalkOptions::new()).await?.len(), 6);
+    assert_eq!(
+        walk(WalkOptions::new().with_ignore_file(DEFAULT_IGNORE_FILE)).await?,
+        ["a.txt", "sub/d.txt", "sub/keep.log"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_rate_limit() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..4 {
+        std::fs::write(temp_dir.path().join(format!("{i}.txt")), "")?;
+    }
+
+    // Four files at 20 per second: the first starts immediately, the rest 50ms apart
+    let options = WalkOptions::new().with_rate_limit(20);
+    let start = std::time::Instant::now();
+    walk_directory_with(temp_dir.path(), "txt", &options, |_| async { Ok(()) }).await?;
+    assert!(start.elapsed() >= std::time::Duration::from_millis(150));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_futures_are_send() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let dir = temp_dir.path().to_path_buf();
+    let options = WalkOptions::new()
+        .with_order(ProcessOrder::ModifiedDesc)
+        .with_rate_limit(1000);
+
+    // Walks can run on another task, whatever the options
+    tokio::spawn(async move {
+        walk_directory_with(&dir, "txt", &options, |_| async { Ok(()) }).await
+    })
+    .await??;
+    Ok(())
+}
+
+/// Records progress events so tests can check how an operation reported them.
+#[derive(Default)]
+struct RecordingProgress {
+    total: std::sync::Mutex<Option<Option<usize>>>,
+    advanced: AtomicUsize,
+    finished: AtomicBool,
+}
+
+impl Progress for RecordingProgress {
+    fn on_start(&self, total: Option<usize>) {
+        *self.total.lock().unwrap() = Some(total);
+    }
+
+    fn on_advance(&self, n: usize) {
+        self.advanced.fetch_add(n, Ordering::SeqCst);
+    }
+
+    fn on_finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_walk_and_delete_report_progress() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for name in ["a.txt", "b.txt", "c.rs"] {
+        std::fs::write(temp_dir.path().join(name), name)?;
+    }
+
+    let progress = Arc::new(RecordingProgress::default());
+    let options = WalkOptions::new().with_progress(Arc::clone(&progress));
+    walk_directory_with(temp_dir.path(), "txt", &options, |_| async { Ok(()) }).await?;
+    assert_eq!(*progress.total.lock().unwrap(), Some(None));
+    assert_eq!(progress.advanced.load(Ordering::SeqCst), 2);
+    assert!(progress.finished.load(Ordering::SeqCst));
+
+    let progress = RecordingProgress::default();
+    delete_files_with_extension_with_progress(temp_dir.path(), "txt", &progress).await?;
+    assert_eq!(*progress.total.lock().unwrap(), Some(Some(2)));
+    assert_eq!(progress.advanced.load(Ordering::SeqCst), 2);
+    assert!(progress.finished.load(Ordering::SeqCst));
+    assert!(!temp_dir.path().join("a.txt").exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remove_dir_all_reporting() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().join("cache");
+    std::fs::create_dir_all(root.join("a").join("b"))?;
+    for file in ["top.bin", "a/one.bin", "a/b/two.bin"] {
+        std::fs::write(root.join(file), "x")?;
+    }
+    let outside = temp_dir.path().join("keep.txt");
+    std::fs::write(&outside, "keep")?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&outside, root.join("a").join("link"))?;
+
+    let progress = RecordingProgress::default();
+    let report = remove_dir_all_reporting_with_progress(&root, &progress).await?;
+    let expected = if cfg!(unix) { 7 } else { 6 };
+    assert!(report.is_complete());
+    assert_eq!(report.removed, expected);
+    assert_eq!(*progress.total.lock().unwrap(), Some(Some(expected)));
+    assert_eq!(progress.advanced.load(Ordering::SeqCst), expected);
+    assert!(progress.finished.load(Ordering::SeqCst));
+    assert!(!root.exists());
+    // Symlinks are removed, never their targets
+    assert_eq!(std::fs::read_to_string(&outside)?, "keep");
+
+    assert!(remove_dir_all_reporting(&root).await.is_err());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_remove_dir_all_reporting_symlinked_root() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let target = temp_dir.path().join("real");
+    std::fs::create_dir_all(target.join("nested"))?;
+    std::fs::write(target.join("a.bin"), "x")?;
+    std::fs::write(target.join("nested").join("b.bin"), "x")?;
+    let link = temp_dir.path().join("cache");
+    std::os::unix::fs::symlink(&target, &link)?;
+
+    // Only the link is removed, never what it points to
+    let report = remove_dir_all_reporting(&link).await?;
+    assert!(report.is_complete());
+    assert_eq!(report.removed, 1);
+    assert!(link.symlink_metadata().is_err());
+    assert!(target.join("a.bin").exists());
+    assert!(target.join("nested").join("b.bin").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_files_with_extension_with_callback() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..5 {
+        std::fs::write(temp_dir.path().join(format!("{i}.tmp")), "x")?;
+    }
+    std::fs::write(temp_dir.path().join("keep.txt"), "x")?;
+
+    let calls = std::sync::Mutex::new(Vec::new());
+    let dir = temp_dir.path();
+    delete_files_with_extension_with_callback(dir, "tmp", |deleted, total| {
+        // The total is reported before any file is deleted
+        if deleted == 0 {
+            assert!((0..5).all(|i| dir.join(format!("{i}.tmp")).exists()));
+        }
+        calls.lock().unwrap().push((deleted, total));
+    })
+    .await?;
+    let calls = calls.into_inner().unwrap();
+    assert_eq!(calls, (0..=5).map(|deleted| (deleted, 5)).collect::<Vec<_>>());
+    assert!(!temp_dir.path().join("0.tmp").exists());
+    assert!(temp_dir.path().join("keep.txt").exists());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_delete_files_with_extension_counts_only_removed() -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new()?;
+    let locked = temp_dir.path().join("locked");
+    std::fs::create_dir(&locked)?;
+    std::fs::write(temp_dir.path().join("a.tmp"), "x")?;
+    std::fs::write(locked.join("b.tmp"), "x")?;
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o555))?;
+    // Permissions do not stop root, so there is nothing to fail
+    let privileged = std::fs::File::create(locked.join("probe")).is_ok();
+
+    let calls = std::sync::Mutex::new(Vec::new());
+    let result = delete_files_with_extension_with_callback(temp_dir.path(), "tmp", |deleted, total| {
+        calls.lock().unwrap().push((deleted, total));
+    })
+    .await;
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755))?;
+    result?;
+
+    if !privileged {
+        // The file that could not be removed is not counted as deleted
+        assert_eq!(calls.into_inner().unwrap(), [(0, 2), (1, 2)]);
+        assert!(locked.join("b.tmp").exists());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_file_preserving() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let src = temp_dir.path().join("app.log");
+    let dst = temp_dir.path().join("copy.log");
+    std::fs::write(&src, "log line")?;
+    let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    xio::fs::touch_with_time(&src, an_hour_ago).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o640))?;
+    }
+
+    assert_eq!(copy_file_preserving(&src, &dst).await?, 8);
+    assert_eq!(std::fs::read_to_string(&dst)?, "log line");
+    let modified = std::fs::metadata(&dst)?.modified()?;
+    let drift = modified
+        .duration_since(an_hour_ago)
+        .unwrap_or_else(|e| e.duration());
+    assert!(drift < std::time::Duration::from_secs(1));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&dst)?.permissions().mode() & 0o777, 0o640);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_files_with_extension() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    // Create test files
+    std::fs::File::create(temp_dir.path().join("test1.txt"))?;
+    std::fs::File::create(temp_dir.path().join("test2.txt"))?;
+    std::fs::File::create(temp_dir.path().join("test.rs"))?;
+
+    delete_files_with_extension(temp_dir.path(), "txt").await?;
+
+    let entries: Vec<_> = std::fs::read_dir(temp_dir.path())?
+        .filter_map(Result::ok)
+        .collect();
+
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].path().extension().unwrap().to_string_lossy(),
+        "rs"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_extension_with_leading_dot() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::write(temp_dir.path().join("a.txt"), "a")?;
+    std::fs::write(temp_dir.path().join("b.rs"), "b")?;
+
+    for extension in ["txt", ".txt"] {
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = Arc::clone(&count);
+        walk_directory(temp_dir.path(), extension, move |_| {
+            count_clone.fetch_add(1, Ordering::SeqCst);
+            async { Ok(()) }
+        })
+        .await?;
+        assert_eq!(count.load(Ordering::SeqCst), 1, "extension {extension}");
+    }
+
+    delete_files_with_extension(temp_dir.path(), ".rs").await?;
+    assert!(!temp_dir.path().join("b.rs").exists());
+    assert!(temp_dir.path().join("a.txt").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_check_file_for_multiple_lines() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let multi_line_files = Arc::new(Mutex::new(Vec::new()));
+
+    // Create test files
+    let single_line = temp_dir.path().join("single.txt");
+    std::fs::write(&single_line, "Single line")?;
+
+    let multi_line = temp_dir.path().join("multi.txt");
+    std::fs::write(&multi_line, "Line 1\nLine 2")?;
+
+    check_file_for_multiple_lines(&single_line, Arc::clone(&multi_line_files)).await?;
+    check_file_for_multiple_lines(&multi_line, Arc::clone(&multi_line_files)).await?;
+
+    let files = multi_line_files.lock().await;
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0], multi_line);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_files_in_neovim() -> anyhow::Result<()> {
+    // Test empty file list
+    let empty_files: Vec<PathBuf> = vec![];
+    open_files_in_neovim(&empty_files, None).await?;
+
+    // Test with files using echo instead of nvim
+    let files = vec![PathBuf::from("test1.txt"), PathBuf::from("test2.txt")];
+    open_files_in_neovim(&files, Some("echo")).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_files_detached() -> anyhow::Result<()> {
+    assert!(open_files_detached(&[], None)?.is_none());
+
+    let files = vec![PathBuf::from("test1.txt")];
+    let mut child = open_files_detached(&files, Some("true"))?
+        .expect("editor should be spawned");
+    assert!(child.wait().await?.success());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_matching_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let fixme = temp_dir.path().join("a.rs");
+    let clean = temp_dir.path().join("b.rs");
+    let binary = temp_dir.path().join("c.rs");
+    std::fs::write(&fixme, "// FIXME: handle errors\n")?;
+    std::fs::write(&clean, "fn main() {}\n")?;
+    std::fs::write(&binary, [0xff, 0xfe, 0x00])?;
+
+    let matches =
+        collect_matching_files(temp_dir.path(), "rs", |_, content| content.contains("FIXME"))
+            .await?;
+    assert_eq!(matches, vec![fixme.clone()]);
+
+    let opened = open_matching_files(
+        temp_dir.path(),
+        "rs",
+        |_, content| content.contains("FIXME"),
+        Some("true"),
+    )
+    .await?;
+    assert_eq!(opened, vec![fixme]);
+
+    // No match means no editor launch, so a missing editor is not an error
+    let opened =
+        open_matching_files(temp_dir.path(), "rs", |_, _| false, Some("xio-no-such-editor"))
+            .await?;
+    assert!(opened.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_open_files_missing_editor() {
+    let files = vec![PathBuf::from("test1.txt")];
+    let err = open_files_in_neovim(&files, Some("xio-no-such-editor"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("editor 'xio-no-such-editor' not found on PATH"));
+    assert!(open_files_detached(&files, Some("xio-no-such-editor")).is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_open_files_editor_not_executable() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let editor = temp_dir.path().join("editor");
+    std::fs::write(&editor, "#!/bin/sh\n")?;
+    let editor = editor.to_str().unwrap();
+    let files = vec![PathBuf::from("test1.txt")];
+
+    // A file without an execute bit is not accepted as the editor
+    let err = open_files_in_neovim(&files, Some(editor)).await.unwrap_err();
+    assert!(err.to_string().contains("not executable"));
+    assert!(open_files_detached(&files, Some(editor)).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_process_file() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "Test content")?;
+
+    let processed = Arc::new(Mutex::new(false));
+    let processed_clone = Arc::clone(&processed);
+
+    process_file(&file_path, move |_| {
+        let processed = Arc::clone(&processed_clone);
+        async move {
+            let mut p = processed.lock().await;
+            *p = true;
+            Ok(())
+        }
+    })
+    .await?;
+
+    assert!(*processed.lock().await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_analyze_rust_file() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("lib.rs");
+    std::fs::write(
+        &file_path,
+        "#![forbid(unsafe_code)]\n#![deny(\n    clippy::all,\n    clippy::pedantic\n)]\n\n//! Docs\n\nfn main() {}\n",
+    )?;
+
+    let report = analyze_rust_file(&file_path).await?;
+    assert_eq!(
+        report,
+        RustFileReport {
+            has_pedantic: true,
+            has_forbid_unsafe: true,
+            has_module_doc: true,
+            line_count: 9,
+            clippy_attributes: vec!["#![deny( clippy::all, clippy::pedantic )]".to_string()],
+        }
+    );
+
+    std::fs::write(&file_path, "#![allow(clippy::pedantic)]\nfn main() {}\n")?;
+    let report = analyze_rust_file(&file_path).await?;
+    assert!(!report.has_pedantic);
+    assert!(!report.has_forbid_unsafe);
+    assert!(!report.has_module_doc);
+    assert_eq!(report.clippy_attributes, ["#![allow(clippy::pedantic)]"]);
+
+    let mut files_without_warning = Vec::new();
+    process_rust_file(&file_path, &mut files_without_warning).await?;
+    assert_eq!(files_without_warning, [file_path]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_process_rust_file() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.rs");
+    std::fs::write(
+        &file_path,
+        "#![warn(clippy::all, clippy::pedantic)]\nfn main() {}",
+    )?;
+
+    let mut files_without_warning = Vec::new();
+    process_rust_file(&file_path, &mut files_without_warning).await?;
+
+    assert_eq!(files_without_warning.len(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transform_file_lines() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let src = temp_dir.path().join("src.txt");
+    let dst = temp_dir.path().join("dst.txt");
+    std::fs::write(&src, "keep 1\r\n# drop\nkeep 2")?;
+
+    transform_file_lines(&src, &dst, |line| {
+        (!line.starts_with('#')).then(|| line.to_uppercase())
+    })
+    .await?;
+    assert_eq!(std::fs::read_to_string(&dst)?, "KEEP 1\r\nKEEP 2");
+
+    // In-place transformation
+    transform_file_lines(&src, &src, |line| Some(line.replace("keep", "kept"))).await?;
+    assert_eq!(std::fs::read_to_string(&src)?, "kept 1\r\n# drop\nkept 2");
+    assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ensure_trailing_newline() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("lib.rs");
+
+    for (content, expected) in [
+        ("fn main() {}", "fn main() {}\n"),
+        ("fn main() {}\n\n\n", "fn main() {}\n"),
+        ("a\r\nb", "a\r\nb\r\n"),
+        ("\n\n", "\n"),
+    ] {
+        std::fs::write(&path, content)?;
+        assert!(ensure_trailing_newline(&path).await?);
+        assert_eq!(std::fs::read_to_string(&path)?, expected);
+    }
+
+    // Files that are already fine, including empty ones, are not rewritten
+    for content in ["fn main() {}\n", ""] {
+        std::fs::write(&path, content)?;
+        let modified = std::fs::metadata(&path)?.modified()?;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert!(!ensure_trailing_newline(&path).await?);
+        assert_eq!(std::fs::metadata(&path)?.modified()?, modified);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_strip_trailing_whitespace() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("lib.rs");
+
+    std::fs::write(&path, "fn main() {  \r\n\tlet x = 1;\t\n   \n}  ")?;
+    assert!(strip_trailing_whitespace(&path).await?);
+    assert_eq!(std::fs::read_to_string(&path)?, "fn main() {\r\n\tlet x = 1;\n\n}");
+
+    let modified = std::fs::metadata(&path)?.modified()?;
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(!strip_trailing_whitespace(&path).await?);
+    assert_eq!(std::fs::metadata(&path)?.modified()?, modified);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dedup_lines_in_file() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("paths.txt");
+
+    std::fs::write(&path, "b\na\nb\r\nc\na")?;
+    assert_eq!(dedup_lines_in_file(&path, true).await?, 2);
+    assert_eq!(std::fs::read_to_string(&path)?, "b\na\nc\n");
+
+    std::fs::write(&path, "b\na\nb\n")?;
+    assert_eq!(dedup_lines_in_file(&path, false).await?, 1);
+    assert_eq!(std::fs::read_to_string(&path)?, "a\nb\n");
+
+    // Nothing to remove leaves the file untouched
+    std::fs::write(&path, "x\r\ny")?;
+    assert_eq!(dedup_lines_in_file(&path, true).await?, 0);
+    assert_eq!(std::fs::read_to_string(&path)?, "x\r\ny");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dedup_sorted_lines_in_file() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("sorted.txt");
+
+    std::fs::write(&path, "a\na\r\nb\nc\nc\nc")?;
+    assert_eq!(dedup_sorted_lines_in_file(&path).await?, 3);
+    assert_eq!(std::fs::read_to_string(&path)?, "a\nb\nc\n");
+
+    assert_eq!(dedup_sorted_lines_in_file(&path).await?, 0);
+    assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_lines() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("lines.txt");
+
+    write_lines(&path, &["one", "two"]).await?;
+    assert_eq!(std::fs::read_to_string(&path)?, "one\ntwo\n");
+    assert_eq!(read_lines(&path).await?, ["one", "two"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_canonicalize() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let processed_files = Arc::new(Mutex::new(Vec::new()));
+    std::fs::create_dir(temp_dir.path().join("sub"))?;
+    std::fs::File::create(temp_dir.path().join("sub").join("test.txt"))?;
+
+    // Walk from a messy root that contains "." and ".." components
+    let root = temp_dir.path().join("sub").join("..").join(".");
+    let options = WalkOptions::new().with_canonicalize(true);
+    let processed_files_clone = Arc::clone(&processed_files);
+    walk_directory_with(root, "txt", &options, move |path: &Path| {
+        let processed_files = Arc::clone(&processed_files_clone);
+        let path_buf = path.to_path_buf();
+        async move {
+            processed_files.lock().await.push(path_buf);
+            Ok(())
+        }
+    })
+    .await?;
+
+    let expected = temp_dir.path().canonicalize()?.join("sub").join("test.txt");
+    assert_eq!(*processed_files.lock().await, vec![expected]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_to_file_with_backup() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("notes.txt");
+    let backup = temp_dir.path().join("notes.txt.bak");
+
+    // No backup for a new file
+    assert!(!write_to_file_with_backup(&path, "first", ".bak").await?);
+    assert!(!backup.exists());
+    assert_eq!(std::fs::read_to_string(&path)?, "first");
+
+    assert!(write_to_file_with_backup(&path, "second", ".bak").await?);
+    assert_eq!(std::fs::read_to_string(&backup)?, "first");
+    assert_eq!(std::fs::read_to_string(&path)?, "second");
+
+    // An older backup is replaced
+    assert!(write_to_file_with_backup(&path, "third", ".bak").await?);
+    assert_eq!(std::fs::read_to_string(&backup)?, "second");
+    assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 2);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_writers_preserve_permissions() -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new()?;
+    let script = temp_dir.path().join("run.sh");
+    std::fs::write(&script, "#!/bin/sh\n")?;
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+    let mode = |path: &Path| std::fs::metadata(path).map(|m| m.permissions().mode() & 0o777);
+
+    write_to_file(&script, "#!/bin/sh\necho one\n").await?;
+    assert_eq!(mode(&script)?, 0o755);
+    write_to_file_with_backup(&script, "#!/bin/sh\necho two\n", ".bak").await?;
+    assert_eq!(mode(&script)?, 0o755);
+    write_lines(&script, &["#!/bin/sh", "echo three"]).await?;
+    assert_eq!(mode(&script)?, 0o755);
+    transform_file_lines(&script, &script, |line| Some(line.replace("three", "four"))).await?;
+    assert_eq!(mode(&script)?, 0o755);
+    concat_files(std::slice::from_ref(&script), &script, None).await?;
+    assert_eq!(mode(&script)?, 0o755);
+    assert_eq!(std::fs::read_to_string(&script)?, "#!/bin/sh\necho four\n");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_write_and_return() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+
+    let returned = write_and_return(&file_path, "Test content").await?;
+    assert_eq!(returned, "Test content");
+    assert_eq!(std::fs::read_to_string(&file_path)?, returned);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_fail_fast() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for i in 0..20 {
+        std::fs::File::create(temp_dir.path().join(format!("{i}.txt")))?;
+    }
+
+    for fail_fast in [false, true] {
+        let processed_files = Arc::new(Mutex::new(Vec::new()));
+        let processed_files_clone = Arc::clone(&processed_files);
+        let options = WalkOptions::new().with_fail_fast(fail_fast);
+        let result = walk_directory_with(temp_dir.path(), "txt", &options, move |path: &Path| {
+            let processed_files = Arc::clone(&processed_files_clone);
+            let path_buf = path.to_path_buf();
+            async move {
+                if path_buf.file_stem().unwrap() == "0" {
+                    anyhow::bail!("failed on {}", path_buf.display());
+                }
+                tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+                processed_files.lock().await.push(path_buf);
+                Ok(())
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        let processed = processed_files.lock().await.len();
+        if fail_fast {
+            assert!(processed < 19);
+        } else {
+            assert_eq!(processed, 19);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+#[tokio::test]
+async fn test_read_file_mmap() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "Line 1\nLine 2")?;
+
+    let map = xio::read_file_mmap(&file_path).await?;
+    assert_eq!(xio::mmap_as_str(&map)?, "Line 1\nLine 2");
+
+    let binary_path = temp_dir.path().join("test.bin");
+    std::fs::write(&binary_path, [0xff, 0xfe, 0x00])?;
+    let map = xio::read_file_mmap(&binary_path).await?;
+    assert!(xio::mmap_as_str(&map).is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn test_read_write_json() -> anyhow::Result<()> {
+    use std::collections::BTreeMap;
+
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("config.json");
+    let config = BTreeMap::from([("retries".to_string(), 3), ("workers".to_string(), 8)]);
+
+    xio::write_json(&path, &config, false).await?;
+    assert_eq!(std::fs::read_to_string(&path)?, "{\"retries\":3,\"workers\":8}\n");
+    assert_eq!(xio::read_json::<BTreeMap<String, u32>>(&path).await?, config);
+
+    xio::write_json(&path, &config, true).await?;
+    assert!(std::fs::read_to_string(&path)?.contains("\n  \"retries\": 3,\n"));
+    assert_eq!(xio::read_json::<BTreeMap<String, u32>>(&path).await?, config);
+
+    // Parse errors name the file
+    std::fs::write(&path, "{ not json")?;
+    let err = xio::read_json::<BTreeMap<String, u32>>(&path).await.unwrap_err();
+    assert!(err.to_string().contains("config.json"));
+
+    Ok(())
+}
+
+#[cfg(feature = "toml")]
+#[tokio::test]
+async fn test_read_write_toml() -> anyhow::Result<()> {
+    use std::collections::BTreeMap;
+
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("config.toml");
+    let config = BTreeMap::from([("retries".to_string(), 3), ("workers".to_string(), 8)]);
+
+    xio::write_toml(&path, &config).await?;
+    assert_eq!(std::fs::read_to_string(&path)?, "retries = 3\nworkers = 8\n");
+    assert_eq!(xio::read_toml::<BTreeMap<String, u32>>(&path).await?, config);
+
+    // Parse errors name the file and the location of the problem
+    std::fs::write(&path, "retries = 3\nworkers = = 8\n")?;
+    let err = xio::read_toml::<BTreeMap<String, u32>>(&path).await.unwrap_err();
+    assert!(err.to_string().contains("config.toml at line 2, column"), "{err}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_process_paths() -> anyhow::Result<()> {
+    let paths: Vec<PathBuf> = ["a", "bad1", "b", "bad2", "c"].iter().map(PathBuf::from).collect();
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let processed = Arc::new(AtomicUsize::new(0));
+
+    let (running_clone, max_clone, processed_clone) =
+        (Arc::clone(&running), Arc::clone(&max_running), Arc::clone(&processed));
+    let failures = process_paths(&paths, 2, move |path| {
+        let path = path.to_path_buf();
+        let (running, max_running, processed) =
+            (Arc::clone(&running_clone), Arc::clone(&max_clone), Arc::clone(&processed_clone));
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            processed.fetch_add(1, Ordering::SeqCst);
+            if path.to_string_lossy().starts_with("bad") {
+                anyhow::bail!("cannot process");
+            }
+            Ok(())
+        }
+    })
+    .await;
+
+    assert_eq!(processed.load(Ordering::SeqCst), 5);
+    assert!(max_running.load(Ordering::SeqCst) <= 2);
+    let failed: Vec<&PathBuf> = failures.iter().map(|(path, _)| path).collect();
+    assert_eq!(failed, [&PathBuf::from("bad1"), &PathBuf::from("bad2")]);
+    assert!(format!("{:#}", failures[0].1).contains("Failed to process bad1"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_map_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir(temp_dir.path().join("sub"))?;
+    std::fs::write(temp_dir.path().join("b.txt"), "1\n2")?;
+    std::fs::write(temp_dir.path().join("a.txt"), "1")?;
+    std::fs::write(temp_dir.path().join("sub").join("c.txt"), "1\n2\n3")?;
+    std::fs::write(temp_dir.path().join("d.rs"), "")?;
+
+    let counts = map_files(
+        temp_dir.path(),
+        "txt",
+        |path| {
+            let path = path.to_path_buf();
+            async move { Ok(tokio::fs::read_to_string(&path).await?.lines().count()) }
+        },
+        2,
+    )
+    .await?;
+    assert_eq!(counts, vec![1, 2, 3]);
+
+    let result = map_files(
+        temp_dir.path(),
+        "txt",
+        |_| async { Err::<(), _>(anyhow::anyhow!("failed")) },
+        2,
+    )
+    .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_partition_files() -> std::io::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir(temp_dir.path().join("sub"))?;
+    std::fs::write(temp_dir.path().join("b.json"), "{}")?;
+    std::fs::write(temp_dir.path().join("a.json"), "{")?;
+    std::fs::write(temp_dir.path().join("sub").join("c.json"), "{}")?;
+    std::fs::write(temp_dir.path().join("d.txt"), "{}")?;
+
+    let (valid, invalid) = partition_files(
+        temp_dir.path(),
+        "json",
+        |path| {
+            let path = path.to_path_buf();
+            async move { Ok(tokio::fs::read_to_string(&path).await?.ends_with('}')) }
+        },
+        2,
+    )
+    .await?;
+    assert_eq!(
+        valid,
+        [temp_dir.path().join("b.json"), temp_dir.path().join("sub").join("c.json")]
+    );
+    assert_eq!(invalid, [temp_dir.path().join("a.json")]);
+
+    let result = partition_files(
+        temp_dir.path(),
+        "json",
+        |_| async { Err(std::io::Error::other("failed")) },
+        2,
+    )
+    .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fold_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::write(temp_dir.path().join("b.txt"), "b")?;
+    std::fs::write(temp_dir.path().join("a.txt"), "a")?;
+    std::fs::write(temp_dir.path().join("c.txt"), "c")?;
+
+    let joined = fold_files(temp_dir.path(), "txt", String::new(), |mut acc, path| {
+        let path = path.to_path_buf();
+        async move {
+            acc.push_str(&tokio::fs::read_to_string(&path).await?);
+            Ok(acc)
+        }
+    })
+    .await?;
+    assert_eq!(joined, "abc");
+
+    let untouched = fold_files(temp_dir.path(), "rs", 7, |acc, _| async move { Ok(acc + 1) }).await?;
+    assert_eq!(untouched, 7);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_per_file_timeout() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::File::create(temp_dir.path().join("fast.txt"))?;
+    std::fs::File::create(temp_dir.path().join("hung.txt"))?;
+
+    let options =
+        WalkOptions::new().with_per_file_timeout(tokio::time::Duration::from_millis(50));
+    let started = std::time::Instant::now();
+    let result = walk_directory_with(temp_dir.path(), "txt", &options, |path: &Path| {
+        let hung = path.file_stem().unwrap() == "hung";
+        async move {
+            if hung {
+                tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+            }
+            Ok(())
+        }
+    })
+    .await;
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("hung.txt"), "{error}");
+    assert!(started.elapsed() < tokio::time::Duration::from_secs(10));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_file_by_lines() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let src = temp_dir.path().join("data.txt");
+    std::fs::write(&src, "one\ntwo\r\nthree\nfour\nfive")?;
+    let out_dir = temp_dir.path().join("chunks");
+
+    let chunks = split_file_by_lines(&src, 2, &out_dir, "part").await?;
+    assert_eq!(
+        chunks,
+        vec![
+            out_dir.join("part_0000"),
+            out_dir.join("part_0001"),
+            out_dir.join("part_0002"),
+        ]
+    );
+    assert_eq!(std::fs::read_to_string(&chunks[0])?, "one\ntwo\r\n");
+    assert_eq!(std::fs::read_to_string(&chunks[1])?, "three\nfour\n");
+    assert_eq!(std::fs::read_to_string(&chunks[2])?, "five");
+
+    // An empty source produces no chunks, and a zero chunk size is rejected
+    let empty = temp_dir.path().join("empty.txt");
+    std::fs::write(&empty, "")?;
+    assert!(split_file_by_lines(&empty, 2, &out_dir, "empty").await?.is_empty());
+    assert!(split_file_by_lines(&src, 0, &out_dir, "zero").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_file_by_bytes() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let src = temp_dir.path().join("data.bin");
+    let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    std::fs::write(&src, &data)?;
+    let out_dir = temp_dir.path().join("chunks");
+
+    let chunks = split_file_by_bytes(&src, 300, &out_dir, "part").await?;
+    assert_eq!(chunks.len(), 4);
+    assert_eq!(chunks[3], out_dir.join("part_0003"));
+    let sizes: Vec<u64> = chunks
+        .iter()
+        .map(|chunk| std::fs::metadata(chunk).map(|m| m.len()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(sizes, [300, 300, 300, 100]);
+
+    let mut joined = Vec::new();
+    for chunk in &chunks {
+        joined.extend(std::fs::read(chunk)?);
+    }
+    assert_eq!(joined, data);
+
+    // Exact multiples do not produce a trailing empty chunk
+    assert_eq!(split_file_by_bytes(&src, 500, &out_dir, "half").await?.len(), 2);
+    assert!(split_file_by_bytes(&src, 0, &out_dir, "zero").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concat_files() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let a = temp_dir.path().join("a.log");
+    let b = temp_dir.path().join("b.log");
+    std::fs::write(&a, "first")?;
+    std::fs::write(&b, [0xff, 0x00])?;
+    let output = temp_dir.path().join("all.log");
+
+    let inputs = vec![a.clone(), b.clone()];
+    assert_eq!(concat_files(&inputs, &output, None).await?, 7);
+    assert_eq!(std::fs::read(&output)?, b"first\xff\x00");
+
+    assert_eq!(concat_files(&inputs, &output, Some(b"\n")).await?, 8);
+    assert_eq!(std::fs::read(&output)?, b"first\n\xff\x00");
+
+    // Round-trips with split_file_by_bytes, even when the output is an input
+    let mut inputs = split_file_by_bytes(&output, 3, &temp_dir.path().join("chunks"), "part").await?;
+    inputs.insert(0, a.clone());
+    concat_files(&inputs, &a, None).await?;
+    assert_eq!(std::fs::read(&a)?, b"firstfirst\n\xff\x00");
+
+    assert!(concat_files(&[temp_dir.path().join("missing")], &output, None).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_copy_file_with_progress() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let src = temp_dir.path().join("big.bin");
+    let content: Vec<u8> = (0..3 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&src, &content)?;
+    let total = content.len() as u64;
+
+    let dst = temp_dir.path().join("copy.bin");
+    std::fs::write(&dst, "stale content to overwrite")?;
+    let calls = std::sync::Mutex::new(Vec::new());
+    let copied = copy_file_with_progress(&src, &dst, |done, of| calls.lock().unwrap().push((done, of))).await?;
+    assert_eq!(copied, total);
+    assert_eq!(std::fs::read(&dst)?, content);
+
+    let calls = calls.into_inner().unwrap();
+    assert!(calls.len() > 2);
+    assert_eq!(calls.first(), Some(&(0, total)));
+    assert_eq!(calls.last(), Some(&(total, total)));
+    assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(calls.iter().all(|&(_, of)| of == total));
+
+    // Resuming appends the missing tail and reports progress from the existing size
+    let partial = temp_dir.path().join("partial.bin");
+    std::fs::write(&partial, &content[..1000])?;
+    let calls = std::sync::Mutex::new(Vec::new());
+    resume_copy_file_with_progress(&src, &partial, |done, of| calls.lock().unwrap().push((done, of))).await?;
+    assert_eq!(std::fs::read(&partial)?, content);
+    assert_eq!(calls.into_inner().unwrap().first(), Some(&(1000, total)));
+
+    // Copying a file onto itself is rejected before the source is truncated
+    let alias = temp_dir.path().join(".").join("big.bin");
+    for resume in [false, true] {
+        let result = if resume {
+            resume_copy_file_with_progress(&src, &alias, |_, _| {}).await
+        } else {
+            copy_file_with_progress(&src, &alias, |_, _| {}).await
+        };
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(std::fs::read(&src)?, content);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directories() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    std::fs::create_dir_all(temp_dir.path().join("a").join("b"))?;
+    std::fs::create_dir_all(temp_dir.path().join(".git").join("hooks"))?;
+    std::fs::create_dir_all(temp_dir.path().join("target").join("debug"))?;
+    std::fs::File::create(temp_dir.path().join("a").join("file.txt"))?;
+
+    let visited = Arc::new(Mutex::new(Vec::new()));
+    let visited_clone = Arc::clone(&visited);
+    walk_directories(temp_dir.path(), move |dir| {
+        let visited = Arc::clone(&visited_clone);
+        let dir = dir.to_path_buf();
+        async move {
+            visited.lock().await.push(dir);
+            Ok(())
+        }
+    })
+    .await?;
+
+    let mut visited = visited.lock().await.clone();
+    assert_eq!(visited[0], temp_dir.path());
+    visited.sort();
+    assert_eq!(
+        visited,
+        vec![
+            temp_dir.path().to_path_buf(),
+            temp_dir.path().join("a"),
+            temp_dir.path().join("a").join("b"),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_directory_exclude_extensions() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    for name in ["a.rs", "b.toml", "Cargo.lock", "debug.log", "README", "backup.tar.gz", "c.gz"] {
+        std::fs::File::create(temp_dir.path().join(name))?;
+    }
+
+    let cases = [("*", vec!["README", "a.rs", "b.toml", "c.gz"]), ("log", vec!["debug.log"])];
+    for (extension, expected) in cases {
+        let processed_files = Arc::new(Mutex::new(Vec::new()));
+        let processed_files_clone = Arc::clone(&processed_files);
+        // A leading dot is optional and multi-part extensions are matched whole
+        let options = WalkOptions::new().with_exclude_extensions([".lock", "log", "tar.gz"]);
+        walk_directory_with(temp_dir.path(), extension, &options, move |path: &Path| {
+            let processed_files = Arc::clone(&processed_files_clone);
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            async move {
+                processed_files.lock().await.push(name);
+                Ok(())
+            }
+        })
+        .await?;
+
+        let mut processed = processed_files.lock().await.clone();
+        processed.sort();
+        assert_eq!(processed, expected);
+    }
 
-    assert_eq!(files_without_warning.len(), 0);
     Ok(())
 }
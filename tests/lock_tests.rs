@@ -0,0 +1,38 @@
+use tempfile::TempDir;
+use xio::{acquire_lock, try_acquire_lock};
+
+#[tokio::test]
+async fn test_lock_excludes_second_holder() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("output.lock");
+
+    let lock = acquire_lock(&path).await?;
+    assert_eq!(lock.path(), path);
+    assert!(try_acquire_lock(&path)?.is_none());
+
+    // Dropping the guard releases the lock and leaves the lock file in place
+    drop(lock);
+    let lock = try_acquire_lock(&path)?.unwrap();
+    assert!(path.exists());
+    lock.release()?;
+    assert!(try_acquire_lock(&path)?.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_acquire_lock_waits_for_release() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("output.lock");
+
+    let lock = try_acquire_lock(&path)?.unwrap();
+    let waiter = tokio::spawn({
+        let path = path.clone();
+        async move { acquire_lock(&path).await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(!waiter.is_finished());
+
+    drop(lock);
+    waiter.await??;
+    Ok(())
+}
@@ -0,0 +1,775 @@
+use fancy_regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+use xio::split::RESUME_LOG_NAME;
+use xio::{
+    verify_split, CancellationToken, CopyMode, DirectorySplitter, FileMatcher, FnMatcher, Holdout,
+    MagicMatcher, Progress, RegexFileMatcher, ResumeCheck, SearchScope, SplitConfig, SymlinkStyle,
+};
+
+/// Matches `.png` files and treats a `.txt` file with the same stem as their caption.
+#[derive(Clone)]
+struct CaptionMatcher;
+
+#[async_trait::async_trait]
+impl FileMatcher for CaptionMatcher {
+    async fn is_match(&self, path: &Path) -> anyhow::Result<bool> {
+        Ok(path.extension().is_some_and(|ext| ext == "png"))
+    }
+
+    async fn find_accompanying_files(&self, path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let caption = path.with_extension("txt");
+        Ok(if caption.exists() { vec![caption] } else { vec![] })
+    }
+}
+
+fn files_in(dirs: &[PathBuf]) -> Vec<String> {
+    let mut names: Vec<String> = dirs
+        .iter()
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_split_copies_groups() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt", "c.png", "c.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let created_dirs = splitter.split().await?;
+
+    assert_eq!(created_dirs.len(), 2);
+    assert_eq!(
+        files_in(&created_dirs),
+        ["a.png", "a.txt", "b.png", "b.txt", "c.png", "c.txt"]
+    );
+
+    splitter.cleanup(created_dirs.clone()).await?;
+    assert!(created_dirs.iter().all(|dir| !dir.exists()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_skips_orphans() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "c.png", "c.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_min_accompanying(1);
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let report = splitter.split_with_report().await?;
+
+    assert_eq!(report.skipped_orphans, vec![source.path().join("b.png")]);
+    assert_eq!(
+        files_in(&report.created_dirs),
+        ["a.png", "a.txt", "c.png", "c.txt"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_groups_by_key() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["1_a.png", "1_b.png", "1_c.png", "2_a.png", "2_b.png"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_group_key_fn(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.split('_').next().unwrap_or_default().to_string()
+        });
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let created_dirs = splitter.split().await?;
+
+    assert_eq!(files_in(&created_dirs[..1]), ["1_a.png", "1_b.png", "1_c.png"]);
+    assert_eq!(files_in(&created_dirs[1..]), ["2_a.png", "2_b.png"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_split() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt", "notes.md"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let report = splitter.split_with_report().await?;
+    let created_dirs = &report.created_dirs;
+
+    // Files the matcher never selected are not expected in the split
+    assert!(verify_split(&splitter, &report, true).await?.is_ok());
+
+    // Corrupt one copy, duplicate another and add a new source file
+    std::fs::write(created_dirs[0].join("a.png"), "truncated")?;
+    std::fs::write(created_dirs[0].join("b.txt"), "b.txt")?;
+    std::fs::write(created_dirs[1].join("b.png"), "B.PNG")?;
+    std::fs::write(source.path().join("c.png"), "c.png")?;
+
+    let verified = verify_split(&splitter, &report, false).await?;
+    assert_eq!(verified.missing, vec![source.path().join("c.png")]);
+    assert_eq!(verified.duplicated.len(), 1);
+    assert_eq!(verified.size_mismatched, vec![created_dirs[0].join("a.png")]);
+    assert!(verified.hash_mismatched.is_empty());
+
+    // Only the hash comparison catches a copy with the right size
+    let verified = verify_split(&splitter, &report, true).await?;
+    assert_eq!(verified.hash_mismatched, vec![created_dirs[1].join("b.png")]);
+
+    // Links made by the symlink copy mode are followed to the files they point at
+    #[cfg(unix)]
+    {
+        let links = TempDir::new()?;
+        let config = SplitConfig::new(source.path(), 2)
+            .with_output_dir(links.path())
+            .with_copy_mode(CopyMode::Symlink(SymlinkStyle::Relative));
+        let splitter = DirectorySplitter::new(config, CaptionMatcher);
+        let report = splitter.split_with_report().await?;
+        assert!(verify_split(&splitter, &report, true).await?.is_ok());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_verify_split_same_names() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for dir in ["x", "y"] {
+        std::fs::create_dir(source.path().join(dir))?;
+        std::fs::write(source.path().join(dir).join("a.png"), format!("{dir}/a.png"))?;
+        std::fs::write(source.path().join(dir).join("a.txt"), dir)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let report = splitter.split_with_report().await?;
+
+    // Each source is matched to its own copy rather than reported as duplicated
+    assert_eq!(files_in(&report.created_dirs), ["a.png", "a.png", "a.txt", "a.txt"]);
+    assert!(verify_split(&splitter, &report, true).await?.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_resume() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    // Split in place so the output directories live inside the source directory
+    let config = SplitConfig::new(source.path(), 2).with_resume(true);
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let first = splitter.split_with_report().await?;
+    assert!(first.already_present.is_empty());
+
+    // Simulate an interrupted run by removing one copy
+    std::fs::remove_file(first.created_dirs[1].join("b.txt"))?;
+
+    let second = splitter.split_with_report().await?;
+    assert_eq!(second.already_present.len(), 3);
+    assert_eq!(
+        files_in(&second.created_dirs),
+        ["a.png", "a.txt", "b.png", "b.txt"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_resume_log_and_hash() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_resume(true);
+    let first = DirectorySplitter::new(config.clone(), CaptionMatcher).split_with_report().await?;
+    let log = output.path().join(RESUME_LOG_NAME);
+    assert_eq!(std::fs::read_to_string(&log)?, "part_0/a.png\tpart_0/a.txt\npart_1/b.png\tpart_1/b.txt\n");
+
+    // A different file of the same size passes the size check but not the hash check
+    let copy = first.created_dirs[0].join("a.png");
+    std::fs::write(&copy, "A.PNG")?;
+    let report = DirectorySplitter::new(config.clone(), CaptionMatcher).split_with_report().await?;
+    assert_eq!(report.already_present.len(), 4);
+    assert_eq!(std::fs::read_to_string(&copy)?, "A.PNG");
+
+    let hashed = config.clone().with_resume_check(ResumeCheck::Hash);
+    let report = DirectorySplitter::new(hashed, CaptionMatcher).split_with_report().await?;
+    assert_eq!(report.already_present.len(), 3);
+    assert_eq!(std::fs::read_to_string(&copy)?, "a.png");
+
+    // Groups missing from the log are copied again in full, whatever is present
+    std::fs::write(&log, "part_1/b.png\tpart_1/b.txt\n")?;
+    std::fs::write(&copy, "A.PNG")?;
+    let report = DirectorySplitter::new(config, CaptionMatcher).split_with_report().await?;
+    assert_eq!(report.already_present.len(), 2);
+    assert_eq!(std::fs::read_to_string(&copy)?, "a.png");
+    assert_eq!(std::fs::read_to_string(&log)?.lines().count(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_size_range() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    std::fs::write(source.path().join("tiny.png"), "x")?;
+    std::fs::write(source.path().join("ok.png"), "x".repeat(10))?;
+    std::fs::write(source.path().join("ok.txt"), "")?;
+    std::fs::write(source.path().join("huge.png"), "x".repeat(100))?;
+
+    let config = SplitConfig::new(source.path(), 1)
+        .with_output_dir(output.path())
+        .with_min_size(5)
+        .with_max_size(50);
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let report = splitter.split_with_report().await?;
+
+    // The empty caption is copied because accompanying files are not size-checked
+    assert_eq!(files_in(&report.created_dirs), ["ok.png", "ok.txt"]);
+    assert_eq!(
+        report.skipped_by_size,
+        vec![source.path().join("huge.png"), source.path().join("tiny.png")]
+    );
+
+    let config = SplitConfig::new(source.path(), 1).with_min_size(10).with_max_size(5);
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    assert!(splitter.split().await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_files_per_dir() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for i in 0..5 {
+        std::fs::write(source.path().join(format!("{i}.png")), "png")?;
+    }
+
+    let config = SplitConfig::new(source.path(), 0)
+        .with_output_dir(output.path())
+        .with_files_per_dir(2);
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let created_dirs = splitter.split().await?;
+    assert_eq!(created_dirs.len(), 3);
+    assert_eq!(files_in(&created_dirs).len(), 5);
+
+    // An explicit number of directories cannot be combined with files_per_dir
+    let config = SplitConfig::new(source.path(), 2).with_files_per_dir(2);
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    assert!(splitter.split().await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_max_files_per_dir() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    for i in 0..5 {
+        std::fs::write(source.path().join(format!("{i}.png")), "png")?;
+        std::fs::write(source.path().join(format!("{i}.txt")), "txt")?;
+    }
+    let count = |dir: &PathBuf| std::fs::read_dir(dir).unwrap().count();
+
+    // Within the cap, the round-robin distribution is unchanged
+    let output = TempDir::new()?;
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_max_files_per_dir(6);
+    let created_dirs = DirectorySplitter::new(config, CaptionMatcher).split().await?;
+    assert_eq!(created_dirs.iter().map(count).collect::<Vec<_>>(), [6, 4]);
+
+    // Once every directory is full the split fails, unless overflow directories are allowed
+    let output = TempDir::new()?;
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_max_files_per_dir(4);
+    assert!(DirectorySplitter::new(config.clone(), CaptionMatcher).split().await.is_err());
+    let config = config.with_overflow_dirs(true);
+    let created_dirs = DirectorySplitter::new(config, CaptionMatcher).split().await?;
+    assert_eq!(created_dirs.iter().map(count).collect::<Vec<_>>(), [4, 4, 2]);
+    assert_eq!(files_in(&created_dirs).len(), 10);
+
+    // Groups are never split, so a group larger than the cap cannot be placed
+    let config = SplitConfig::new(source.path(), 2)
+        .with_max_files_per_dir(1)
+        .with_overflow_dirs(true);
+    assert!(DirectorySplitter::new(config, CaptionMatcher).split().await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_shuffle_seed() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    for i in 0..20 {
+        std::fs::write(source.path().join(format!("{i:02}.png")), "png")?;
+        std::fs::write(source.path().join(format!("{i:02}.txt")), "caption")?;
+    }
+
+    let split_into = |output: &Path, seed: Option<u64>| {
+        let mut config = SplitConfig::new(source.path(), 2).with_output_dir(output);
+        if let Some(seed) = seed {
+            config = config.with_shuffle_seed(seed);
+        }
+        DirectorySplitter::new(config, CaptionMatcher)
+    };
+
+    let mut shards = Vec::new();
+    for seed in [Some(42), Some(42), None] {
+        let output = TempDir::new()?;
+        let created_dirs = split_into(output.path(), seed).split().await?;
+        shards.push(vec![files_in(&created_dirs[..1]), files_in(&created_dirs[1..])]);
+    }
+
+    // The same seed reproduces the same shards, and captions stay with their images
+    assert_eq!(shards[0], shards[1]);
+    assert_ne!(shards[0], shards[2]);
+    for shard in &shards[0] {
+        assert_eq!(shard.len(), 20);
+        for name in shard.iter().filter(|name| name.ends_with(".png")) {
+            assert!(shard.contains(&name.replace(".png", ".txt")));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_regex_matcher_non_utf8_names() -> anyhow::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let root = TempDir::new()?;
+    let image = root.path().join("a.png");
+    let caption = root.path().join(std::ffi::OsStr::from_bytes(b"a\xff.txt"));
+    std::fs::write(&image, "png")?;
+    std::fs::write(&caption, "x")?;
+    std::fs::write(root.path().join(std::ffi::OsStr::from_bytes(b"b\xff.json")), "x")?;
+
+    // Non-UTF-8 names are matched lossily instead of panicking
+    let matcher = RegexFileMatcher::new(|path| Ok(path.extension().is_some_and(|ext| ext == "png")))
+        .with_regex_patterns(vec![Regex::new(r"\.txt$").unwrap()]);
+    assert_eq!(matcher.find_accompanying_files(&image).await?, [caption]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_regex_matcher_search_scope() -> anyhow::Result<()> {
+    let root = TempDir::new()?;
+    for dir in ["images", "captions", "extra"] {
+        std::fs::create_dir(root.path().join(dir))?;
+    }
+    let image = root.path().join("images").join("a.png");
+    for file in ["images/a.json", "captions/a.txt", "extra/a.txt"] {
+        std::fs::write(root.path().join(file), "x")?;
+    }
+    std::fs::write(&image, "png")?;
+
+    let matcher = |scope| {
+        RegexFileMatcher::new(|path| Ok(path.extension().is_some_and(|ext| ext == "png")))
+            .with_regex_patterns(vec![Regex::new(r"a\.(txt|json)$").unwrap()])
+            .with_search_scope(scope)
+    };
+    let found = |scope| {
+        let matcher = matcher(scope);
+        let image = image.clone();
+        async move {
+            let mut found = matcher.find_accompanying_files(&image).await?;
+            found.sort();
+            anyhow::Ok(found)
+        }
+    };
+
+    let json = root.path().join("images").join("a.json");
+    let caption = root.path().join("captions").join("a.txt");
+    let extra = root.path().join("extra").join("a.txt");
+    assert_eq!(found(SearchScope::SameDir).await?, std::slice::from_ref(&json));
+    assert_eq!(found(SearchScope::SiblingDir("captions".into())).await?, [caption, json.clone()]);
+    let dirs = vec![root.path().join("extra"), root.path().join("missing")];
+    assert_eq!(found(SearchScope::Dirs(dirs)).await?, [extra, json]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fn_matcher() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "c.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let is_png = |path: &Path| Ok(path.extension().is_some_and(|ext| ext == "png"));
+    let matcher = FnMatcher::new(is_png);
+    assert!(matcher.is_match(&source.path().join("a.png")).await?);
+    assert!(matcher.find_accompanying_files(&source.path().join("a.png")).await?.is_empty());
+
+    let matcher = matcher.with_accompanying_files(|path| {
+        let caption = path.with_extension("txt");
+        Ok(if caption.exists() { vec![caption] } else { vec![] })
+    });
+    let config = SplitConfig::new(source.path(), 1).with_output_dir(output.path());
+    let created_dirs = DirectorySplitter::new(config, matcher).split().await?;
+    assert_eq!(files_in(&created_dirs), ["a.png", "a.txt", "b.png"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_with_holdout() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    for i in 0..20 {
+        std::fs::write(source.path().join(format!("{i:02}.png")), "png")?;
+        std::fs::write(source.path().join(format!("{i:02}.txt")), "caption")?;
+    }
+
+    let mut holdouts = Vec::new();
+    for holdout in [Holdout::Fraction(0.25), Holdout::Fraction(0.25), Holdout::Count(3)] {
+        let output = TempDir::new()?;
+        let config = SplitConfig::new(source.path(), 2)
+            .with_output_dir(output.path())
+            .with_shuffle_seed(42)
+            .with_holdout(holdout)
+            .with_holdout_dir_name("val");
+        let splitter = DirectorySplitter::new(config.clone(), CaptionMatcher);
+        let report = splitter.split_with_report().await?;
+
+        let holdout_dir = report.holdout_dir.clone().unwrap();
+        assert_eq!(holdout_dir, output.path().join("val"));
+        let held_out = files_in(std::slice::from_ref(&holdout_dir));
+        assert_eq!(held_out.len() + files_in(&report.created_dirs).len(), 40);
+        for name in held_out.iter().filter(|name| name.ends_with(".png")) {
+            assert!(held_out.contains(&name.replace(".png", ".txt")));
+        }
+
+        assert!(verify_split(&splitter, &report, false).await?.is_ok());
+        holdouts.push(held_out);
+    }
+
+    // The same seed holds out the same groups
+    assert_eq!(holdouts[0].len(), 10);
+    assert_eq!(holdouts[0], holdouts[1]);
+    assert_eq!(holdouts[2].len(), 6);
+
+    // The holdout is chosen with the seeded shuffle, so a seed is required
+    let config = SplitConfig::new(source.path(), 2).with_holdout(Holdout::Count(1));
+    assert!(DirectorySplitter::new(config, CaptionMatcher).split().await.is_err());
+
+    // Split into the source directory, the holdout is counted as placed, not as source
+    let config = SplitConfig::new(source.path(), 2)
+        .with_shuffle_seed(42)
+        .with_holdout(Holdout::Count(3));
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let report = splitter.split_with_report().await?;
+    assert!(verify_split(&splitter, &report, true).await?.is_ok());
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[tokio::test]
+async fn test_split_writes_shard_info() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt", "c.png"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_shuffle_seed(7)
+        .with_shard_info(true);
+    let splitter = DirectorySplitter::new(config.clone(), CaptionMatcher);
+    let report = splitter.split_with_report().await?;
+    let created_dirs = report.created_dirs.clone();
+
+    let mut total_files = 0;
+    for (index, dir) in created_dirs.iter().enumerate() {
+        let info: xio::ShardInfo = xio::read_json(&dir.join(xio::split::DEFAULT_SHARD_INFO_NAME)).await?;
+        assert_eq!(info.index, index);
+        assert_eq!(info.num_dirs, 2);
+        assert_eq!(info.shuffle_seed, Some(7));
+        assert_eq!(info.file_count, std::fs::read_dir(dir)?.count() - 1);
+        total_files += info.file_count;
+    }
+    assert_eq!(total_files, 5);
+    assert!(verify_split(&splitter, &report, false).await?.is_ok());
+
+    // Re-scanning the shards does not pick up the info files as dataset files
+    let rescan = TempDir::new()?;
+    let config = SplitConfig::new(&created_dirs[0], 1).with_output_dir(rescan.path());
+    let created = DirectorySplitter::new(config, CaptionMatcher).split().await?;
+    assert!(files_in(&created).iter().all(|name| name != xio::split::DEFAULT_SHARD_INFO_NAME));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_magic_matcher() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let png = dir.path().join("real.png");
+    let mislabeled = dir.path().join("fake.png");
+    let unlabeled = dir.path().join("noext");
+    let custom = dir.path().join("image.qoi");
+    let empty = dir.path().join("empty.png");
+    std::fs::write(&png, b"\x89PNG\r\n\x1a\nrest of the image")?;
+    std::fs::write(&mislabeled, b"\xFF\xD8\xFF\xE0 jpeg data")?;
+    std::fs::write(&unlabeled, b"\x89PNG\r\n\x1a\n")?;
+    std::fs::write(&custom, b"qoif....")?;
+    std::fs::write(&empty, b"")?;
+
+    let matcher = MagicMatcher::new(["png", "qoi"]).with_signature("qoi", 0, *b"qoif");
+    assert!(matcher.is_match(&png).await?);
+    assert!(matcher.is_match(&unlabeled).await?);
+    assert!(matcher.is_match(&custom).await?);
+    assert!(!matcher.is_match(&mislabeled).await?);
+    assert!(!matcher.is_match(&empty).await?);
+    assert_eq!(matcher.detect(&mislabeled).await?, Some("jpeg"));
+    assert_eq!(matcher.detect(&empty).await?, None);
+    assert!(matcher.is_match(&dir.path().join("missing")).await.is_err());
+    Ok(())
+}
+
+#[derive(Default)]
+struct CountingProgress {
+    total: AtomicUsize,
+    advanced: AtomicUsize,
+}
+
+impl Progress for CountingProgress {
+    fn on_start(&self, total: Option<usize>) {
+        self.total.store(total.unwrap_or_default(), Ordering::SeqCst);
+    }
+
+    fn on_advance(&self, n: usize) {
+        self.advanced.fetch_add(n, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_split_reports_progress() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let progress = Arc::new(CountingProgress::default());
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter =
+        DirectorySplitter::new(config, CaptionMatcher).with_progress(Arc::clone(&progress));
+    splitter.split().await?;
+
+    assert_eq!(progress.total.load(Ordering::SeqCst), 3);
+    assert_eq!(progress.advanced.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
+
+/// Cancels a token once the given number of files have been copied.
+struct CancelAfter {
+    token: CancellationToken,
+    remaining: AtomicUsize,
+}
+
+impl Progress for CancelAfter {
+    fn on_advance(&self, n: usize) {
+        if self.remaining.fetch_sub(n, Ordering::SeqCst) <= n {
+            self.token.cancel();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_split_cancellation() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for i in 0..5 {
+        std::fs::write(source.path().join(format!("{i}.png")), "png")?;
+        std::fs::write(source.path().join(format!("{i}.txt")), "caption")?;
+    }
+
+    let token = CancellationToken::new();
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_cancellation(token.clone());
+    let progress = CancelAfter { token, remaining: AtomicUsize::new(3) };
+    let report = DirectorySplitter::new(config, CaptionMatcher)
+        .with_progress(progress)
+        .split_with_report()
+        .await?;
+
+    // The file being copied is finished, then no further files are copied
+    assert!(report.cancelled);
+    assert_eq!(files_in(&report.created_dirs).len(), 3);
+
+    // Resuming without the token completes the split
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_resume(true);
+    let report = DirectorySplitter::new(config, CaptionMatcher).split_with_report().await?;
+    assert!(!report.cancelled);
+    assert_eq!(report.already_present.len(), 3);
+    assert_eq!(files_in(&report.created_dirs).len(), 10);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_streaming_sends_completed_dirs() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt", "c.png", "c.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter = DirectorySplitter::new(config, CaptionMatcher);
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+    let consumer = async move {
+        let mut shards = Vec::new();
+        while let Some(shard) = receiver.recv().await {
+            // Each shard must already be fully populated when it arrives
+            shards.push((shard.clone(), files_in(&[shard]).len()));
+        }
+        shards
+    };
+    let (report, shards) = tokio::join!(splitter.split_streaming(sender), consumer);
+    let report = report?;
+
+    let streamed: Vec<PathBuf> = shards.iter().map(|(dir, _)| dir.clone()).collect();
+    assert_eq!(streamed, report.created_dirs);
+    let counts: Vec<usize> = shards.iter().map(|(_, count)| *count).collect();
+    assert_eq!(counts, [4, 2]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_on_shard_complete() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt", "c.png", "c.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    let shards = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let shards_clone = Arc::clone(&shards);
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter = DirectorySplitter::new(config, CaptionMatcher).with_on_shard_complete(
+        move |shard, files| {
+            // Every listed file is already in place when the hook runs
+            assert!(files.iter().all(|file| file.parent() == Some(shard) && file.exists()));
+            shards_clone.lock().unwrap().push((shard.to_path_buf(), files.len()));
+            async { Ok(()) }
+        },
+    );
+    let created_dirs = splitter.split().await?;
+    let shards = shards.lock().unwrap().clone();
+    assert_eq!(
+        shards,
+        [(created_dirs[0].clone(), 4), (created_dirs[1].clone(), 2)]
+    );
+
+    // A failing hook aborts the split before the second shard is filled
+    let output = TempDir::new()?;
+    let config = SplitConfig::new(source.path(), 2).with_output_dir(output.path());
+    let splitter = DirectorySplitter::new(config, CaptionMatcher)
+        .with_on_shard_complete(|_, _| async { anyhow::bail!("upload failed") });
+    let error = splitter.split().await.unwrap_err();
+    assert!(format!("{error:#}").contains("upload failed"));
+    let dirs: Vec<PathBuf> = std::fs::read_dir(output.path())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(files_in(&dirs).len(), 4);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_split_symlink_mode() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt"] {
+        std::fs::write(source.path().join(name), name)?;
+    }
+
+    for style in [SymlinkStyle::Absolute, SymlinkStyle::Relative] {
+        let config = SplitConfig::new(source.path(), 2)
+            .with_output_dir(output.path())
+            .with_copy_mode(CopyMode::Symlink(style));
+        let splitter = DirectorySplitter::new(config, CaptionMatcher);
+        let created_dirs = splitter.split().await?;
+        assert_eq!(files_in(&created_dirs), ["a.png", "a.txt", "b.png", "b.txt"]);
+
+        for dir in &created_dirs {
+            for entry in std::fs::read_dir(dir)? {
+                let link = entry?.path();
+                let target = std::fs::read_link(&link)?;
+                assert_eq!(target.is_relative(), style == SymlinkStyle::Relative);
+                // Accompanying files are linked too, and every link resolves to its source
+                let original = source.path().join(link.file_name().unwrap());
+                assert_eq!(std::fs::canonicalize(&link)?, std::fs::canonicalize(&original)?);
+            }
+        }
+
+        // Cleanup removes the links but leaves the originals alone
+        splitter.cleanup(created_dirs.clone()).await?;
+        assert!(created_dirs.iter().all(|dir| !dir.exists()));
+        assert_eq!(std::fs::read_to_string(source.path().join("a.txt"))?, "a.txt");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_copy_preserving() -> anyhow::Result<()> {
+    let source = TempDir::new()?;
+    let output = TempDir::new()?;
+    let a_day_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(86_400);
+    for name in ["a.png", "a.txt", "b.png"] {
+        let path = source.path().join(name);
+        std::fs::write(&path, name)?;
+        xio::fs::touch_with_time(&path, a_day_ago).await?;
+    }
+
+    let config = SplitConfig::new(source.path(), 2)
+        .with_output_dir(output.path())
+        .with_copy_mode(CopyMode::CopyPreserving);
+    let created_dirs = DirectorySplitter::new(config, CaptionMatcher).split().await?;
+    assert_eq!(files_in(&created_dirs), ["a.png", "a.txt", "b.png"]);
+    for dir in &created_dirs {
+        for entry in std::fs::read_dir(dir)? {
+            let modified = entry?.metadata()?.modified()?;
+            let drift = modified.duration_since(a_day_ago).unwrap_or_else(|e| e.duration());
+            assert!(drift < std::time::Duration::from_secs(1));
+        }
+    }
+
+    Ok(())
+}
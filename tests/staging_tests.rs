@@ -0,0 +1,45 @@
+use tempfile::TempDir;
+use tokio::io::AsyncWriteExt;
+use xio::StagingFile;
+
+#[tokio::test]
+async fn test_staging_file_commit() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+    let output = temp_dir.path().join("report.txt");
+    std::fs::write(&output, "old")?;
+
+    let mut staging = StagingFile::for_path(&output)?;
+    let temp_path = staging.path().to_path_buf();
+    staging.write_all(b"line 1\n").await?;
+    staging.write_all(b"line 2\n").await?;
+
+    // Nothing is visible at the final path until the commit
+    assert_eq!(std::fs::read_to_string(&output)?, "old");
+    staging.commit(&output).await?;
+
+    assert_eq!(std::fs::read_to_string(&output)?, "line 1\nline 2\n");
+    assert!(!temp_path.exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_staging_file_discard() -> anyhow::Result<()> {
+    let temp_dir = TempDir::new()?;
+
+    let mut staging = StagingFile::new_in(temp_dir.path())?;
+    let temp_path = staging.path().to_path_buf();
+    staging.write_all(b"draft").await?;
+    assert!(temp_path.exists());
+    staging.discard()?;
+    assert!(!temp_path.exists());
+
+    // Dropping an uncommitted staging file cleans up as well
+    let staging = StagingFile::new_in(temp_dir.path())?;
+    let temp_path = staging.path().to_path_buf();
+    drop(staging);
+    assert!(!temp_path.exists());
+    assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 0);
+
+    Ok(())
+}
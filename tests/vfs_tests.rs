@@ -0,0 +1,112 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tempfile::TempDir;
+use xio::vfs::{FileSystem, MemFs, RealFs};
+use xio::{DirectorySplitter, FileMatcher, SplitConfig};
+
+/// Matches `.png` files and pairs them with a `.txt` caption, looking files up in a `MemFs`
+#[derive(Clone)]
+struct MemCaptionMatcher(Arc<MemFs>);
+
+#[async_trait::async_trait]
+impl FileMatcher for MemCaptionMatcher {
+    async fn is_match(&self, path: &Path) -> anyhow::Result<bool> {
+        Ok(path.extension().is_some_and(|ext| ext == "png"))
+    }
+
+    async fn find_accompanying_files(&self, path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let caption = path.with_extension("txt");
+        Ok(match self.0.metadata(&caption).await {
+            Ok(_) => vec![caption],
+            Err(_) => vec![],
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_mem_fs_operations() -> std::io::Result<()> {
+    let fs = MemFs::new();
+    let dir = Path::new("/data/sub");
+
+    assert_eq!(fs.write(&dir.join("a.txt"), b"a").await.unwrap_err().kind(), ErrorKind::NotFound);
+    fs.create_dir_all(dir).await?;
+    fs.write(&dir.join("a.txt"), b"abc").await?;
+    fs.write(Path::new("/data/./b.txt"), b"b").await?;
+
+    assert_eq!(fs.read(Path::new("/data/sub/a.txt")).await?, b"abc");
+    assert_eq!(fs.metadata(&dir.join("a.txt")).await?.len(), 3);
+    assert!(fs.metadata(dir).await?.is_dir());
+    assert!(fs.metadata(Path::new("/")).await?.is_dir());
+    assert_eq!(
+        fs.read_dir(Path::new("/data")).await?,
+        [PathBuf::from("/data/b.txt"), PathBuf::from("/data/sub")]
+    );
+    assert_eq!(fs.read(dir).await.unwrap_err().kind(), ErrorKind::IsADirectory);
+    assert_eq!(fs.read(Path::new("/missing")).await.unwrap_err().kind(), ErrorKind::NotFound);
+
+    assert_eq!(fs.copy(&dir.join("a.txt"), Path::new("/data/c.txt")).await?, 3);
+    assert_eq!(fs.read(Path::new("/data/c.txt")).await?, b"abc");
+
+    fs.remove_file(Path::new("/data/c.txt")).await?;
+    assert_eq!(fs.read(Path::new("/data/c.txt")).await.unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(fs.remove_file(dir).await.unwrap_err().kind(), ErrorKind::IsADirectory);
+
+    fs.remove_dir_all(dir).await?;
+    assert_eq!(fs.metadata(&dir.join("a.txt")).await.unwrap_err().kind(), ErrorKind::NotFound);
+    Ok(())
+}
+
+/// Builds a small tree under `root` and checks that `walk` skips the default exclusions
+async fn check_walk(fs: &dyn FileSystem, root: &Path) -> std::io::Result<()> {
+    for dir in ["sub", ".hidden", "target", ".git"] {
+        fs.create_dir_all(&root.join(dir)).await?;
+    }
+    for file in ["a.txt", "sub/b.txt", ".hidden/c.txt", "target/d.txt", ".git/e.txt", ".f.txt"] {
+        fs.write(&root.join(file), b"").await?;
+    }
+
+    let mut files = fs.walk(root).await?;
+    files.sort();
+    assert_eq!(files, [root.join("a.txt"), root.join("sub/b.txt")]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_walk_applies_default_filter() -> std::io::Result<()> {
+    check_walk(&MemFs::new(), Path::new("/root")).await?;
+
+    let temp_dir = TempDir::new()?;
+    check_walk(&RealFs, &temp_dir.path().join("root")).await
+}
+
+#[tokio::test]
+async fn test_split_in_memory() -> anyhow::Result<()> {
+    let fs = Arc::new(MemFs::new());
+    fs.create_dir_all(Path::new("/source")).await?;
+    for name in ["a.png", "a.txt", "b.png", "b.txt", "c.png", "orphan.txt"] {
+        fs.write(&Path::new("/source").join(name), name.as_bytes()).await?;
+    }
+
+    let config = SplitConfig::new("/source", 2).with_output_dir("/output");
+    let splitter = DirectorySplitter::new(config, MemCaptionMatcher(Arc::clone(&fs)))
+        .with_file_system(Arc::clone(&fs));
+    let created_dirs = splitter.split().await?;
+
+    assert_eq!(created_dirs.len(), 2);
+    let mut copied = Vec::new();
+    for dir in &created_dirs {
+        copied.extend(fs.read_dir(dir).await?);
+    }
+    let mut names: Vec<_> = copied.iter().map(|p| p.file_name().unwrap().to_owned()).collect();
+    names.sort();
+    assert_eq!(names, ["a.png", "a.txt", "b.png", "b.txt", "c.png"]);
+    assert_eq!(fs.read(&created_dirs[0].join("a.png")).await?, b"a.png");
+
+    splitter.cleanup(created_dirs.clone()).await?;
+    for dir in &created_dirs {
+        assert!(fs.metadata(dir).await.is_err());
+    }
+    assert!(std::fs::metadata("/output").is_err());
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use xio::watch::{debounced_batch, debounced_batch_with_max_wait};
+
+#[tokio::test]
+async fn test_debounced_batch_coalesces_events() -> anyhow::Result<()> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(16);
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let batches_clone = Arc::clone(&batches);
+    let collector = tokio::spawn(debounced_batch(receiver, Duration::from_millis(100), move |paths| {
+        let batches = Arc::clone(&batches_clone);
+        let paths = paths.to_vec();
+        async move {
+            batches.lock().await.push(paths);
+            Ok(())
+        }
+    }));
+
+    // A burst with repeated paths, each event arriving within the window of the previous one
+    for name in ["b.txt", "a.txt", "b.txt"] {
+        sender.send(PathBuf::from(name)).await?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    // A quiet period closes the batch, so this event starts a new one
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    sender.send(PathBuf::from("c.txt")).await?;
+    drop(sender);
+    collector.await??;
+
+    assert_eq!(
+        *batches.lock().await,
+        [
+            vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")],
+            vec![PathBuf::from("c.txt")],
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_debounced_batch_stops_on_error() {
+    let (sender, receiver) = tokio::sync::mpsc::channel(16);
+    sender.send(PathBuf::from("a.txt")).await.unwrap();
+    drop(sender);
+
+    let result = debounced_batch(receiver, Duration::from_millis(10), |_| async {
+        Err(anyhow::anyhow!("rebuild failed"))
+    })
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_debounced_batch_max_wait_flushes_steady_stream() -> anyhow::Result<()> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(64);
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let batches_clone = Arc::clone(&batches);
+    let window = Duration::from_millis(100);
+    let max_wait = Duration::from_millis(200);
+    let collector = tokio::spawn(debounced_batch_with_max_wait(receiver, window, max_wait, move |paths| {
+        let batches = Arc::clone(&batches_clone);
+        let paths = paths.to_vec();
+        async move {
+            batches.lock().await.push(paths);
+            Ok(())
+        }
+    }));
+
+    // Events arrive well within the window for much longer than max_wait
+    for i in 0..30 {
+        sender.send(PathBuf::from(format!("{i:02}.txt"))).await?;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let flushed_while_sending = batches.lock().await.len();
+    drop(sender);
+    collector.await??;
+
+    assert!(flushed_while_sending >= 2, "only {flushed_while_sending} batches flushed");
+    let batches = batches.lock().await;
+    assert_eq!(batches.iter().map(Vec::len).sum::<usize>(), 30);
+    Ok(())
+}